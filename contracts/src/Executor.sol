@@ -36,6 +36,7 @@ contract Executor is IExecutor, Ownable {
     }
 
     error UnauthorizedPool(address expected, address actual);
+    error InvalidRange(int24 tickLower, int24 tickUpper);
 
     IFundManager public fundManager;
     Whitelist public whitelist;
@@ -69,7 +70,42 @@ contract Executor is IExecutor, Ownable {
         require(whitelist.checkWhitelist(pool), "EXECUTOR: Pool not whitelisted");
 
         // Calculate metrics
-        MetricParams memory _metrics = calcMetrics(pool);
+        _execute(calcMetrics(pool));
+    }
+
+    function executeWithParams(address pool, int24 tickLower, int24 tickUpper, uint128 liquidity)
+        external
+        override
+        notExecuting
+        onlyOwner
+    {
+        require(whitelist.checkWhitelist(pool), "EXECUTOR: Pool not whitelisted");
+
+        // Range and liquidity are chosen off-chain, only check that the pool accepts them
+        IUniswapV3Pool pool_contract = IUniswapV3Pool(pool);
+        {
+            int24 spacing = pool_contract.tickSpacing();
+            require(
+                tickLower < tickUpper && tickLower % spacing == 0 && tickUpper % spacing == 0,
+                InvalidRange(tickLower, tickUpper)
+            );
+        }
+
+        // Assign field by field to keep the stack shallow
+        MetricParams memory _metrics;
+        _metrics.pool = pool;
+        _metrics.token0 = pool_contract.token0();
+        _metrics.token1 = pool_contract.token1();
+        _metrics.fee = pool_contract.fee();
+        (, _metrics.tick,,,,,) = pool_contract.slot0();
+        _metrics.tickLower = tickLower;
+        _metrics.tickUpper = tickUpper;
+        _metrics.liquidity = liquidity;
+
+        _execute(_metrics);
+    }
+
+    function _execute(MetricParams memory _metrics) internal {
         // Write to storage
         metrics = _metrics;
 
@@ -82,7 +118,9 @@ contract Executor is IExecutor, Ownable {
         fundManager.startBenchmark(address(this), tokens);
 
         // Mint liquidity to pool and create callback to ourselves
-        IUniswapV3Pool(pool).mint(address(this), metrics.tickLower, metrics.tickUpper, metrics.liquidity, "");
+        IUniswapV3Pool(_metrics.pool).mint(
+            address(this), _metrics.tickLower, _metrics.tickUpper, _metrics.liquidity, ""
+        );
     }
 
     function uniswapV3MintCallback(uint256 amount0Owed, uint256 amount1Owed, bytes calldata)
@@ -138,6 +176,29 @@ contract Executor is IExecutor, Ownable {
         return _metrics;
     }
 
+    function calcLiquidityForRange(address pool, int24 tickLower, int24 tickUpper)
+        external
+        view
+        override
+        returns (uint128)
+    {
+        IUniswapV3Pool pool_contract = IUniswapV3Pool(pool);
+
+        // Check how much we are able to add
+        uint256 amount0Max = IERC20Token(pool_contract.token0()).balanceOf(address(this));
+        uint256 amount1Max = IERC20Token(pool_contract.token1()).balanceOf(address(this));
+
+        (uint160 sqrtPriceX96,,,,,,) = pool_contract.slot0();
+
+        return LiquidityAmounts.getLiquidityForAmounts(
+            sqrtPriceX96,
+            TickMath.getSqrtRatioAtTick(tickLower),
+            TickMath.getSqrtRatioAtTick(tickUpper),
+            amount0Max,
+            amount1Max
+        );
+    }
+
     function calculateTickBounds(int24 tick, int24 spacing) internal pure returns (int24 tickLower, int24 tickUpper) {
         // Calculate lower and upper ticks
         // If tick is not on a boundary, then choose upper and lower bound
@@ -5,8 +5,11 @@ import {IUniswapV3MintCallback} from "@uniswap/v3-core/contracts/interfaces/call
 
 interface IExecutor is IUniswapV3MintCallback {
     function execute(address pool) external;
+    function executeWithParams(address pool, int24 tickLower, int24 tickUpper, uint128 liquidity) external;
     function finish() external;
     function withdraw(address[] calldata tokens) external;
 
     function setFundManager(address _fundManager) external;
+
+    function calcLiquidityForRange(address pool, int24 tickLower, int24 tickUpper) external view returns (uint128);
 }
@@ -2,6 +2,15 @@
 pragma solidity ^0.8.0;
 
 interface IUniswapV2Pair {
+    event Swap(
+        address indexed sender,
+        uint256 amount0In,
+        uint256 amount1In,
+        uint256 amount0Out,
+        uint256 amount1Out,
+        address indexed to
+    );
+
     function token0() external view returns (address);
     function token1() external view returns (address);
 }
@@ -0,0 +1,9 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+// Every command byte of `commands` is executed with the ABI encoded arguments at the same index of `inputs`
+interface IUniversalRouter {
+    function execute(bytes calldata commands, bytes[] calldata inputs, uint256 deadline) external payable;
+
+    function execute(bytes calldata commands, bytes[] calldata inputs) external payable;
+}
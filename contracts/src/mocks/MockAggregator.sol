@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+// Chainlink feed whose latest round is set by the test
+contract MockAggregator {
+    uint8 public decimals;
+
+    uint80 private roundId;
+    int256 private answer;
+    uint256 private updatedAt;
+    uint80 private answeredInRound;
+
+    constructor(uint8 _decimals) {
+        decimals = _decimals;
+    }
+
+    function setRoundData(uint80 _roundId, int256 _answer, uint256 _updatedAt, uint80 _answeredInRound) external {
+        roundId = _roundId;
+        answer = _answer;
+        updatedAt = _updatedAt;
+        answeredInRound = _answeredInRound;
+    }
+
+    function latestRoundData() external view returns (uint80, int256, uint256, uint256, uint80) {
+        return (roundId, answer, updatedAt, updatedAt, answeredInRound);
+    }
+}
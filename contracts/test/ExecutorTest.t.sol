@@ -125,4 +125,55 @@ contract ExecutorTest is Test {
         // Finish execution
         executor.finish();
     }
+
+    function testCalcLiquidityForRange() public view {
+        uint128 narrow = executor.calcLiquidityForRange(POOL_ADDR, 193670, 193680);
+        uint128 wide = executor.calcLiquidityForRange(POOL_ADDR, 193600, 193750);
+
+        assertGt(narrow, 0);
+        // The same balances spread over a wider range give less liquidity
+        assertGt(narrow, wide);
+    }
+
+    function testExecuteWithParamsInvalidRange() public {
+        // Ticks must be multiples of the tick spacing
+        vm.expectRevert(abi.encodeWithSelector(Executor.InvalidRange.selector, int24(193671), int24(193680)));
+        executor.executeWithParams(POOL_ADDR, 193671, 193680, 1000);
+
+        vm.expectRevert(abi.encodeWithSelector(Executor.InvalidRange.selector, int24(193680), int24(193670)));
+        executor.executeWithParams(POOL_ADDR, 193680, 193670, 1000);
+    }
+
+    function testExecuteWithParamsWithSwap() public {
+        int24 tickLower = 193650;
+        int24 tickUpper = 193700;
+        uint128 liquidity = executor.calcLiquidityForRange(POOL_ADDR, tickLower, tickUpper) / 2;
+
+        executor.executeWithParams(POOL_ADDR, tickLower, tickUpper, liquidity);
+
+        (,,,,, int24 lower, int24 upper, uint128 minted) = executor.metrics();
+        assertEq(lower, tickLower);
+        assertEq(upper, tickUpper);
+        assertEq(minted, liquidity);
+
+        // Swap some USDC for WETH
+        vm.startPrank(alice);
+        IERC20(USDC).approve(address(swapRouter), 1500 * (10 ** 8));
+        swapRouter.exactInputSingle(
+            ISwapRouter.ExactInputSingleParams({
+                tokenIn: USDC,
+                tokenOut: WETH,
+                fee: 500,
+                recipient: address(this),
+                deadline: block.timestamp,
+                amountIn: 1500 * (10 ** 8),
+                amountOutMinimum: 0,
+                sqrtPriceLimitX96: 0
+            })
+        );
+        vm.stopPrank();
+
+        // Finish execution
+        executor.finish();
+    }
 }
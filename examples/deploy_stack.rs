@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+use alloy::{network::EthereumWallet, providers::ProviderBuilder, transports::http::reqwest::Url};
+use eyre::Result;
+
+use jit_liquidity_rust::{
+    config::{deployconfig::DeployConfig, runconfig},
+    deploy::stack,
+};
+
+const DEFAULT_DEPLOY_CONFIG: &str = "deploy.json";
+const MANIFEST_DIR: &str = "deployments";
+
+/// Deploy the contract stack described by a deployment config.
+///
+/// Usage: `cargo run --example deploy_stack [config.json]`, after `forge build` in `contracts/`.
+#[tokio::main]
+async fn main() -> Result<()> {
+    let config = runconfig::Config::load();
+    let deploy_config_path = PathBuf::from(
+        std::env::args()
+            .nth(1)
+            .unwrap_or_else(|| DEFAULT_DEPLOY_CONFIG.to_owned()),
+    );
+    let deploy_config = DeployConfig::load(&deploy_config_path)?;
+
+    let rpc_url = config
+        .rpc_url
+        .ok_or_else(|| eyre::eyre!("RPC_URL must be set"))?;
+    let provider = ProviderBuilder::new()
+        .with_recommended_fillers()
+        .wallet(EthereumWallet::from(config.signer))
+        .on_http(Url::parse(&rpc_url)?);
+
+    let manifest = stack::deploy_stack(&provider, config.address, &deploy_config).await?;
+
+    let path = PathBuf::from(MANIFEST_DIR).join(format!("{}.json", manifest.chain_id));
+    manifest.save(&path)?;
+    println!("Wrote deployment manifest to {}", path.display());
+    println!(
+        "Set EXECUTOR_ADDRESS={} or DEPLOYMENT_MANIFEST={} to run the bot",
+        manifest.executor.address,
+        path.display()
+    );
+
+    Ok(())
+}
@@ -25,11 +25,13 @@ async fn main() -> Result<()> {
     // Constants
     let uniswap_manager = addresses::get_address("0xC36442b4a4522E871399CD717aBDD847Ab11FE88")?;
 
-    // Put in your deployed executor address here!
-    let executor = addresses::get_address("0x840EE4C41De0792Af6aD223D73De591218432D72")?;
-
     // Build a provider
     let config = runconfig::Config::load();
+
+    // Deploy one with `cargo run --example deploy_stack`
+    let executor = config
+        .executor
+        .ok_or_else(|| eyre::eyre!("EXECUTOR_ADDRESS or DEPLOYMENT_MANIFEST must be set"))?;
     let wallet = EthereumWallet::from(config.signer);
 
     let provider = ProviderBuilder::new()
@@ -68,7 +70,10 @@ async fn main() -> Result<()> {
     }
 
     let provider = Arc::new(provider);
-    let bundler = UniswapV3LiquidityBundler::new(IExecutor::new(executor, provider.clone()));
+    let bundler = UniswapV3LiquidityBundler::new(
+        IExecutor::new(executor, provider.clone()),
+        provider.default_signer_address(),
+    );
 
     let mev = FlashBotMev::new(
         provider.clone(),
@@ -76,7 +81,7 @@ async fn main() -> Result<()> {
         provider.wallet(),
         flashbot_signer,
         bundler,
-        signed,
+        vec![signed],
     );
 
     let block_number = provider.get_block_number().await?;
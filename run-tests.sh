@@ -56,6 +56,22 @@ else
     echo "  TEST_NAME: $TEST_NAME"
 fi
 
+# ---------- Build contracts ----------
+# The tests deploy the contracts from the artifacts forge writes to contracts/out
+echo "Building contracts..."
+if ! (cd contracts && forge build); then
+    echo "Contract build failed. Exiting."
+    exit 1
+fi
+
+# test_deploy_stack also deploys the mocks it configures the stack with
+for CONTRACT in Executor Whitelist FundManager Oracle MockUniswapV3Factory FakeUniswapV3Pool; do
+    if [ ! -f "contracts/out/$CONTRACT.sol/$CONTRACT.json" ]; then
+        echo "Artifact of $CONTRACT missing after forge build. Exiting."
+        exit 1
+    fi
+done
+
 # ---------- Start anvil ----------
 anvil --fork-url $URL --fork-block-number $BLOCK_NUMBER > /dev/null 2>&1 &
 ANVIL_PID=$!
@@ -0,0 +1,707 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use alloy::{
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::{Address, B256, U256},
+    providers::Provider,
+    rpc::types::Transaction,
+    signers::local::PrivateKeySigner,
+    transports::http::{Client, Http},
+};
+use eyre::Result;
+use tokio::{
+    sync::broadcast::{self, error::RecvError},
+    task::{AbortHandle, JoinSet},
+};
+
+use crate::{
+    flashbots_share::{
+        jit_bundler::{IExecutor, UniswapV3LiquidityBundler},
+        mev::FlashBotMev,
+        swap_group::SwapGroups,
+    },
+    simulation::{blockenv, statecache::StateCache, workerpool::WorkerPool},
+    utils::blockchain_utils,
+    watcher::{
+        channel::PendingReceiver,
+        pendingpool::{PendingEvent, PendingPool},
+        shutdownconfig::ShutdownConfig,
+    },
+};
+
+const BLOCK_POLL_INTERVAL: Duration = Duration::from_secs(1);
+// slot0, feeGrowthGlobal0X128, feeGrowthGlobal1X128, protocolFees and liquidity of a V3 pool
+const HOT_POOL_SLOTS: [u64; 5] = [0, 1, 2, 3, 4];
+// Number of blocks a pending swap is bundled with later swaps on the same pool
+const DEFAULT_GROUP_WINDOW: u64 = 2;
+
+pub struct JitBot<P: Provider<Http<Client>>> {
+    provider: Arc<P>,
+    flashbot_provider: Arc<P>,
+    wallet: EthereumWallet,
+    flashbot_signer: PrivateKeySigner,
+    executor: Address,
+    target_blocks: u64,
+    shutdown_config: ShutdownConfig,
+    state_cache: Arc<StateCache>,
+    hot_pools: Vec<Address>,
+    worker_pool: WorkerPool,
+    swap_groups: Mutex<SwapGroups>,
+    pending_pool: Option<Arc<PendingPool>>,
+}
+
+impl<P> JitBot<P>
+where
+    P: Provider<Http<Client>> + 'static,
+{
+    pub fn new(
+        provider: Arc<P>,
+        flashbot_provider: Arc<P>,
+        wallet: EthereumWallet,
+        flashbot_signer: PrivateKeySigner,
+        executor: Address,
+        target_blocks: u64,
+        shutdown_config: ShutdownConfig,
+    ) -> Self {
+        Self {
+            provider,
+            flashbot_provider,
+            wallet,
+            flashbot_signer,
+            executor,
+            target_blocks,
+            shutdown_config,
+            state_cache: Arc::new(StateCache::new()),
+            hot_pools: Vec::new(),
+            worker_pool: WorkerPool::default(),
+            swap_groups: Mutex::new(SwapGroups::new(DEFAULT_GROUP_WINDOW)),
+            pending_pool: None,
+        }
+    }
+
+    /// Bound the number of candidates which are simulated at once.
+    pub fn with_worker_pool(mut self, pool: WorkerPool) -> Self {
+        self.worker_pool = pool;
+        self
+    }
+
+    /// Share simulation state through `cache`, e.g. one loaded from disk.
+    pub fn with_state_cache(mut self, cache: Arc<StateCache>) -> Self {
+        self.state_cache = cache;
+        self
+    }
+
+    /// Pools whose state is fetched as soon as a new block arrives.
+    pub fn with_hot_pools(mut self, pools: Vec<Address>) -> Self {
+        self.hot_pools = pools;
+        self
+    }
+
+    /// Bundle a pending swap with the swaps on the same pool seen up to `blocks` blocks earlier.
+    pub fn with_group_window(mut self, blocks: u64) -> Self {
+        self.swap_groups = Mutex::new(SwapGroups::new(blocks));
+        self
+    }
+
+    /// Cancel the submissions of bundles whose victims are mined, replaced or dropped.
+    ///
+    /// `pending_pool` has to be the pool the mempool feeding the bot tracks transactions in.
+    pub fn with_pending_pool(mut self, pending_pool: Arc<PendingPool>) -> Self {
+        self.pending_pool = Some(pending_pool);
+        self
+    }
+
+    /// Move the state cache to a new block and preload the hot pools.
+    async fn refresh_state_cache(&self, block_number: u64) {
+        if !self.state_cache.advance(block_number) || self.hot_pools.is_empty() {
+            return;
+        }
+
+        let slots = self
+            .hot_pools
+            .iter()
+            .flat_map(|pool| HOT_POOL_SLOTS.map(|slot| (*pool, U256::from(slot))))
+            .collect::<Vec<_>>();
+
+        // A failed preload only costs latency, the simulations fetch the state themselves
+        if let Err(e) = self
+            .state_cache
+            .preload(
+                self.provider.as_ref(),
+                block_number,
+                &self.hot_pools,
+                &slots,
+            )
+            .await
+        {
+            println!("Could not preload hot pools: {:?}", e);
+        }
+    }
+
+    /// Consume pending transactions until the channel closes or shutdown is signalled.
+    ///
+    /// Every candidate is simulated, bundled and submitted in its own task so that a
+    /// slow candidate does not hold up the rest of the feed. With a pending pool, the
+    /// task of a candidate which leaves the mempool is aborted.
+    pub async fn run(self, mut recv: PendingReceiver<Transaction>) -> Result<()> {
+        let bot = Arc::new(self);
+        let mut tasks = JoinSet::new();
+        let mut in_flight: HashMap<B256, AbortHandle> = HashMap::new();
+        let mut events = bot.pending_pool.as_ref().map(|pool| pool.subscribe());
+
+        loop {
+            tokio::select! {
+                tx = recv.recv() => {
+                    let Some(tx) = tx else {
+                        break;
+                    };
+                    let bot = bot.clone();
+                    let hash = *tx.inner.tx_hash();
+                    let task = tasks.spawn(async move {
+                        if let Err(e) = bot.process(tx).await {
+                            println!("Skipping candidate {}: {:?}", hash, e);
+                        }
+                        hash
+                    });
+                    in_flight.insert(hash, task);
+                }
+                Some(event) = next_event(&mut events) => {
+                    if !event.is_final() {
+                        continue;
+                    }
+                    bot.swap_groups
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .remove(&event.hash());
+                    if let Some(task) = in_flight.remove(&event.hash()) {
+                        println!("Cancelling bundle for {}: {:?}", event.hash(), event);
+                        task.abort();
+                    }
+                }
+                // Reap candidates which have finished submitting
+                Some(joined) = tasks.join_next(), if !tasks.is_empty() => {
+                    if let Ok(hash) = joined {
+                        in_flight.remove(&hash);
+                    }
+                }
+                _ = tokio::time::sleep(Duration::from_millis(100)) => {
+                    if bot.shutdown_config.is_shutdown() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        // Abort any in-flight submissions
+        tasks.shutdown().await;
+
+        Ok(())
+    }
+
+    /// Build a JIT bundle around a candidate and the other pending swaps on its pool, and
+    /// submit it for the next `target_blocks` blocks.
+    async fn process(&self, tx: Transaction) -> Result<()> {
+        let hash = *tx.inner.tx_hash();
+        let block_number = self.provider.get_block_number().await?;
+        self.refresh_state_cache(block_number).await;
+        // The bundle lands in a block after the one the state cache is pinned to
+        let block_env =
+            blockenv::fetch_next_block_env(self.provider.as_ref(), block_number.into()).await?;
+
+        let owner = NetworkWallet::<Ethereum>::default_signer_address(&self.wallet);
+        let bundler = UniswapV3LiquidityBundler::new(
+            IExecutor::new(self.executor, self.provider.clone()),
+            owner,
+        )
+        .with_state_cache(self.state_cache.clone(), block_number)
+        .with_block_env(block_env)
+        .with_worker_pool(self.worker_pool.clone());
+
+        // Group the candidate with the other pending swaps on the first pool it swaps in
+        let pool = *bundler
+            .swapped_pools(self.provider.clone(), tx.clone().into())
+            .await?
+            .first()
+            .ok_or_else(|| eyre::eyre!("No Uniswap V3 swap found"))?;
+        let victims = {
+            let mut groups = self
+                .swap_groups
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            groups.prune(block_number);
+            groups.insert(pool, tx, block_number)
+        };
+        println!(
+            "Bundling {} in a group of {} swaps on {}",
+            hash,
+            victims.len(),
+            pool
+        );
+
+        let victim_hashes = victims
+            .iter()
+            .map(|victim| *victim.inner.tx_hash())
+            .collect::<Vec<_>>();
+        let forwarder = FlashBotMev::new(
+            self.provider.clone(),
+            self.flashbot_provider.clone(),
+            &self.wallet,
+            self.flashbot_signer.clone(),
+            bundler,
+            victims,
+        )
+        .build_forwarder(block_number + 1)
+        .await?;
+
+        for target in block_number + 1..=block_number + self.target_blocks {
+            // A bundle whose victim left the mempool can no longer land
+            if let Some(pool) = &self.pending_pool {
+                if let Some(gone) = victim_hashes.iter().find(|hash| !pool.contains(hash)) {
+                    return Err(eyre::eyre!("Victim {} is no longer pending", gone));
+                }
+            }
+
+            let response = forwarder
+                .send_bundle_at(target)
+                .await
+                .map_err(|e| eyre::eyre!(e))?;
+            println!(
+                "Submitted bundle for {} at block {}: {:?}",
+                hash, target, response
+            );
+
+            // Wait for the target block to be mined before submitting for the next one
+            blockchain_utils::wait_for_block(self.provider.as_ref(), target, BLOCK_POLL_INTERVAL)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Wait for the next event of the pending pool, if the bot has one.
+async fn next_event(
+    events: &mut Option<broadcast::Receiver<PendingEvent>>,
+) -> Option<PendingEvent> {
+    let events = events.as_mut()?;
+    loop {
+        match events.recv().await {
+            Ok(event) => return Some(event),
+            Err(RecvError::Lagged(missed)) => {
+                println!("Missed {} pending pool events", missed)
+            }
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        consensus::{Transaction as _, TxEnvelope},
+        eips::{eip2718::Decodable2718, BlockNumberOrTag},
+        network::TransactionBuilder,
+        primitives::{
+            aliases::{U24, U80},
+            Bytes, I256, U64,
+        },
+        providers::ProviderBuilder,
+        rpc::types::{BlockTransactionsKind, TransactionRequest},
+        sol,
+        sol_types::{SolCall, SolValue},
+        transports::http::reqwest::Url,
+    };
+    use serde_json::Value;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+        sync::mpsc,
+    };
+
+    use crate::{
+        config::{
+            deployconfig::{DeployConfig, FeedConfig},
+            testconfig,
+        },
+        deploy::stack,
+        interfaces::{
+            artifact::Artifact,
+            erc20,
+            executor::Executor,
+            swaprouter::ISwapRouter::{self, ExactInputSingleParams},
+        },
+        utils::{addresses, testtx},
+        watcher::channel::{self, DropPolicy, DEFAULT_CAPACITY},
+    };
+
+    use super::*;
+
+    sol! {
+        #[sol(rpc)]
+        interface IMockAggregator {
+            function setRoundData(uint80 _roundId, int256 _answer, uint256 _updatedAt, uint80 _answeredInRound) external;
+        }
+    }
+
+    fn ether(amount: u64) -> U256 {
+        U256::from(amount) * U256::from(10).pow(U256::from(18))
+    }
+
+    /// Swap `amount` ETH for USDC in the USDC/WETH 0.05% pool.
+    fn sell_eth(recipient: Address, amount: U256) -> ExactInputSingleParams {
+        ExactInputSingleParams {
+            tokenIn: *addresses::WETH,
+            tokenOut: *addresses::USDC_ADDR,
+            fee: U24::from(500u32),
+            recipient,
+            deadline: U256::MAX,
+            amountIn: amount,
+            amountOutMinimum: U256::ZERO,
+            sqrtPriceLimitX96: Default::default(),
+        }
+    }
+
+    /// Answer JSON-RPC requests like a relay accepting every bundle.
+    ///
+    /// # Returns
+    ///
+    /// * `(Url, mpsc::UnboundedReceiver<Value>)` - The relay's endpoint and the requests it received.
+    async fn mock_relay() -> (Url, mpsc::UnboundedReceiver<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let (sender, recv) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+
+                    // Headers end with an empty line
+                    let mut length = 0;
+                    let mut line = String::new();
+                    loop {
+                        line.clear();
+                        if stream.read_line(&mut line).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+                        let header = line.trim();
+                        if header.is_empty() {
+                            break;
+                        }
+                        if let Some((name, value)) = header.split_once(':') {
+                            if name.eq_ignore_ascii_case("content-length") {
+                                length = value.trim().parse().unwrap();
+                            }
+                        }
+                    }
+
+                    let mut body = vec![0; length];
+                    stream.read_exact(&mut body).await.unwrap();
+                    let Ok(request) = serde_json::from_slice::<Value>(&body) else {
+                        return;
+                    };
+
+                    let response = serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "result": {
+                            "success": true,
+                            "stateBlock": "0x0",
+                            "mevGasPrice": "0x0",
+                            "profit": "0x0",
+                            "refundableValue": "0x0",
+                            "gasUsed": "0x0",
+                            "logs": null
+                        }
+                    })
+                    .to_string();
+                    let _ = sender.send(request);
+
+                    stream
+                        .get_mut()
+                        .write_all(
+                            format!(
+                                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                                response.len(),
+                                response
+                            )
+                            .as_bytes(),
+                        )
+                        .await
+                        .unwrap();
+                });
+            }
+        });
+
+        (url, recv)
+    }
+
+    #[tokio::test]
+    async fn test_jitbot_shutdown() {
+        let config = testconfig::TestConfig::load();
+        let wallet = EthereumWallet::from(config.priv_key.clone());
+
+        let provider = Arc::new(
+            ProviderBuilder::new()
+                .with_recommended_fillers()
+                .wallet(wallet.clone())
+                .on_http(Url::parse(&config.anvil_endpoint).unwrap()),
+        );
+
+        let executor =
+            addresses::get_address("0x840EE4C41De0792Af6aD223D73De591218432D72").unwrap();
+        let shutdown = ShutdownConfig::default();
+
+        let bot = JitBot::new(
+            provider.clone(),
+            provider,
+            wallet,
+            config.priv_key,
+            executor,
+            1,
+            shutdown.clone(),
+        );
+
+        let (_sender, recv) =
+            channel::channel::<Transaction>(DEFAULT_CAPACITY, DropPolicy::default());
+        let handle = tokio::spawn(bot.run(recv));
+
+        // Allow the bot to start
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!handle.is_finished(), "Bot should still be running");
+
+        shutdown.shutdown();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        assert!(handle.is_finished(), "Bot should have stopped");
+        assert!(handle.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_jitbot_channel_closed() {
+        let config = testconfig::TestConfig::load();
+        let wallet = EthereumWallet::from(config.priv_key.clone());
+
+        let provider = Arc::new(
+            ProviderBuilder::new()
+                .with_recommended_fillers()
+                .wallet(wallet.clone())
+                .on_http(Url::parse(&config.anvil_endpoint).unwrap()),
+        );
+
+        let executor =
+            addresses::get_address("0x840EE4C41De0792Af6aD223D73De591218432D72").unwrap();
+
+        let bot = JitBot::new(
+            provider.clone(),
+            provider,
+            wallet,
+            config.priv_key,
+            executor,
+            1,
+            ShutdownConfig::default(),
+        );
+
+        // Dropping the sender closes the feed, which should stop the bot
+        let (sender, recv) =
+            channel::channel::<Transaction>(DEFAULT_CAPACITY, DropPolicy::default());
+        drop(sender);
+
+        assert!(bot.run(recv).await.is_ok());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_jitbot_submits_bundle() {
+        let config = testconfig::TestConfig::load();
+        let endpoint = Url::parse(&config.anvil_endpoint).unwrap();
+        let funder = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(EthereumWallet::from(config.priv_key))
+            .on_http(endpoint.clone());
+
+        let owner = PrivateKeySigner::random();
+        let victim = PrivateKeySigner::random();
+        for (address, amount) in [(owner.address(), 200), (victim.address(), 50)] {
+            funder
+                .send_transaction(
+                    TransactionRequest::default()
+                        .with_to(address)
+                        .with_value(ether(amount)),
+                )
+                .await
+                .unwrap()
+                .get_receipt()
+                .await
+                .unwrap();
+        }
+
+        let wallet = EthereumWallet::from(owner.clone());
+        let provider = Arc::new(
+            ProviderBuilder::new()
+                .with_recommended_fillers()
+                .wallet(wallet.clone())
+                .on_http(endpoint),
+        );
+        let (relay_url, mut requests) = mock_relay().await;
+        let relay = Arc::new(
+            ProviderBuilder::new()
+                .with_recommended_fillers()
+                .wallet(wallet.clone())
+                .on_http(relay_url),
+        );
+
+        // Valuing WETH far above the pool price makes the WETH the position buys from
+        // the victim outweigh the gas, whatever the base fee of the forked block
+        let now = provider
+            .get_block_by_number(BlockNumberOrTag::Latest, BlockTransactionsKind::Hashes)
+            .await
+            .unwrap()
+            .unwrap()
+            .header
+            .timestamp;
+        let mut feeds = Vec::new();
+        for (token, price) in [(*addresses::WETH, 1_000_000), (*addresses::USDC_ADDR, 1)] {
+            let feed = Artifact::load("MockAggregator")
+                .unwrap()
+                .deploy(provider.as_ref(), &U256::from(8).abi_encode())
+                .await
+                .unwrap();
+            IMockAggregator::new(feed, provider.as_ref())
+                .setRoundData(
+                    U80::from(1),
+                    I256::try_from(price * 100_000_000i64).unwrap(),
+                    U256::from(now),
+                    U80::from(1),
+                )
+                .send()
+                .await
+                .unwrap()
+                .watch()
+                .await
+                .unwrap();
+            feeds.push(FeedConfig { token, feed });
+        }
+
+        let pool = addresses::get_address("0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640").unwrap();
+        let manifest = stack::deploy_stack(
+            provider.as_ref(),
+            owner.address(),
+            &DeployConfig {
+                pools: vec![pool],
+                feeds,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let executor = manifest.executor.address;
+
+        // The executor mints with both tokens of the pool
+        let router = ISwapRouter::new(*addresses::UNISWAP_V3_ROUTER, provider.as_ref());
+        router
+            .exactInputSingle(sell_eth(executor, ether(100)))
+            .value(ether(100))
+            .send()
+            .await
+            .unwrap()
+            .watch()
+            .await
+            .unwrap();
+        Executor::new(
+            provider.as_ref(),
+            TransactionRequest::default()
+                .with_to(*addresses::WETH)
+                .with_value(ether(50)),
+        )
+        .send()
+        .await
+        .unwrap();
+        Executor::new(
+            provider.as_ref(),
+            erc20::transfer(provider.as_ref(), *addresses::WETH, executor, ether(50)),
+        )
+        .send()
+        .await
+        .unwrap();
+
+        // The victim sells ETH in the whitelisted pool, signed but never sent to Anvil
+        let fees = provider.estimate_eip1559_fees(None).await.unwrap();
+        let swap = TransactionRequest::default()
+            .with_to(*addresses::UNISWAP_V3_ROUTER)
+            .with_input(
+                ISwapRouter::exactInputSingleCall {
+                    params: sell_eth(victim.address(), ether(20)),
+                }
+                .abi_encode(),
+            )
+            .with_value(ether(20))
+            .with_nonce(0)
+            .with_chain_id(provider.get_chain_id().await.unwrap())
+            .with_gas_limit(300_000)
+            .with_max_fee_per_gas(fees.max_fee_per_gas)
+            .with_max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
+        let pending = testtx::sign_pending(&victim, swap).await;
+
+        let shutdown = ShutdownConfig::default();
+        let bot = JitBot::new(
+            provider.clone(),
+            relay,
+            wallet,
+            PrivateKeySigner::random(),
+            executor,
+            1,
+            shutdown.clone(),
+        );
+
+        let (sender, recv) =
+            channel::channel::<Transaction>(DEFAULT_CAPACITY, DropPolicy::default());
+        let handle = tokio::spawn(bot.run(recv));
+        // Nothing is mined while the bot works, so the bundle targets the block after this
+        let block_number = provider.get_block_number().await.unwrap();
+        sender.send(pending.clone()).await.unwrap();
+
+        let request = tokio::time::timeout(Duration::from_secs(120), requests.recv())
+            .await
+            .expect("No bundle was submitted")
+            .unwrap();
+        assert_eq!(request["method"], "mev_sendBundle");
+        let bundle = &request["params"][0];
+        let target: U64 = serde_json::from_value(bundle["inclusion"]["block"].clone()).unwrap();
+        assert_eq!(target.to::<u64>(), block_number + 1);
+
+        // The victim sits between our frontrun and backrun
+        let body = bundle["body"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| {
+                let tx: Bytes = serde_json::from_value(item["tx"].clone()).unwrap();
+                TxEnvelope::decode_2718(&mut tx.as_ref()).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(body.len(), 3);
+        assert_eq!(body[1].tx_hash(), pending.inner.tx_hash());
+
+        // The owner mints the position and then burns it, with consecutive nonces
+        let (frontrun, backrun) = (&body[0], &body[2]);
+        assert_eq!(frontrun.recover_signer().unwrap(), owner.address());
+        assert_eq!(backrun.recover_signer().unwrap(), owner.address());
+        assert_eq!(backrun.nonce(), frontrun.nonce() + 1);
+        assert_eq!(frontrun.to(), Some(executor));
+        assert_eq!(backrun.to(), Some(executor));
+        assert!(frontrun
+            .input()
+            .starts_with(&IExecutor::executeWithParamsCall::SELECTOR));
+        assert!(backrun
+            .input()
+            .starts_with(&IExecutor::finishCall::SELECTOR));
+
+        shutdown.shutdown();
+        assert!(handle.await.unwrap().is_ok());
+    }
+}
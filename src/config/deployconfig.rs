@@ -0,0 +1,85 @@
+use std::{fs, path::Path};
+
+use alloy::primitives::Address;
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::addresses;
+
+/// A Chainlink USD feed the oracle prices a token with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedConfig {
+    /// The token, the zero address stands for ETH
+    pub token: Address,
+    pub feed: Address,
+}
+
+/// Settings of a contract stack deployment, see `deploy::stack::deploy_stack`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeployConfig {
+    /// Owner of the executor, whitelist and oracle, the deployer if not set
+    #[serde(default)]
+    pub owner: Option<Address>,
+    /// Factory whose pools the whitelist accepts
+    #[serde(default = "default_factory")]
+    pub uniswap_factory: Address,
+    /// Pools to whitelist
+    #[serde(default)]
+    pub pools: Vec<Address>,
+    #[serde(default)]
+    pub feeds: Vec<FeedConfig>,
+}
+
+fn default_factory() -> Address {
+    *addresses::UNISWAP_V3_FACTORY
+}
+
+impl Default for DeployConfig {
+    fn default() -> Self {
+        Self {
+            owner: None,
+            uniswap_factory: default_factory(),
+            pools: Vec::new(),
+            feeds: Vec::new(),
+        }
+    }
+}
+
+impl DeployConfig {
+    /// Load a deployment config from a JSON file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| eyre::eyre!("Could not read {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config: DeployConfig = serde_json::from_str(
+            r#"{
+                "pools": ["0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640"],
+                "feeds": [{
+                    "token": "0x0000000000000000000000000000000000000000",
+                    "feed": "0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419"
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.owner, None);
+        assert_eq!(config.uniswap_factory, *addresses::UNISWAP_V3_FACTORY);
+        assert_eq!(
+            config.pools,
+            vec![addresses::get_address("0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640").unwrap()]
+        );
+        assert_eq!(config.feeds[0].token, Address::ZERO);
+
+        let empty: DeployConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(empty, DeployConfig::default());
+    }
+}
@@ -1,3 +1,4 @@
+pub mod deployconfig;
 pub mod runconfig;
 
 pub mod testconfig;
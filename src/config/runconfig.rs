@@ -1,10 +1,25 @@
-use std::str::FromStr;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use alloy::{primitives::Address, signers::local::PrivateKeySigner};
 
+use crate::deploy::manifest::DeploymentManifest;
+
+const DEFAULT_RELAY_URL: &str = "https://relay.flashbots.net";
+const DEFAULT_TARGET_BLOCKS: u64 = 3;
+
 pub struct Config {
     pub rpc_url: Option<String>,
     pub rpc_url_ws: Option<String>,
+    pub ws_alchemy: bool,
+    pub relay_url: String,
+    pub executor: Option<Address>,
+    pub target_blocks: u64,
+    pub hot_pools: Vec<Address>,
+    pub state_cache_path: Option<PathBuf>,
+    pub simulation_workers: Option<usize>,
     pub signer: PrivateKeySigner,
     pub address: Address,
     pub flashbot_signer: Option<PrivateKeySigner>,
@@ -25,11 +40,45 @@ impl Config {
             PrivateKeySigner::from_str(&key).expect("Could not parse flashbot private key")
         });
 
+        // Address of our deployed executor contract, or the one of a deployment manifest
+        let executor = std::env::var("EXECUTOR_ADDRESS")
+            .ok()
+            .map(|addr| Address::from_str(&addr).expect("Could not parse executor address"))
+            .or_else(|| {
+                std::env::var("DEPLOYMENT_MANIFEST").ok().map(|path| {
+                    DeploymentManifest::load(Path::new(&path))
+                        .expect("Could not load deployment manifest")
+                        .executor
+                        .address
+                })
+            });
+
+        // Pools whose state is preloaded every block, comma separated
+        let hot_pools = std::env::var("HOT_POOLS").map_or(Vec::new(), |pools| {
+            pools
+                .split(',')
+                .filter(|pool| !pool.trim().is_empty())
+                .map(|pool| Address::from_str(pool.trim()).expect("Could not parse hot pool"))
+                .collect()
+        });
+
         let addr = key.address();
 
         Self {
             rpc_url: std::env::var("RPC_URL").ok(),
             rpc_url_ws: std::env::var("RPC_WS_URL").ok(),
+            ws_alchemy: std::env::var("RPC_WS_ALCHEMY")
+                .map_or(true, |v| v.parse().expect("Could not parse RPC_WS_ALCHEMY")),
+            relay_url: std::env::var("RELAY_URL").map_or(DEFAULT_RELAY_URL.to_owned(), |v| v),
+            executor,
+            target_blocks: std::env::var("TARGET_BLOCKS").map_or(DEFAULT_TARGET_BLOCKS, |v| {
+                v.parse().expect("Could not parse TARGET_BLOCKS")
+            }),
+            hot_pools,
+            state_cache_path: std::env::var("STATE_CACHE_PATH").ok().map(PathBuf::from),
+            simulation_workers: std::env::var("SIMULATION_WORKERS")
+                .ok()
+                .map(|v| v.parse().expect("Could not parse SIMULATION_WORKERS")),
             signer: key,
             address: addr,
             flashbot_signer: flashbot_key,
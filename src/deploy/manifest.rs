@@ -0,0 +1,85 @@
+use std::{fs, path::Path};
+
+use alloy::primitives::{Address, B256};
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::interfaces::artifact::Deployment;
+
+/// A transaction which configured a deployed contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigTx {
+    /// What the transaction did, e.g. `addWhitelist(0x88e6..)`
+    pub action: String,
+    pub tx_hash: B256,
+}
+
+/// Record of a deployed contract stack, written by `deploy_stack`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeploymentManifest {
+    pub chain_id: u64,
+    /// Block the first contract was deployed in
+    pub block: u64,
+    pub deployer: Address,
+    /// Owner of the executor, whitelist and oracle
+    pub owner: Address,
+    pub whitelist: Deployment,
+    pub oracle: Deployment,
+    pub fund_manager: Deployment,
+    pub executor: Deployment,
+    /// Configuration transactions, in the order they were sent
+    pub transactions: Vec<ConfigTx>,
+}
+
+impl DeploymentManifest {
+    /// Load a manifest from a JSON file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| eyre::eyre!("Could not read {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Write the manifest to a JSON file, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_load() {
+        let deployment = |byte| Deployment {
+            address: Address::repeat_byte(byte),
+            tx_hash: B256::repeat_byte(byte),
+            block: 100,
+        };
+        let manifest = DeploymentManifest {
+            chain_id: 31337,
+            block: 100,
+            deployer: Address::repeat_byte(0xaa),
+            owner: Address::repeat_byte(0xbb),
+            whitelist: deployment(0x01),
+            oracle: deployment(0x02),
+            fund_manager: deployment(0x03),
+            executor: deployment(0x04),
+            transactions: vec![ConfigTx {
+                action: "setOracle".to_owned(),
+                tx_hash: B256::repeat_byte(0x05),
+            }],
+        };
+
+        let path = std::env::temp_dir()
+            .join("manifest_test_save_load")
+            .join("31337.json");
+        manifest.save(&path).unwrap();
+
+        assert_eq!(DeploymentManifest::load(&path).unwrap(), manifest);
+    }
+}
@@ -0,0 +1,241 @@
+use alloy::{
+    network::Network,
+    primitives::{Address, B256},
+    providers::Provider,
+    sol,
+    sol_types::SolValue,
+    transports::Transport,
+};
+use eyre::Result;
+
+use crate::{
+    config::deployconfig::DeployConfig,
+    interfaces::{artifact::Artifact, executor::Executor, fundmanager, oracle, whitelist},
+};
+
+use super::manifest::{ConfigTx, DeploymentManifest};
+
+sol! {
+    #[sol(rpc)]
+    interface IOwnable {
+        function owner() external view returns (address);
+        function transferOwnership(address newOwner) external;
+    }
+}
+
+/// Hand `contract` over to `new_owner`.
+pub async fn transfer_ownership<P, T, N>(
+    provider: &P,
+    contract: Address,
+    new_owner: Address,
+) -> Result<B256>
+where
+    P: Provider<T, N>,
+    T: Transport + Clone,
+    N: Network,
+{
+    let tx = IOwnable::new(contract, provider)
+        .transferOwnership(new_owner)
+        .into_transaction_request();
+
+    Executor::new(provider, tx).send().await
+}
+
+/// Deploy and wire up the `Whitelist`, `Oracle`, `FundManager` and `Executor` contracts.
+///
+/// The deployer seeds the oracle's feeds and the whitelisted pools, then hands the
+/// fund manager to the executor, which is the only caller of its benchmarks, and
+/// the whitelist and oracle to the configured owner. The executor is created with
+/// the fund manager and whitelist, so no `setFundManager` call is needed.
+///
+/// # Arguments
+///
+/// * `provider` - A provider with the deployer's wallet.
+/// * `deployer` - The address of the deployer's wallet.
+/// * `config` - The owner, pools and feeds to set up.
+///
+/// # Returns
+///
+/// * `Result<DeploymentManifest>` - The deployed addresses and every transaction sent.
+pub async fn deploy_stack<P, T, N>(
+    provider: &P,
+    deployer: Address,
+    config: &DeployConfig,
+) -> Result<DeploymentManifest>
+where
+    P: Provider<T, N>,
+    T: Transport + Clone,
+    N: Network,
+{
+    let chain_id = provider.get_chain_id().await?;
+    let owner = config.owner.unwrap_or(deployer);
+
+    // The deployer owns everything until the contracts are configured
+    let whitelist = whitelist::deploy(provider, deployer, config.uniswap_factory).await?;
+    let oracle = oracle::deploy(provider).await?;
+    let fund_manager = fundmanager::deploy(provider, deployer).await?;
+    let executor = Artifact::load("Executor")?
+        .deploy_tracked(
+            provider,
+            &(owner, fund_manager.address, whitelist.address).abi_encode_params(),
+        )
+        .await?;
+    println!(
+        "Deployed executor at {}, fund manager at {}, whitelist at {} and oracle at {}",
+        executor.address, fund_manager.address, whitelist.address, oracle.address
+    );
+
+    let mut transactions = Vec::new();
+    let mut record = |action: String, tx_hash: B256| {
+        println!("{}: {}", action, tx_hash);
+        transactions.push(ConfigTx { action, tx_hash });
+    };
+
+    for feed in &config.feeds {
+        let tx_hash = oracle::set_feed(provider, oracle.address, feed.token, feed.feed).await?;
+        record(format!("setFeed({}, {})", feed.token, feed.feed), tx_hash);
+    }
+
+    for pool in &config.pools {
+        let tx_hash = whitelist::add_whitelist(provider, whitelist.address, *pool).await?;
+        record(format!("addWhitelist({})", pool), tx_hash);
+    }
+
+    let tx_hash = fundmanager::set_oracle(provider, fund_manager.address, oracle.address).await?;
+    record(format!("setOracle({})", oracle.address), tx_hash);
+
+    let tx_hash = transfer_ownership(provider, fund_manager.address, executor.address).await?;
+    record(
+        format!(
+            "transferOwnership({}) of the fund manager",
+            executor.address
+        ),
+        tx_hash,
+    );
+
+    if owner != deployer {
+        for (name, contract) in [("whitelist", whitelist.address), ("oracle", oracle.address)] {
+            let tx_hash = transfer_ownership(provider, contract, owner).await?;
+            record(
+                format!("transferOwnership({}) of the {}", owner, name),
+                tx_hash,
+            );
+        }
+    }
+
+    Ok(DeploymentManifest {
+        chain_id,
+        block: whitelist.block,
+        deployer,
+        owner,
+        whitelist,
+        oracle,
+        fund_manager,
+        executor,
+        transactions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{aliases::U24, U256};
+
+    use crate::{config::deployconfig::FeedConfig, utils::setup};
+
+    use super::*;
+
+    sol! {
+        #[sol(rpc)]
+        interface IMockUniswapV3Factory {
+            function setPool(address token0, address token1, uint24 fee, address pool) external;
+        }
+    }
+
+    async fn owner_of<P: Provider<T>, T: Transport + Clone>(
+        provider: &P,
+        contract: Address,
+    ) -> Address {
+        IOwnable::new(contract, provider)
+            .owner()
+            .call()
+            .await
+            .unwrap()
+            ._0
+    }
+
+    #[tokio::test]
+    async fn test_deploy_stack() {
+        let provider = setup::setup_provider_with_anvil(None).await.unwrap();
+        let accounts = provider.get_accounts().await.unwrap();
+        let (deployer, owner) = (accounts[0], accounts[1]);
+
+        // A pool the mock factory vouches for, so that it can be whitelisted
+        let (token0, token1, fee) = (
+            Address::repeat_byte(0x0a),
+            Address::repeat_byte(0x0b),
+            500u32,
+        );
+        let factory = Artifact::load("MockUniswapV3Factory")
+            .unwrap()
+            .deploy(&provider, &[])
+            .await
+            .unwrap();
+        let pool = Artifact::load("FakeUniswapV3Pool")
+            .unwrap()
+            .deploy(
+                &provider,
+                &(token0, token1, U256::from(fee)).abi_encode_params(),
+            )
+            .await
+            .unwrap();
+        IMockUniswapV3Factory::new(factory, &provider)
+            .setPool(token0, token1, U24::from(fee), pool)
+            .send()
+            .await
+            .unwrap()
+            .watch()
+            .await
+            .unwrap();
+
+        let config = DeployConfig {
+            owner: Some(owner),
+            uniswap_factory: factory,
+            pools: vec![pool],
+            feeds: vec![FeedConfig {
+                token: Address::ZERO,
+                feed: Address::repeat_byte(0x0f),
+            }],
+        };
+        let manifest = deploy_stack(&provider, deployer, &config).await.unwrap();
+
+        assert_eq!(manifest.chain_id, 31337);
+        assert_eq!(manifest.owner, owner);
+        assert!(manifest.block <= manifest.executor.block);
+        // setFeed, addWhitelist, setOracle and three ownership transfers
+        assert_eq!(manifest.transactions.len(), 6);
+
+        assert!(
+            whitelist::check_whitelist(&provider, manifest.whitelist.address, pool)
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            fundmanager::oracle(&provider, manifest.fund_manager.address)
+                .await
+                .unwrap(),
+            manifest.oracle.address
+        );
+
+        assert_eq!(
+            owner_of(&provider, manifest.fund_manager.address).await,
+            manifest.executor.address
+        );
+        for contract in [
+            manifest.executor.address,
+            manifest.whitelist.address,
+            manifest.oracle.address,
+        ] {
+            assert_eq!(owner_of(&provider, contract).await, owner);
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod protocol;
+pub mod registry;
+pub mod swap;
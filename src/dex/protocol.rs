@@ -0,0 +1,269 @@
+use alloy::{
+    primitives::{address, b256, keccak256, Address, B256, U256},
+    sol,
+    sol_types::SolEvent,
+};
+
+use crate::{
+    interfaces::{router02::IUniswapV2Pair, uniswapv3pool::IUniswapV3Pool},
+    uniswap_v3::pool::PoolKey,
+};
+
+sol! {
+    // PancakeSwap V3 adds the protocol fees to the Uniswap V3 event, which changes its signature
+    #[sol(rpc)]
+    interface IPancakeV3Pool {
+        event Swap(
+            address indexed sender,
+            address indexed recipient,
+            int256 amount0,
+            int256 amount1,
+            uint160 sqrtPriceX96,
+            uint128 liquidity,
+            int24 tick,
+            uint128 protocolFeesToken0,
+            uint128 protocolFeesToken1
+        );
+    }
+}
+
+/// Fee of every V2 pair in hundredths of a bip, so V2 keys compare with V3 ones.
+pub const V2_FEE: u32 = 3000;
+
+/// Layout of the `Swap` event a protocol's pools emit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SwapEvent {
+    /// `Swap(address,uint256,uint256,uint256,uint256,address)`
+    UniswapV2,
+    /// `Swap(address,address,int256,int256,uint160,uint128,int24)`
+    UniswapV3,
+    /// The Uniswap V3 event followed by the protocol fees of both tokens
+    PancakeV3,
+}
+
+impl SwapEvent {
+    pub fn topic(&self) -> B256 {
+        match self {
+            SwapEvent::UniswapV2 => IUniswapV2Pair::Swap::SIGNATURE_HASH,
+            SwapEvent::UniswapV3 => IUniswapV3Pool::Swap::SIGNATURE_HASH,
+            SwapEvent::PancakeV3 => IPancakeV3Pool::Swap::SIGNATURE_HASH,
+        }
+    }
+
+    /// Whether the pools are concentrated liquidity pools keyed by tokens and fee.
+    pub fn is_concentrated(&self) -> bool {
+        !matches!(self, SwapEvent::UniswapV2)
+    }
+}
+
+/// How we can provide liquidity around a swap in a protocol's pools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiquidityStrategy {
+    /// Mint a concentrated position around the swap, see `IExecutor::executeWithParams`
+    ConcentratedJit,
+    /// Swaps are detected, but the executor cannot provide liquidity to the pools yet
+    DetectOnly,
+}
+
+/// A DEX whose pools are deployed with CREATE2, so a pool can be proven genuine
+/// from its address alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DexProtocol {
+    pub name: &'static str,
+    pub event: SwapEvent,
+    /// Contract which deploys the pools, the factory unless it delegates deployment
+    pub deployer: Address,
+    pub init_code_hash: B256,
+    pub strategy: LiquidityStrategy,
+}
+
+impl DexProtocol {
+    pub fn uniswap_v2() -> Self {
+        Self {
+            name: "Uniswap V2",
+            event: SwapEvent::UniswapV2,
+            deployer: address!("5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f"),
+            init_code_hash: b256!(
+                "96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f"
+            ),
+            strategy: LiquidityStrategy::DetectOnly,
+        }
+    }
+
+    pub fn sushiswap_v2() -> Self {
+        Self {
+            name: "SushiSwap V2",
+            event: SwapEvent::UniswapV2,
+            deployer: address!("C0AEe478e3658e2610c5F7A4A2E1777cE9e4f2Ac"),
+            init_code_hash: b256!(
+                "e18a34eb0e04b04f7a0ac29a6e80748dca96319b42c520b4f4d8fb8d0ae68f0d"
+            ),
+            strategy: LiquidityStrategy::DetectOnly,
+        }
+    }
+
+    pub fn uniswap_v3() -> Self {
+        Self {
+            name: "Uniswap V3",
+            event: SwapEvent::UniswapV3,
+            deployer: address!("1F98431c8aD98523631AE4a59f267346ea31F984"),
+            init_code_hash: b256!(
+                "e34f199b19b2b4f47f68442619d555527d244f78a3297ea89325f843f87b8b54"
+            ),
+            strategy: LiquidityStrategy::ConcentratedJit,
+        }
+    }
+
+    /// Sushi deployed the unmodified Uniswap V3 contracts, so the executor's
+    /// `uniswapV3MintCallback` works with its pools too.
+    pub fn sushiswap_v3() -> Self {
+        Self {
+            name: "SushiSwap V3",
+            event: SwapEvent::UniswapV3,
+            deployer: address!("bACEB8eC6b9355Dfc0269C18bac9d6E2Bdc29C4F"),
+            init_code_hash: b256!(
+                "e34f199b19b2b4f47f68442619d555527d244f78a3297ea89325f843f87b8b54"
+            ),
+            strategy: LiquidityStrategy::ConcentratedJit,
+        }
+    }
+
+    /// PancakeSwap V3 pools call `pancakeV3MintCallback`, which the executor does not implement.
+    pub fn pancakeswap_v3() -> Self {
+        Self {
+            name: "PancakeSwap V3",
+            event: SwapEvent::PancakeV3,
+            deployer: address!("41ff9AA7e16B8B1a8a8dc4f0eFacd93D02d071c9"),
+            init_code_hash: b256!(
+                "6ce8eb472fa82df5469c6ab6d485f17c3ad13c8cd7af59b3d4a8026c5ce0f7e2"
+            ),
+            strategy: LiquidityStrategy::DetectOnly,
+        }
+    }
+
+    /// Address the deployer creates the pool for `key` at.
+    ///
+    /// V2 pairs are salted with the tokens only, so the fee of the key is ignored.
+    pub fn pool_address(&self, key: &PoolKey) -> Address {
+        let salt = if self.event.is_concentrated() {
+            // keccak256(abi.encode(token0, token1, fee))
+            let mut preimage = [0u8; 96];
+            preimage[12..32].copy_from_slice(key.token0.as_slice());
+            preimage[44..64].copy_from_slice(key.token1.as_slice());
+            preimage[64..].copy_from_slice(&U256::from(key.fee).to_be_bytes::<32>());
+            keccak256(preimage)
+        } else {
+            // keccak256(abi.encodePacked(token0, token1))
+            keccak256([key.token0.as_slice(), key.token1.as_slice()].concat())
+        };
+
+        self.deployer.create2(salt, self.init_code_hash)
+    }
+
+    /// Whether `pool` was deployed by this protocol for `key`.
+    pub fn is_genuine(&self, pool: Address, key: &PoolKey) -> bool {
+        self.pool_address(key) == pool
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{primitives::aliases::U24, sol};
+
+    use crate::utils::{addresses, setup};
+
+    use super::*;
+
+    sol! {
+        #[sol(rpc)]
+        interface IFactory {
+            function getPair(address tokenA, address tokenB) external view returns (address);
+            function getPool(address tokenA, address tokenB, uint24 fee) external view returns (address);
+        }
+    }
+
+    fn usdc_weth(fee: u32) -> PoolKey {
+        PoolKey {
+            token0: *addresses::USDC_ADDR,
+            token1: *addresses::WETH,
+            fee,
+        }
+    }
+
+    #[test]
+    fn test_pool_address() {
+        assert_eq!(
+            DexProtocol::uniswap_v3().pool_address(&usdc_weth(500)),
+            addresses::get_address("0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640").unwrap()
+        );
+        assert_eq!(
+            DexProtocol::uniswap_v2().pool_address(&usdc_weth(V2_FEE)),
+            *addresses::WETH_USDC_PAIR
+        );
+
+        // The fee only salts concentrated pools
+        assert_eq!(
+            DexProtocol::uniswap_v2().pool_address(&usdc_weth(0)),
+            *addresses::WETH_USDC_PAIR
+        );
+        assert!(!DexProtocol::uniswap_v3().is_genuine(*addresses::WETH_USDC_PAIR, &usdc_weth(500)));
+    }
+
+    #[test]
+    fn test_swap_event_topics() {
+        assert_eq!(
+            SwapEvent::UniswapV3.topic(),
+            b256!("c42079f94a6350d7e6235f29174924f928cc2ac818eb64fed8004e115fbcca67")
+        );
+        assert_eq!(
+            SwapEvent::UniswapV2.topic(),
+            b256!("d78ad95fa46c994b6551d0da85fc275fe613ce37657fb8d5e3d130840159d822")
+        );
+        assert_eq!(
+            SwapEvent::PancakeV3.topic(),
+            b256!("19b47279256b2a23a1665c810c8d55a1758940ee09377d4f8d26497a3577dc83")
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_pool_address_matches_factories() {
+        let (provider, _) = setup::test_setup().await;
+        let (usdc, weth) = (*addresses::USDC_ADDR, *addresses::WETH);
+
+        let sushi_v2 = IFactory::new(
+            addresses::get_address("0xC0AEe478e3658e2610c5F7A4A2E1777cE9e4f2Ac").unwrap(),
+            &provider,
+        );
+        let pair = sushi_v2.getPair(usdc, weth).call().await.unwrap()._0;
+        assert_eq!(
+            DexProtocol::sushiswap_v2().pool_address(&usdc_weth(V2_FEE)),
+            pair
+        );
+
+        // PancakeSwap V3 pools are deployed by a separate deployer, not the factory.
+        // Both protocols have had a USDC/WETH 0.05% pool since their launch in April 2023
+        for (protocol, factory, fee) in [
+            (
+                DexProtocol::sushiswap_v3(),
+                "0xbACEB8eC6b9355Dfc0269C18bac9d6E2Bdc29C4F",
+                500,
+            ),
+            (
+                DexProtocol::pancakeswap_v3(),
+                "0x0BFbCF9fa4f9C56B0F40a671Ad40E0805A091865",
+                500,
+            ),
+        ] {
+            let factory = IFactory::new(addresses::get_address(factory).unwrap(), &provider);
+            let pool = factory
+                .getPool(usdc, weth, U24::from(fee))
+                .call()
+                .await
+                .unwrap()
+                ._0;
+
+            assert!(!pool.is_zero(), "{} pool not deployed", protocol.name);
+            assert_eq!(protocol.pool_address(&usdc_weth(fee)), pool);
+        }
+    }
+}
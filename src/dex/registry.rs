@@ -0,0 +1,410 @@
+use std::{collections::HashMap, fmt::Debug};
+
+use alloy::{
+    primitives::{Address, Log, U256},
+    sol_types::SolEvent,
+};
+use eyre::Result;
+use revm::{Database, DatabaseCommit};
+
+use crate::{
+    interfaces::{router02::IUniswapV2Pair, uniswapv3pool::IUniswapV3Pool},
+    simulation::outcome::TxOutcome,
+    uniswap_v3::pool::{unpack_slot0, view_call, PoolKey, SLOT0_SLOT},
+};
+
+use super::{
+    protocol::{DexProtocol, IPancakeV3Pool, LiquidityStrategy, SwapEvent, V2_FEE},
+    swap::{DexSwap, UniswapV2SwapInfo, UniswapV3SwapInfo},
+};
+
+/// A swap in a pool which was verified to be deployed by a registered protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedSwap {
+    pub protocol: &'static str,
+    pub strategy: LiquidityStrategy,
+    pub swap: DexSwap,
+}
+
+/// The DEXes whose swaps are recognized in simulated transactions.
+///
+/// A `Swap` log is only trusted if its emitter is the CREATE2 address a registered
+/// protocol would deploy for the emitter's tokens and fee, so contracts which merely
+/// mimic a pool are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DexRegistry {
+    protocols: Vec<DexProtocol>,
+}
+
+impl Default for DexRegistry {
+    /// The mainnet deployments of the supported protocols.
+    fn default() -> Self {
+        Self::new(vec![
+            DexProtocol::uniswap_v3(),
+            DexProtocol::sushiswap_v3(),
+            DexProtocol::pancakeswap_v3(),
+            DexProtocol::uniswap_v2(),
+            DexProtocol::sushiswap_v2(),
+        ])
+    }
+}
+
+impl DexRegistry {
+    pub fn new(protocols: Vec<DexProtocol>) -> Self {
+        Self { protocols }
+    }
+
+    /// Register another protocol, e.g. a fork on a different chain.
+    pub fn with_protocol(mut self, protocol: DexProtocol) -> Self {
+        self.protocols.push(protocol);
+        self
+    }
+
+    pub fn protocols(&self) -> &[DexProtocol] {
+        &self.protocols
+    }
+
+    /// The protocol which deployed `pool`, among those whose pools emit `event`.
+    pub fn verify(&self, event: SwapEvent, pool: Address, key: &PoolKey) -> Option<&DexProtocol> {
+        self.protocols
+            .iter()
+            .find(|protocol| protocol.event == event && protocol.is_genuine(pool, key))
+    }
+
+    /// Decode the swaps of every registered protocol in a simulated transaction.
+    ///
+    /// Pool keys are read from the simulated state and the price before the first swap
+    /// in a concentrated pool from the `slot0` the transaction touched, later swaps in
+    /// the same pool start where the previous one ended. Logs which cannot be
+    /// attributed to a registered protocol are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache_db` - The state the transaction executed on.
+    /// * `tx` - The transaction outcome which potentially contains logs.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<DetectedSwap>>` - The decoded swaps, in execution order.
+    pub fn decode_logs<DB>(&self, cache_db: &mut DB, tx: &TxOutcome) -> Result<Vec<DetectedSwap>>
+    where
+        DB: Database + DatabaseCommit,
+        DB::Error: Debug,
+    {
+        // Pools which were already attributed, or `None` if they could not be
+        let mut pools: HashMap<Address, Option<(&DexProtocol, PoolKey)>> = HashMap::new();
+        // Price and tick the next swap in each concentrated pool starts at
+        let mut prices: HashMap<Address, (U256, i32)> = HashMap::new();
+        let mut swaps = Vec::new();
+
+        for log in tx.result.logs() {
+            let Some(event) = self.event(log) else {
+                continue;
+            };
+
+            let pool = log.address;
+            let attributed = *pools
+                .entry(pool)
+                .or_insert_with(|| self.attribute(cache_db, event, pool));
+            let Some((protocol, key)) = attributed else {
+                continue;
+            };
+
+            let swap = match event {
+                SwapEvent::UniswapV2 => {
+                    let log = IUniswapV2Pair::Swap::decode_log(log, true)?;
+                    DexSwap::V2(UniswapV2SwapInfo {
+                        pool,
+                        key,
+                        sender: log.data.sender,
+                        to: log.data.to,
+                        amount0_in: log.data.amount0In,
+                        amount1_in: log.data.amount1In,
+                        amount0_out: log.data.amount0Out,
+                        amount1_out: log.data.amount1Out,
+                    })
+                }
+                SwapEvent::UniswapV3 | SwapEvent::PancakeV3 => {
+                    let before = match prices.get(&pool) {
+                        Some(before) => *before,
+                        None => slot0_before(tx, pool)?,
+                    };
+                    let swap = decode_v3_swap(event, log, key, before)?;
+                    prices.insert(pool, (swap.sqrt_price_x96, swap.tick));
+                    DexSwap::V3(swap)
+                }
+            };
+
+            swaps.push(DetectedSwap {
+                protocol: protocol.name,
+                strategy: protocol.strategy,
+                swap,
+            });
+        }
+
+        Ok(swaps)
+    }
+
+    /// The swap event a log is, if any registered protocol emits it.
+    fn event(&self, log: &Log) -> Option<SwapEvent> {
+        let topic = log.topics().first()?;
+        self.protocols
+            .iter()
+            .map(|protocol| protocol.event)
+            .find(|event| event.topic() == *topic)
+    }
+
+    /// Read the key of a pool and find the protocol which deployed it.
+    fn attribute<DB>(
+        &self,
+        cache_db: &mut DB,
+        event: SwapEvent,
+        pool: Address,
+    ) -> Option<(&DexProtocol, PoolKey)>
+    where
+        DB: Database + DatabaseCommit,
+        DB::Error: Debug,
+    {
+        let key = match load_key(cache_db, event, pool) {
+            Ok(key) => key,
+            Err(e) => {
+                println!("Skipping swap log of {}: {:?}", pool, e);
+                return None;
+            }
+        };
+
+        let protocol = self.verify(event, pool, &key);
+        if protocol.is_none() {
+            println!(
+                "Skipping swap log of {} which no registered protocol deployed",
+                pool
+            );
+        }
+        protocol.map(|protocol| (protocol, key))
+    }
+}
+
+/// Read the key of a pool from simulated state, V2 pairs all charge `V2_FEE`.
+fn load_key<DB>(cache_db: &mut DB, event: SwapEvent, pool: Address) -> Result<PoolKey>
+where
+    DB: Database + DatabaseCommit,
+    DB::Error: Debug,
+{
+    if event.is_concentrated() {
+        return PoolKey::load_from_db(cache_db, pool);
+    }
+
+    Ok(PoolKey {
+        token0: view_call(cache_db, pool, IUniswapV2Pair::token0Call {})?._0,
+        token1: view_call(cache_db, pool, IUniswapV2Pair::token1Call {})?._0,
+        fee: V2_FEE,
+    })
+}
+
+fn decode_v3_swap(
+    event: SwapEvent,
+    log: &Log,
+    key: PoolKey,
+    (sqrt_price_before_x96, tick_before): (U256, i32),
+) -> Result<UniswapV3SwapInfo> {
+    // Both events share the leading fields, PancakeSwap only appends the protocol fees
+    let (sender, recipient, amount0, amount1, sqrt_price_x96, liquidity, tick) = match event {
+        SwapEvent::PancakeV3 => {
+            let log = IPancakeV3Pool::Swap::decode_log(log, true)?;
+            (
+                log.data.sender,
+                log.data.recipient,
+                log.data.amount0,
+                log.data.amount1,
+                log.data.sqrtPriceX96,
+                log.data.liquidity,
+                log.data.tick,
+            )
+        }
+        _ => {
+            let log = IUniswapV3Pool::Swap::decode_log(log, true)?;
+            (
+                log.data.sender,
+                log.data.recipient,
+                log.data.amount0,
+                log.data.amount1,
+                log.data.sqrtPriceX96,
+                log.data.liquidity,
+                log.data.tick,
+            )
+        }
+    };
+
+    Ok(UniswapV3SwapInfo {
+        pool: log.address,
+        key,
+        sender,
+        recipient,
+        amount0,
+        amount1,
+        sqrt_price_before_x96,
+        tick_before,
+        sqrt_price_x96: U256::from(sqrt_price_x96),
+        liquidity,
+        tick: tick.as_i32(),
+    })
+}
+
+/// Pool price and tick at the start of a transaction, from the `slot0` it touched.
+fn slot0_before(tx: &TxOutcome, pool: Address) -> Result<(U256, i32)> {
+    let slot = tx
+        .state
+        .get(&pool)
+        .and_then(|account| account.storage.get(&U256::from(SLOT0_SLOT)))
+        .ok_or_else(|| eyre::eyre!("slot0 of {} was not touched", pool))?;
+
+    Ok(unpack_slot0(slot.original_value))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use alloy::{eips::BlockNumberOrTag, primitives::LogData, providers::Provider};
+    use revm::primitives::{Account, AccountInfo, EvmStorageSlot};
+
+    use crate::{
+        simulation::statecache,
+        utils::{addresses, setup},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_verify() {
+        let registry = DexRegistry::default();
+        let key = PoolKey {
+            token0: *addresses::USDC_ADDR,
+            token1: *addresses::WETH,
+            fee: 500,
+        };
+        let pool = addresses::get_address("0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640").unwrap();
+
+        let protocol = registry.verify(SwapEvent::UniswapV3, pool, &key).unwrap();
+        assert_eq!(protocol.name, "Uniswap V3");
+
+        // A lookalike at another address, or one claiming another fee tier
+        assert!(registry
+            .verify(SwapEvent::UniswapV3, Address::repeat_byte(0x01), &key)
+            .is_none());
+        assert!(registry
+            .verify(SwapEvent::UniswapV3, pool, &PoolKey { fee: 3000, ..key })
+            .is_none());
+
+        // Only protocols emitting the event are considered
+        assert!(registry.verify(SwapEvent::PancakeV3, pool, &key).is_none());
+        assert!(DexRegistry::new(Vec::new())
+            .verify(SwapEvent::UniswapV3, pool, &key)
+            .is_none());
+    }
+
+    #[test]
+    fn test_event() {
+        let registry = DexRegistry::new(vec![DexProtocol::uniswap_v2()]);
+        let log = |topic| Log {
+            address: Address::ZERO,
+            data: LogData::new_unchecked(vec![topic], Default::default()),
+        };
+
+        assert_eq!(
+            registry.event(&log(SwapEvent::UniswapV2.topic())),
+            Some(SwapEvent::UniswapV2)
+        );
+        // Not registered
+        assert_eq!(registry.event(&log(SwapEvent::UniswapV3.topic())), None);
+        assert_eq!(
+            registry
+                .with_protocol(DexProtocol::uniswap_v3())
+                .event(&log(SwapEvent::UniswapV3.topic())),
+            Some(SwapEvent::UniswapV3)
+        );
+    }
+
+    #[test]
+    fn test_slot0_before() {
+        let pool = Address::repeat_byte(0x01);
+        let before = (U256::from(7) << 160) | U256::from(1000);
+        let after = (U256::from(9) << 160) | U256::from(2000);
+
+        let mut account = Account::from(AccountInfo::default());
+        account.storage.insert(
+            U256::from(SLOT0_SLOT),
+            EvmStorageSlot::new_changed(before, after),
+        );
+        let mut tx = TxOutcome::success(21_000, Vec::new());
+        tx.state.insert(pool, account);
+
+        assert_eq!(slot0_before(&tx, pool).unwrap(), (U256::from(1000), 7));
+        assert!(slot0_before(&tx, Address::repeat_byte(0x02)).is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_decode_v2_logs() {
+        let (provider, client) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+        let block = provider.get_block_number().await.unwrap();
+
+        // The log the USDC/WETH pair emits when selling 1 WETH for 3000 USDC
+        let swap = IUniswapV2Pair::Swap {
+            sender: client,
+            amount0In: U256::ZERO,
+            amount1In: U256::from(10).pow(U256::from(18)),
+            amount0Out: U256::from(3_000_000_000u64),
+            amount1Out: U256::ZERO,
+            to: client,
+        };
+        let pair = *addresses::WETH_USDC_PAIR;
+        let lookalike = Address::repeat_byte(0x01);
+        let tx = TxOutcome::success(
+            100_000,
+            vec![
+                Log {
+                    address: pair,
+                    data: swap.encode_log_data(),
+                },
+                // Same event from a contract no registered protocol deployed
+                Log {
+                    address: lookalike,
+                    data: swap.encode_log_data(),
+                },
+            ],
+        );
+
+        let mut cache_db =
+            statecache::init_state_cache_db(provider, BlockNumberOrTag::Number(block).into(), None);
+        let swaps = tokio::task::spawn_blocking(move || {
+            DexRegistry::default()
+                .decode_logs(&mut cache_db, &tx)
+                .unwrap()
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            swaps,
+            vec![DetectedSwap {
+                protocol: "Uniswap V2",
+                strategy: LiquidityStrategy::DetectOnly,
+                swap: DexSwap::V2(UniswapV2SwapInfo {
+                    pool: pair,
+                    key: PoolKey {
+                        token0: *addresses::USDC_ADDR,
+                        token1: *addresses::WETH,
+                        fee: V2_FEE,
+                    },
+                    sender: client,
+                    to: client,
+                    amount0_in: U256::ZERO,
+                    amount1_in: U256::from(10).pow(U256::from(18)),
+                    amount0_out: U256::from(3_000_000_000u64),
+                    amount1_out: U256::ZERO,
+                }),
+            }]
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use alloy::primitives::{Address, I256, U256};
+
+use crate::uniswap_v3::{pool::PoolKey, tick_bitmap::compress};
+
+/// A decoded V3 style `Swap` log together with the pool state around it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniswapV3SwapInfo {
+    pub pool: Address,
+    pub key: PoolKey,
+    /// Caller of the pool, usually a router
+    pub sender: Address,
+    pub recipient: Address,
+    /// Positive amounts are paid into the pool, negative amounts are paid out
+    pub amount0: I256,
+    pub amount1: I256,
+    /// Pool price and tick before the swap
+    pub sqrt_price_before_x96: U256,
+    pub tick_before: i32,
+    /// Pool price, in-range liquidity and tick after the swap
+    pub sqrt_price_x96: U256,
+    pub liquidity: u128,
+    pub tick: i32,
+}
+
+impl UniswapV3SwapInfo {
+    /// Whether token0 is sold for token1, which moves the price down.
+    pub fn zero_for_one(&self) -> bool {
+        self.amount0 > I256::ZERO || self.amount1 < I256::ZERO
+    }
+
+    pub fn token_in(&self) -> Address {
+        if self.zero_for_one() {
+            self.key.token0
+        } else {
+            self.key.token1
+        }
+    }
+
+    pub fn token_out(&self) -> Address {
+        if self.zero_for_one() {
+            self.key.token1
+        } else {
+            self.key.token0
+        }
+    }
+
+    /// Amount paid into the pool, including the fee.
+    pub fn amount_in(&self) -> U256 {
+        self.amount0.max(self.amount1).unsigned_abs()
+    }
+
+    /// Amount paid out of the pool.
+    pub fn amount_out(&self) -> U256 {
+        self.amount0.min(self.amount1).unsigned_abs()
+    }
+
+    /// Lowest and highest tick the pool was at during the swap.
+    pub fn tick_range(&self) -> (i32, i32) {
+        (
+            self.tick_before.min(self.tick),
+            self.tick_before.max(self.tick),
+        )
+    }
+
+    /// Number of tick spacing boundaries the swap crossed, initialized or not.
+    pub fn spacings_crossed(&self, tick_spacing: i32) -> u32 {
+        compress(self.tick, tick_spacing).abs_diff(compress(self.tick_before, tick_spacing))
+    }
+}
+
+/// A decoded V2 style `Swap` log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniswapV2SwapInfo {
+    pub pool: Address,
+    /// The pair's tokens, the fee is fixed at 0.3%
+    pub key: PoolKey,
+    pub sender: Address,
+    pub to: Address,
+    pub amount0_in: U256,
+    pub amount1_in: U256,
+    pub amount0_out: U256,
+    pub amount1_out: U256,
+}
+
+impl UniswapV2SwapInfo {
+    /// Whether token0 is sold for token1.
+    pub fn zero_for_one(&self) -> bool {
+        self.amount0_in > self.amount1_in
+    }
+
+    pub fn token_in(&self) -> Address {
+        if self.zero_for_one() {
+            self.key.token0
+        } else {
+            self.key.token1
+        }
+    }
+
+    pub fn token_out(&self) -> Address {
+        if self.zero_for_one() {
+            self.key.token1
+        } else {
+            self.key.token0
+        }
+    }
+
+    /// Amount paid into the pair, including the fee.
+    pub fn amount_in(&self) -> U256 {
+        self.amount0_in.max(self.amount1_in)
+    }
+
+    /// Amount paid out of the pair.
+    pub fn amount_out(&self) -> U256 {
+        self.amount0_out.max(self.amount1_out)
+    }
+}
+
+/// A swap in any of the supported pool designs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DexSwap {
+    V2(UniswapV2SwapInfo),
+    V3(UniswapV3SwapInfo),
+}
+
+impl DexSwap {
+    pub fn pool(&self) -> Address {
+        match self {
+            DexSwap::V2(swap) => swap.pool,
+            DexSwap::V3(swap) => swap.pool,
+        }
+    }
+
+    pub fn key(&self) -> &PoolKey {
+        match self {
+            DexSwap::V2(swap) => &swap.key,
+            DexSwap::V3(swap) => &swap.key,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> PoolKey {
+        PoolKey {
+            token0: Address::repeat_byte(0x0a),
+            token1: Address::repeat_byte(0x0b),
+            fee: 500,
+        }
+    }
+
+    fn swap(tick_before: i32, tick: i32) -> UniswapV3SwapInfo {
+        UniswapV3SwapInfo {
+            pool: Address::ZERO,
+            key: key(),
+            sender: Address::ZERO,
+            recipient: Address::ZERO,
+            amount0: I256::try_from(1000).unwrap(),
+            amount1: I256::try_from(-990).unwrap(),
+            sqrt_price_before_x96: U256::ZERO,
+            tick_before,
+            sqrt_price_x96: U256::ZERO,
+            liquidity: 0,
+            tick,
+        }
+    }
+
+    #[test]
+    fn test_v3_swap_info() {
+        let sell = swap(25, -5);
+
+        assert!(sell.zero_for_one());
+        assert_eq!(sell.token_in(), sell.key.token0);
+        assert_eq!(sell.token_out(), sell.key.token1);
+        assert_eq!(sell.amount_in(), U256::from(1000));
+        assert_eq!(sell.amount_out(), U256::from(990));
+        assert_eq!(sell.tick_range(), (-5, 25));
+        // From spacing [20, 30) down to [-10, 0)
+        assert_eq!(sell.spacings_crossed(10), 3);
+
+        let buy = UniswapV3SwapInfo {
+            amount0: I256::try_from(-990).unwrap(),
+            amount1: I256::try_from(1000).unwrap(),
+            ..swap(3, 7)
+        };
+
+        assert!(!buy.zero_for_one());
+        assert_eq!(buy.token_in(), buy.key.token1);
+        assert_eq!(buy.amount_in(), U256::from(1000));
+        assert_eq!(buy.spacings_crossed(10), 0);
+    }
+
+    #[test]
+    fn test_v2_swap_info() {
+        let buy = UniswapV2SwapInfo {
+            pool: Address::repeat_byte(0x01),
+            key: key(),
+            sender: Address::ZERO,
+            to: Address::ZERO,
+            amount0_in: U256::ZERO,
+            amount1_in: U256::from(1000),
+            amount0_out: U256::from(990),
+            amount1_out: U256::ZERO,
+        };
+
+        assert!(!buy.zero_for_one());
+        assert_eq!(buy.token_in(), buy.key.token1);
+        assert_eq!(buy.token_out(), buy.key.token0);
+        assert_eq!(buy.amount_in(), U256::from(1000));
+        assert_eq!(buy.amount_out(), U256::from(990));
+
+        let swap = DexSwap::V2(buy);
+        assert_eq!(swap.pool(), Address::repeat_byte(0x01));
+        assert_eq!(swap.key().fee, 500);
+    }
+}
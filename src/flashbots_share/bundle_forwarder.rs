@@ -64,17 +64,28 @@ where
     }
 
     pub async fn send_bundle(self) -> TransportResult<SimBundleResponse> {
+        let block_number = self.bundle.inclusion.block;
+        self.send_bundle_at(block_number).await
+    }
+
+    /// Send the bundle to the relay targeting a specific block, the bundle can be
+    /// resent for several consecutive blocks.
+    pub async fn send_bundle_at(&self, block_number: u64) -> TransportResult<SimBundleResponse> {
+        let mut bundle = self.bundle.clone();
+        bundle.inclusion.block = block_number;
+        bundle.inclusion.max_block = None;
+
         // Send the bundle to the flashbots relay
         let request = self
             .flashbot_provider
             .client()
-            .make_request("mev_sendBundle", (self.bundle,));
+            .make_request("mev_sendBundle", (bundle,));
 
         RpcCall::new(
             request,
             MevHttp::flashbots(
                 self.flashbot_provider.client().transport().clone(),
-                self.signer,
+                self.signer.clone(),
             ),
         )
         .await
@@ -1,19 +1,91 @@
 use alloy::{
-    consensus::Transaction,
+    eips::eip2718::Encodable2718,
     network::{EthereumWallet, TransactionBuilder},
+    primitives::{Address, Bytes},
+    providers::Provider,
     rpc::types::{
         mev::{BundleItem, SendBundleRequest},
         Transaction as RpcTransaction, TransactionRequest,
     },
+    transports::http::{Client, Http},
 };
 
 use eyre::Result;
 use tokio_stream::StreamExt;
 
+// Gas limit used for our own bundle transactions when none is set, these cannot be
+// estimated against the chain as the backrun depends on the frontrun
+pub(super) const BUNDLE_TX_GAS_LIMIT: u64 = 1_000_000;
+
+/// Fill in the fields required to sign our frontrun and backrun transactions.
+///
+/// Gas limits and fees already set are kept, so the signed transactions pay what
+/// they were simulated with.
+///
+/// # Arguments
+///
+/// * `provider` - A reference to the provider.
+/// * `from` - The address which signs the transactions.
+/// * `frontrun` - The frontrun transactions.
+/// * `backrun` - The backrun transactions.
+///
+/// # Returns
+///
+/// * `Result<(Vec<TransactionRequest>, Vec<TransactionRequest>)>` - The filled frontrun and backrun transactions.
+pub async fn prepare_requests<P: Provider<Http<Client>>>(
+    provider: &P,
+    from: Address,
+    frontrun: Vec<TransactionRequest>,
+    backrun: Vec<TransactionRequest>,
+) -> Result<(Vec<TransactionRequest>, Vec<TransactionRequest>)> {
+    let chain_id = provider.get_chain_id().await?;
+    let nonce = provider.get_transaction_count(from).await?;
+    let fees = provider.estimate_eip1559_fees(None).await?;
+
+    let front_len = frontrun.len();
+
+    // Nonces are sequential across the frontrun and backrun as they land in the same block
+    let mut filled = frontrun
+        .into_iter()
+        .chain(backrun)
+        .enumerate()
+        .map(|(i, tx)| {
+            let gas = tx.gas.unwrap_or(BUNDLE_TX_GAS_LIMIT);
+            let max_fee = tx.max_fee_per_gas.unwrap_or(fees.max_fee_per_gas);
+            let tip = tx
+                .max_priority_fee_per_gas
+                .unwrap_or(fees.max_priority_fee_per_gas);
+            tx.with_from(from)
+                .with_chain_id(chain_id)
+                .with_nonce(nonce + i as u64)
+                .with_gas_limit(gas)
+                .with_max_fee_per_gas(max_fee)
+                .with_max_priority_fee_per_gas(tip)
+        })
+        .collect::<Vec<_>>();
+
+    let backrun = filled.split_off(front_len);
+
+    Ok((filled, backrun))
+}
+
+/// Sign our transactions and wrap them around the victims.
+///
+/// # Arguments
+///
+/// * `wallet` - The wallet which signs our transactions.
+/// * `frontrun` - The filled frontrun transactions.
+/// * `victims` - The signed victim transactions, in bundle order.
+/// * `backrun` - The filled backrun transactions.
+/// * `block_number` - The block the bundle targets.
+///
+/// # Returns
+///
+/// * `Result<SendBundleRequest>` - The bundle.
 pub async fn create_bundle(
     wallet: &EthereumWallet,
     frontrun: Vec<TransactionRequest>,
-    sandwich: RpcTransaction,
+    victims: Vec<RpcTransaction>,
     backrun: Vec<TransactionRequest>,
     block_number: u64,
 ) -> Result<SendBundleRequest> {
@@ -23,7 +95,7 @@ pub async fn create_bundle(
         .then(|tx| async {
             tx.build(wallet)
                 .await
-                .map(|sig| sig.input().clone())
+                .map(|sig| Bytes::from(sig.encoded_2718()))
                 .map_err(eyre::Report::new)
         })
         .collect::<Result<Vec<_>>>()
@@ -32,14 +104,15 @@ pub async fn create_bundle(
         .then(|tx| async {
             tx.build(wallet)
                 .await
-                .map(|sig| sig.input().clone())
+                .map(|sig| Bytes::from(sig.encoded_2718()))
                 .map_err(eyre::Report::new)
         })
         .collect::<Result<Vec<_>>>()
         .await?;
 
-    // Receive the sandwich transaction and convert into bytes
-    let sandwich_hash = sandwich.input().clone();
+    if victims.is_empty() {
+        return Err(eyre::eyre!("Bundle has no victims"));
+    }
 
     // Create a vector of BundleItems
     let mut bundle_items: Vec<BundleItem> = Vec::new();
@@ -51,10 +124,13 @@ pub async fn create_bundle(
         });
     }
 
-    bundle_items.push(BundleItem::Tx {
-        tx: sandwich_hash,
-        can_revert: false,
-    });
+    // Receive the signed victim transactions and convert into bytes
+    for victim in victims {
+        bundle_items.push(BundleItem::Tx {
+            tx: Bytes::from(victim.inner.encoded_2718()),
+            can_revert: false,
+        });
+    }
 
     for tx in signed_back {
         bundle_items.push(BundleItem::Tx {
@@ -3,23 +3,38 @@ use std::sync::Arc;
 use async_trait::async_trait;
 
 use alloy::{
+    eips::BlockId,
     network::{Ethereum, Network},
     providers::Provider,
     rpc::types::TransactionRequest,
     sol,
-    sol_types::SolEvent,
     transports::{BoxTransport, Transport},
 };
 
 use eyre::Result;
+use futures_util::future::join_all;
 
-use revm::primitives::{Address, ExecutionResult, Log};
-use sandwich_bundler::SandwichBundler;
+use revm::primitives::{Address, BlockEnv};
+use sandwich_bundler::{GroupBundle, SandwichBundler};
 use IExecutor::IExecutorInstance;
 
-use crate::simulation::engine::EngineTask;
+use crate::{
+    dex::{
+        protocol::{DexProtocol, LiquidityStrategy, SwapEvent},
+        registry::{DetectedSwap, DexRegistry},
+        swap::{DexSwap, UniswapV3SwapInfo},
+    },
+    interfaces::whitelist::Whitelist,
+    simulation::{
+        engine::{EngineResultBundle, EngineTask},
+        outcome::TxOutcome,
+        statecache::StateCache,
+        workerpool::WorkerPool,
+    },
+    uniswap_v3::pool::PoolKey,
+};
 
-use super::sandwich_bundler;
+use super::{jit_optimizer::JitOptimizer, sandwich_bundler};
 
 sol!(
     #[sol(rpc)]
@@ -28,44 +43,79 @@ sol!(
 
 sol! {
     #[sol(rpc)]
-    event Swap(
-    address sender,
-    address recipient,
-    int256 amount0,
-    int256 amount1,
-    uint160 sqrtPriceX96,
-    uint128 liquidity,
-    int24 tick);
+    interface IExecutorWhitelist {
+        function whitelist() external view returns (address);
+    }
+
 }
 
-pub struct UniswapV3SwapInfo {
-    pub pool: Address,
+/// The protocol which deployed `pool` for `key`, if we can mint a JIT position in it.
+///
+/// The pool address is recomputed from the deployer, tokens and fee, so a contract
+/// which only reports the tokens and fee of a real pool is rejected.
+fn genuine_protocol<'a>(
+    registry: &'a DexRegistry,
+    pool: Address,
+    key: &PoolKey,
+) -> Result<&'a DexProtocol> {
+    registry
+        .verify(SwapEvent::UniswapV3, pool, key)
+        .filter(|protocol| protocol.strategy == LiquidityStrategy::ConcentratedJit)
+        .ok_or_else(|| {
+            eyre::eyre!(
+                "{} is not the pool of {} and {} with fee {}",
+                pool,
+                key.token0,
+                key.token1,
+                key.fee
+            )
+        })
 }
 
-/// Extract key information from UniswapV3 logs.
+/// Keep the swaps we can provide liquidity for.
 ///
 /// # Arguments
 ///
-/// * `tx` - The transaction result which potentially contains logs.
+/// * `swaps` - The swaps detected in a transaction.
 ///
 /// # Returns
 ///
-/// * `Vec<Log<Swap>` - The resulting swap logs.
-fn decode_uniswapv3_logs(tx: ExecutionResult) -> Result<Vec<UniswapV3SwapInfo>> {
-    // Assert that we are using Uniswap V3 with the corret function signatures
-    let result = tx.into_logs();
-
-    result
+/// * `Vec<UniswapV3SwapInfo>` - The swaps in pools the executor can mint a JIT position in.
+fn jit_swaps(swaps: Vec<DetectedSwap>) -> Vec<UniswapV3SwapInfo> {
+    swaps
         .into_iter()
-        .filter_map(|res| Swap::decode_log(&res, true).ok())
-        .map(extract_uniswapv3_info)
-        .collect::<Result<Vec<UniswapV3SwapInfo>>>()
+        .filter_map(|detected| match (detected.strategy, detected.swap) {
+            (LiquidityStrategy::ConcentratedJit, DexSwap::V3(swap)) => Some(swap),
+            (_, swap) => {
+                println!(
+                    "Cannot provide liquidity for {} swap in {}",
+                    detected.protocol,
+                    swap.pool()
+                );
+                None
+            }
+        })
+        .collect()
 }
 
-fn extract_uniswapv3_info(log: Log<Swap>) -> Result<UniswapV3SwapInfo> {
-    let pool = log.address;
-
-    Ok(UniswapV3SwapInfo { pool })
+/// Group swaps by pool, in the order the pools are first swapped in.
+///
+/// # Arguments
+///
+/// * `swaps` - The swaps of one or more transactions, e.g. a multi-hop route or an aggregator split.
+///
+/// # Returns
+///
+/// * `Vec<(Address, Vec<UniswapV3SwapInfo>)>` - Every swapped pool with its swaps in execution order.
+fn swaps_by_pool(swaps: Vec<UniswapV3SwapInfo>) -> Vec<(Address, Vec<UniswapV3SwapInfo>)> {
+    let mut pools: Vec<(Address, Vec<UniswapV3SwapInfo>)> = Vec::new();
+    for swap in swaps {
+        match pools.iter_mut().find(|(pool, _)| *pool == swap.pool) {
+            Some((_, swaps)) => swaps.push(swap),
+            None => pools.push((swap.pool, vec![swap])),
+        }
+    }
+    pools
 }
 
 pub struct UniswapV3LiquidityBundler<
@@ -74,23 +124,208 @@ pub struct UniswapV3LiquidityBundler<
     N: Network = Ethereum,
 > {
     executor: IExecutorInstance<T, Arc<P>, N>,
+    owner: Address,
+    // Shared state and the block it was fetched at
+    state_cache: Option<(Arc<StateCache>, u64)>,
+    block_env: Option<BlockEnv>,
+    priority_fee: Option<u128>,
+    worker_pool: Option<WorkerPool>,
+    registry: Arc<DexRegistry>,
 }
 
 impl<P, T, N> UniswapV3LiquidityBundler<P, T, N>
 where
-    P: Provider<T, N>,
+    P: Provider<T, N> + 'static,
     T: Transport + Clone,
     N: Network<TransactionRequest = TransactionRequest>,
 {
-    pub fn new(executor: IExecutorInstance<T, Arc<P>, N>) -> Self {
-        Self { executor }
+    pub fn new(executor: IExecutorInstance<T, Arc<P>, N>, owner: Address) -> Self {
+        Self {
+            executor,
+            owner,
+            state_cache: None,
+            block_env: None,
+            priority_fee: None,
+            worker_pool: None,
+            registry: Arc::new(DexRegistry::default()),
+        }
+    }
+
+    /// Detect victims in the pools of `registry` instead of the mainnet protocols.
+    pub fn with_registry(mut self, registry: Arc<DexRegistry>) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Simulate on top of `block`, sharing fetched state through `cache`.
+    pub fn with_state_cache(mut self, cache: Arc<StateCache>, block: u64) -> Self {
+        self.state_cache = Some((cache, block));
+        self
+    }
+
+    /// Set the block environment every simulation executes in, see `blockenv::next_block_env`.
+    ///
+    /// Without it the bundle executes in an empty block, with a zero timestamp.
+    pub fn with_block_env(mut self, block_env: BlockEnv) -> Self {
+        self.block_env = Some(block_env);
+        self
+    }
+
+    /// Tip our transactions pay on top of the base fee of the target block, in wei.
+    pub fn with_priority_fee(mut self, priority_fee: u128) -> Self {
+        self.priority_fee = Some(priority_fee);
+        self
+    }
+
+    /// Run the simulations on a shared worker pool.
+    pub fn with_worker_pool(mut self, pool: WorkerPool) -> Self {
+        self.worker_pool = Some(pool);
+        self
+    }
+
+    fn engine_task(
+        &self,
+        provider: Arc<P>,
+        bundle: Vec<TransactionRequest>,
+    ) -> EngineTask<P, T, N> {
+        let mut task = EngineTask::new(provider, bundle);
+        if let Some(pool) = &self.worker_pool {
+            task = task.with_worker_pool(pool.clone());
+        }
+        if let Some(block_env) = &self.block_env {
+            task = task.with_block_env(block_env.clone());
+        }
+
+        match &self.state_cache {
+            Some((cache, block)) => task
+                .with_block((*block).into())
+                .with_state_cache(cache.clone()),
+            None => task,
+        }
+    }
+
+    fn optimizer(&self) -> JitOptimizer<P, T, N> {
+        let mut optimizer = JitOptimizer::new(self.executor.clone(), self.owner);
+        if let Some(block_env) = &self.block_env {
+            optimizer = optimizer.with_block_env(block_env.clone());
+        }
+        if let Some(priority_fee) = self.priority_fee {
+            optimizer = optimizer.with_priority_fee(priority_fee);
+        }
+        if let Some(pool) = &self.worker_pool {
+            optimizer = optimizer.with_worker_pool(pool.clone());
+        }
+        match &self.state_cache {
+            Some((cache, block)) => optimizer.with_state_cache(cache.clone(), *block),
+            None => optimizer,
+        }
+    }
+
+    /// Simulate the victims in order and decode the swaps we can provide liquidity for.
+    ///
+    /// A victim which reverts is not committed, so the victims after it execute as if
+    /// it was left out of the bundle.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider to simulate with.
+    /// * `victims` - The victim transactions, in bundle order.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Result<Vec<UniswapV3SwapInfo>>>>` - The swaps of every victim, or why it failed.
+    async fn simulate_victims(
+        &self,
+        provider: Arc<P>,
+        victims: Vec<TransactionRequest>,
+    ) -> Result<Vec<Result<Vec<UniswapV3SwapInfo>>>> {
+        let registry = self.registry.clone();
+        self.engine_task(provider, victims)
+            .consume_with_async(move |cache_db, results| {
+                results
+                    .into_iter()
+                    .map(|result| {
+                        let result =
+                            result.map_err(|e| eyre::eyre!("Victim simulation failed: {}", e))?;
+                        if !result.is_success() {
+                            return Err(eyre::eyre!(
+                                "Victim did not succeed: {:?} {:?}",
+                                result.result,
+                                result.revert_reason
+                            ));
+                        }
+                        Ok(jit_swaps(registry.decode_logs(cache_db, &result)?))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .await
+    }
+
+    /// Check that `pool` is safe to hand to the executor before building a frontrun.
+    ///
+    /// The pool must be at the CREATE2 address of a registered protocol and be
+    /// whitelisted by the executor's `Whitelist`, otherwise `executeWithParams` would
+    /// either revert or mint our funds into a contract posing as a pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider to query the whitelist with.
+    /// * `pool` - The pool a victim swaps in.
+    /// * `key` - The tokens and fee the pool reported.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error describing why the pool cannot be used.
+    pub async fn verify_pool(&self, provider: Arc<P>, pool: Address, key: &PoolKey) -> Result<()> {
+        let protocol = genuine_protocol(&self.registry, pool, key)?;
+
+        let block = match &self.state_cache {
+            Some((_, block)) => BlockId::number(*block),
+            None => BlockId::latest(),
+        };
+        let executor = IExecutorWhitelist::new(*self.executor.address(), provider.clone());
+        let whitelist = executor.whitelist().block(block).call().await?._0;
+        let whitelisted = Whitelist::new(whitelist, provider)
+            .checkWhitelist(pool)
+            .block(block)
+            .call()
+            .await?
+            ._0;
+
+        if !whitelisted {
+            return Err(eyre::eyre!(
+                "{} pool {} is not whitelisted",
+                protocol.name,
+                pool
+            ));
+        }
+        Ok(())
+    }
+
+    /// Pools a victim swaps in, in the order it first swaps in them.
+    pub async fn swapped_pools(
+        &self,
+        provider: Arc<P>,
+        tx: TransactionRequest,
+    ) -> Result<Vec<Address>> {
+        let swaps = self
+            .simulate_victims(provider, vec![tx])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| eyre::eyre!("No result found"))??;
+
+        Ok(swaps_by_pool(swaps)
+            .into_iter()
+            .map(|(pool, _)| pool)
+            .collect())
     }
 }
 
 #[async_trait]
 impl<P, T, N> SandwichBundler<P, T, N> for UniswapV3LiquidityBundler<P, T, N>
 where
-    P: Provider<T, N>,
+    P: Provider<T, N> + 'static,
     T: Transport + Clone,
     N: Network<TransactionRequest = TransactionRequest>,
 {
@@ -99,27 +334,279 @@ where
         provider: Arc<P>,
         tx: TransactionRequest,
     ) -> Result<(Vec<TransactionRequest>, Vec<TransactionRequest>)> {
-        // Extract pool address
-        // Create an engine task and execute
-        let task = EngineTask::new(provider, vec![tx]);
-        let result = task.consume();
-
-        // Extract ResultAndState and assert we have no errors
-        let result = result
-            .first()
-            .ok_or_else(|| eyre::eyre!("No result found"))?;
-        let logs = decode_uniswapv3_logs(result.as_ref().unwrap().result.clone())?;
-
-        // For now assert that we only have one Swap log
-        // TODO: Handle multiple logs
-        if logs.len() != 1 {
-            return Err(eyre::eyre!("Expected 1 log, got {}", logs.len()));
+        let group = self.build_group(provider, vec![tx]).await?;
+        Ok((group.frontrun, group.backrun))
+    }
+
+    async fn build_group(
+        &self,
+        provider: Arc<P>,
+        txs: Vec<TransactionRequest>,
+    ) -> Result<GroupBundle> {
+        let mut kept = (0..txs.len()).collect::<Vec<_>>();
+
+        // Every round drops at least one victim, so this terminates
+        loop {
+            let victims = kept
+                .iter()
+                .map(|index| txs[*index].clone())
+                .collect::<Vec<_>>();
+            let outcomes = self.simulate_victims(provider.clone(), victims).await?;
+
+            // Drop victims which revert on their own
+            let mut swaps = Vec::new();
+            let mut survivors = Vec::new();
+            for (index, outcome) in kept.iter().zip(outcomes) {
+                match outcome {
+                    Ok(logs) => {
+                        survivors.push(*index);
+                        swaps.extend(logs);
+                    }
+                    Err(e) => println!("Dropping victim {}: {:?}", index, e),
+                }
+            }
+            kept = survivors;
+
+            let pools = swaps_by_pool(swaps);
+            if pools.is_empty() {
+                return Err(eyre::eyre!("No Uniswap V3 swap found"));
+            }
+
+            // Only ever mint into pools which are proven genuine and whitelisted
+            let verified = join_all(
+                pools
+                    .iter()
+                    .map(|(pool, swaps)| self.verify_pool(provider.clone(), *pool, &swaps[0].key)),
+            )
+            .await;
+            let pools = pools
+                .into_iter()
+                .zip(verified)
+                .filter_map(|((pool, swaps), verified)| match verified {
+                    Ok(()) => Some((pool, swaps)),
+                    Err(e) => {
+                        println!("Skipping pool {}: {:?}", pool, e);
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+            if pools.is_empty() {
+                return Err(eyre::eyre!("No verified pool to provide liquidity in"));
+            }
+
+            // The executor holds a single position at a time, so find the most profitable
+            // position in every swapped pool and keep the best one
+            let victims = kept
+                .iter()
+                .map(|index| txs[*index].clone())
+                .collect::<Vec<_>>();
+            let optimizer = self.optimizer();
+            let plans =
+                join_all(pools.iter().map(|(pool, swaps)| {
+                    optimizer.optimize(provider.clone(), &victims, *pool, swaps)
+                }))
+                .await;
+
+            let (pool, key, plan) = pools
+                .iter()
+                .zip(plans)
+                .filter_map(|((pool, swaps), plan)| match plan {
+                    Ok(plan) => plan.map(|plan| (*pool, swaps[0].key, plan)),
+                    Err(e) => {
+                        println!("Could not optimize pool {}: {:?}", pool, e);
+                        None
+                    }
+                })
+                .max_by_key(|(_, _, plan)| plan.profit)
+                .ok_or_else(|| eyre::eyre!("No profitable position in {} pools", pools.len()))?;
+
+            let frontrun = optimizer.frontrun(pool, &plan.params)?;
+            let backrun = optimizer.backrun();
+
+            // Simulate the complete bundle before spending relay rate limits on it, tracking
+            // the executor's holdings of both tokens `verify_pool` checked the pool against
+            let mut bundle = vec![frontrun.clone()];
+            bundle.extend(victims);
+            bundle.push(backrun.clone());
+            let simulation = self
+                .engine_task(provider.clone(), bundle)
+                .simulate_bundle_async(*self.executor.address(), vec![key.token0, key.token1])
+                .await?;
+
+            if simulation.is_success() {
+                return Ok(GroupBundle {
+                    frontrun: vec![frontrun],
+                    victims: kept,
+                    backrun: vec![backrun],
+                });
+            }
+
+            // Victims sit between the frontrun and the backrun
+            let failed = failed_victims(&simulation.results);
+            if failed.is_empty() || failed.len() == kept.len() {
+                return Err(eyre::eyre!(
+                    "Bundle simulation failed: {:?}",
+                    simulation.results
+                ));
+            }
+
+            for position in failed.iter().rev() {
+                println!(
+                    "Dropping victim {} which reverts in the bundle",
+                    kept[*position]
+                );
+                kept.remove(*position);
+            }
         }
+    }
+}
+
+/// Positions of the victims which failed in a `[frontrun, victims.., backrun]` bundle.
+fn failed_victims(results: &EngineResultBundle) -> Vec<usize> {
+    let victims = results.len().saturating_sub(2);
+    results
+        .iter()
+        .skip(1)
+        .take(victims)
+        .enumerate()
+        .filter(|(_, result)| !result.as_ref().is_ok_and(TxOutcome::is_success))
+        .map(|(position, _)| position)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{I256, U256};
+    use revm::primitives::{Bytes, ExecutionResult};
+
+    use crate::{dex::swap::UniswapV2SwapInfo, utils::addresses};
+
+    use super::*;
+
+    fn success() -> Result<TxOutcome> {
+        Ok(TxOutcome::success(21_000, Vec::new()))
+    }
+
+    fn revert() -> Result<TxOutcome> {
+        Ok(TxOutcome::new(
+            ExecutionResult::Revert {
+                gas_used: 21_000,
+                output: Bytes::new(),
+            },
+            Default::default(),
+            false,
+        ))
+    }
+
+    fn swap(pool: Address, tick_before: i32, tick: i32) -> UniswapV3SwapInfo {
+        UniswapV3SwapInfo {
+            pool,
+            key: PoolKey {
+                token0: Address::repeat_byte(0x0a),
+                token1: Address::repeat_byte(0x0b),
+                fee: 500,
+            },
+            sender: Address::ZERO,
+            recipient: Address::ZERO,
+            amount0: I256::try_from(1000).unwrap(),
+            amount1: I256::try_from(-990).unwrap(),
+            sqrt_price_before_x96: U256::ZERO,
+            tick_before,
+            sqrt_price_x96: U256::ZERO,
+            liquidity: 0,
+            tick,
+        }
+    }
+
+    #[test]
+    fn test_swaps_by_pool() {
+        let a = Address::repeat_byte(0x01);
+        let b = Address::repeat_byte(0x02);
+
+        // A split route which swaps through pool a twice
+        let swaps = vec![swap(a, 12, 10), swap(b, 0, -5), swap(a, 10, 3)];
+
+        assert_eq!(
+            swaps_by_pool(swaps.clone()),
+            vec![
+                (a, vec![swaps[0].clone(), swaps[2].clone()]),
+                (b, vec![swaps[1].clone()])
+            ]
+        );
+        assert!(swaps_by_pool(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_jit_swaps() {
+        let v3 = swap(Address::repeat_byte(0x01), 0, 10);
+        let detected = |strategy, swap| DetectedSwap {
+            protocol: "test",
+            strategy,
+            swap,
+        };
+
+        let swaps = vec![
+            detected(LiquidityStrategy::ConcentratedJit, DexSwap::V3(v3.clone())),
+            // PancakeSwap V3 pools are detected but need another mint callback
+            detected(LiquidityStrategy::DetectOnly, DexSwap::V3(v3.clone())),
+            detected(
+                LiquidityStrategy::DetectOnly,
+                DexSwap::V2(UniswapV2SwapInfo {
+                    pool: Address::repeat_byte(0x02),
+                    key: v3.key,
+                    sender: Address::ZERO,
+                    to: Address::ZERO,
+                    amount0_in: U256::from(1),
+                    amount1_in: U256::ZERO,
+                    amount0_out: U256::ZERO,
+                    amount1_out: U256::from(1),
+                }),
+            ),
+        ];
+
+        assert_eq!(jit_swaps(swaps), vec![v3]);
+    }
+
+    #[test]
+    fn test_genuine_protocol() {
+        let registry = DexRegistry::default();
+        let key = PoolKey {
+            token0: *addresses::USDC_ADDR,
+            token1: *addresses::WETH,
+            fee: 500,
+        };
+        let pool = addresses::get_address("0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640").unwrap();
+
+        assert_eq!(
+            genuine_protocol(&registry, pool, &key).unwrap().name,
+            "Uniswap V3"
+        );
+
+        // A `FakeUniswapV3Pool` reporting the same tokens and fee
+        assert!(genuine_protocol(&registry, Address::repeat_byte(0x01), &key).is_err());
+
+        // A genuine pool of a protocol the executor cannot mint in
+        let detect_only = DexRegistry::new(vec![DexProtocol {
+            strategy: LiquidityStrategy::DetectOnly,
+            ..DexProtocol::uniswap_v3()
+        }]);
+        assert!(genuine_protocol(&detect_only, pool, &key).is_err());
+    }
 
-        let log = logs.first().ok_or_else(|| eyre::eyre!("No log found"))?;
-        let frontrun = self.executor.execute(log.pool).into_transaction_request();
-        let backrun = self.executor.finish().into_transaction_request();
+    #[test]
+    fn test_failed_victims() {
+        // The second victim reverts and the third could not be executed at all
+        let results = vec![
+            success(),
+            success(),
+            revert(),
+            Err(eyre::eyre!("nonce too low")),
+            success(),
+        ];
+        assert_eq!(failed_victims(&results), vec![1, 2]);
 
-        Ok((vec![frontrun], vec![backrun]))
+        // A failing frontrun or backrun is not blamed on the victims
+        let results = vec![revert(), success(), revert()];
+        assert!(failed_victims(&results).is_empty());
     }
 }
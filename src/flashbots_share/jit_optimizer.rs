@@ -0,0 +1,501 @@
+use std::sync::Arc;
+
+use alloy::{
+    eips::BlockId,
+    network::{Ethereum, Network},
+    primitives::{aliases::I24, Address, I256, U256},
+    providers::Provider,
+    rpc::types::TransactionRequest,
+    sol,
+    transports::{BoxTransport, Transport},
+};
+use eyre::Result;
+use revm::primitives::BlockEnv;
+
+use crate::{
+    dex::swap::UniswapV3SwapInfo,
+    interfaces::{
+        fundmanager::{self, FundChange},
+        oracle,
+        uniswapv3pool::IUniswapV3Pool,
+    },
+    simulation::{
+        batch::{BatchSimulator, Candidate},
+        engine::BundleSimulation,
+        statecache::StateCache,
+        workerpool::WorkerPool,
+    },
+    utils::addresses,
+};
+
+use super::{bundler::BUNDLE_TX_GAS_LIMIT, jit_bundler::IExecutor::IExecutorInstance};
+
+sol! {
+    #[sol(rpc)]
+    interface IExecutorState {
+        function fundManager() external view returns (address);
+    }
+}
+
+const MIN_TICK: i32 = -887272;
+const MAX_TICK: i32 = 887272;
+
+// Extra tick spacings added on each side of the range the victim swaps through
+const DEFAULT_WIDTHS: [i32; 4] = [0, 1, 2, 4];
+// Percentages of the largest liquidity our balances allow for a range
+const DEFAULT_FRACTIONS: [u128; 4] = [25, 50, 75, 90];
+// Tip our transactions pay on top of the base fee, in wei
+const DEFAULT_PRIORITY_FEE: u128 = 1_000_000_000;
+
+/// Position the executor mints around a victim swap, see `IExecutor::executeWithParams`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JitParams {
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+}
+
+/// Most profitable position found by the optimizer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JitPlan {
+    pub params: JitParams,
+    /// Change in the USD value of our holdings minus gas, with 8 decimals
+    pub profit: I256,
+}
+
+/// Searches tick ranges and liquidity amounts for the most profitable JIT position.
+///
+/// Every combination is simulated as `[executeWithParams, victim, finish]`, profit is
+/// the change in holdings reported by the fund manager, which includes both the fees
+/// captured and the inventory loss, minus the gas of our own transactions.
+pub struct JitOptimizer<
+    P: Provider<T, N>,
+    T: Clone + Transport = BoxTransport,
+    N: Network = Ethereum,
+> {
+    executor: IExecutorInstance<T, Arc<P>, N>,
+    owner: Address,
+    widths: Vec<i32>,
+    fractions: Vec<u128>,
+    priority_fee: u128,
+    // Fetched when the optimizer runs if not set
+    gas_price: Option<u128>,
+    eth_price: Option<U256>,
+    state_cache: Option<(Arc<StateCache>, u64)>,
+    block_env: Option<BlockEnv>,
+    worker_pool: Option<WorkerPool>,
+}
+
+impl<P, T, N> JitOptimizer<P, T, N>
+where
+    P: Provider<T, N> + 'static,
+    T: Transport + Clone,
+    N: Network<TransactionRequest = TransactionRequest>,
+{
+    pub fn new(executor: IExecutorInstance<T, Arc<P>, N>, owner: Address) -> Self {
+        Self {
+            executor,
+            owner,
+            widths: DEFAULT_WIDTHS.to_vec(),
+            fractions: DEFAULT_FRACTIONS.to_vec(),
+            priority_fee: DEFAULT_PRIORITY_FEE,
+            gas_price: None,
+            eth_price: None,
+            state_cache: None,
+            block_env: None,
+            worker_pool: None,
+        }
+    }
+
+    /// Extra tick spacings on each side of the swapped range to try.
+    pub fn with_widths(mut self, widths: Vec<i32>) -> Self {
+        self.widths = widths;
+        self
+    }
+
+    /// Percentages of the largest possible liquidity to try.
+    pub fn with_fractions(mut self, fractions: Vec<u128>) -> Self {
+        self.fractions = fractions;
+        self
+    }
+
+    /// Tip our transactions pay on top of the base fee of the target block, in wei.
+    pub fn with_priority_fee(mut self, priority_fee: u128) -> Self {
+        self.priority_fee = priority_fee;
+        self
+    }
+
+    /// Gas price the gas of our transactions is valued at, in wei.
+    ///
+    /// Defaults to the base fee of the block environment plus our tip, or the node's
+    /// gas price without a block environment.
+    pub fn with_gas_price(mut self, gas_price: u128) -> Self {
+        self.gas_price = Some(gas_price);
+        self
+    }
+
+    /// ETH price in USD with 8 decimals, as reported by our oracle.
+    pub fn with_eth_price(mut self, eth_price: U256) -> Self {
+        self.eth_price = Some(eth_price);
+        self
+    }
+
+    /// Simulate on top of `block`, sharing fetched state through `cache`.
+    pub fn with_state_cache(mut self, cache: Arc<StateCache>, block: u64) -> Self {
+        self.state_cache = Some((cache, block));
+        self
+    }
+
+    /// Set the block environment the candidates execute in, see `blockenv::next_block_env`.
+    pub fn with_block_env(mut self, block_env: BlockEnv) -> Self {
+        self.block_env = Some(block_env);
+        self
+    }
+
+    /// Simulate the candidates on a shared worker pool.
+    pub fn with_worker_pool(mut self, pool: WorkerPool) -> Self {
+        self.worker_pool = Some(pool);
+        self
+    }
+
+    /// The block the state cache is pinned to, so reads agree with the simulations.
+    fn block(&self) -> BlockId {
+        match &self.state_cache {
+            Some((_, block)) => BlockId::number(*block),
+            None => BlockId::latest(),
+        }
+    }
+
+    /// Find the most profitable position around a group of victim swaps.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider to simulate with.
+    /// * `victims` - The victim transactions, in bundle order.
+    /// * `pool` - The pool the victims swap in.
+    /// * `swaps` - The victims' swaps in the pool, decoded from their `Swap` logs.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<JitPlan>>` - The best position, if any is profitable.
+    pub async fn optimize(
+        &self,
+        provider: Arc<P>,
+        victims: &[TransactionRequest],
+        pool: Address,
+        swaps: &[UniswapV3SwapInfo],
+    ) -> Result<Option<JitPlan>> {
+        let pool_contract = IUniswapV3Pool::new(pool, provider.clone());
+        let spacing = pool_contract
+            .tickSpacing()
+            .block(self.block())
+            .call()
+            .await?
+            ._0
+            .as_i32();
+
+        // The position has to cover every tick the victims move the pool through
+        let ticks = swaps
+            .iter()
+            .flat_map(|swap| [swap.tick_before, swap.tick])
+            .collect::<Vec<_>>();
+
+        let mut params = Vec::new();
+        for (tick_lower, tick_upper) in candidate_ranges(&ticks, spacing, &self.widths) {
+            let max_liquidity = self
+                .executor
+                .calcLiquidityForRange(pool, to_i24(tick_lower)?, to_i24(tick_upper)?)
+                .block(self.block())
+                .call()
+                .await?
+                ._0;
+
+            params.extend(
+                self.fractions
+                    .iter()
+                    .map(|fraction| max_liquidity / 100 * fraction)
+                    .filter(|liquidity| *liquidity > 0)
+                    .map(|liquidity| JitParams {
+                        tick_lower,
+                        tick_upper,
+                        liquidity,
+                    }),
+            );
+        }
+
+        if params.is_empty() {
+            return Ok(None);
+        }
+
+        let candidates = params
+            .iter()
+            .map(|params| {
+                let mut bundle = vec![self.frontrun(pool, params)?];
+                bundle.extend_from_slice(victims);
+                bundle.push(self.backrun());
+                Ok(Candidate::new(bundle, Vec::new()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let gas_price = match (self.gas_price, &self.block_env) {
+            (Some(gas_price), _) => gas_price,
+            (None, Some(_)) => self.max_fee_per_gas(),
+            (None, None) => provider.get_gas_price().await?,
+        };
+        let eth_price = match self.eth_price {
+            Some(eth_price) => eth_price,
+            None => {
+                fetch_eth_price(provider.as_ref(), *self.executor.address(), self.block()).await?
+            }
+        };
+
+        let mut simulator = BatchSimulator::new(provider, *self.executor.address());
+        if let Some((cache, block)) = &self.state_cache {
+            simulator = simulator
+                .with_block(BlockId::number(*block))
+                .with_state_cache(cache.clone());
+        }
+        if let Some(block_env) = &self.block_env {
+            simulator = simulator.with_block_env(block_env.clone());
+        }
+        if let Some(pool) = &self.worker_pool {
+            simulator = simulator.with_worker_pool(pool.clone());
+        }
+
+        let ranked = simulator
+            .simulate_async(candidates, move |simulation| {
+                score(simulation, gas_price, eth_price)
+            })
+            .await?;
+
+        // Ranked by profit, so only the first candidate can be the best
+        Ok(ranked
+            .first()
+            .and_then(|ranked| {
+                Some(JitPlan {
+                    params: params[ranked.index],
+                    profit: ranked.profit?,
+                })
+            })
+            .filter(|plan| plan.profit > I256::ZERO))
+    }
+
+    /// Transaction which mints the position.
+    pub fn frontrun(&self, pool: Address, params: &JitParams) -> Result<TransactionRequest> {
+        let frontrun = self
+            .executor
+            .executeWithParams(
+                pool,
+                to_i24(params.tick_lower)?,
+                to_i24(params.tick_upper)?,
+                params.liquidity,
+            )
+            .from(self.owner)
+            .into_transaction_request();
+        Ok(self.with_fees(frontrun))
+    }
+
+    /// Transaction which burns the position and checks the benchmark.
+    pub fn backrun(&self) -> TransactionRequest {
+        let backrun = self
+            .executor
+            .finish()
+            .from(self.owner)
+            .into_transaction_request();
+        self.with_fees(backrun)
+    }
+
+    /// Most our transactions pay per gas, the base fee of the target block plus our tip.
+    fn max_fee_per_gas(&self) -> u128 {
+        let basefee = self
+            .block_env
+            .as_ref()
+            .map_or(0, |block_env| block_env.basefee.saturating_to());
+        basefee + self.priority_fee
+    }
+
+    /// Set the gas limit and fees our transactions are signed with, so that the
+    /// simulation pays the base fee of the target block like the bundle does.
+    fn with_fees(&self, request: TransactionRequest) -> TransactionRequest {
+        request
+            .gas_limit(BUNDLE_TX_GAS_LIMIT)
+            .max_fee_per_gas(self.max_fee_per_gas())
+            .max_priority_fee_per_gas(self.priority_fee)
+    }
+}
+
+/// Read the ETH price from the oracle our fund manager benchmarks with.
+///
+/// # Arguments
+///
+/// * `provider` - A reference to the provider.
+/// * `executor` - The executor contract.
+/// * `block` - The block to read the fund manager at.
+///
+/// # Returns
+///
+/// * `Result<U256>` - The ETH price in USD with 8 decimals.
+pub async fn fetch_eth_price<T, N, P>(
+    provider: &P,
+    executor: Address,
+    block: BlockId,
+) -> Result<U256>
+where
+    T: Transport + Clone,
+    N: Network,
+    P: Provider<T, N>,
+{
+    let fund_manager = IExecutorState::new(executor, provider)
+        .fundManager()
+        .block(block)
+        .call()
+        .await?
+        ._0;
+    let price_oracle = fundmanager::oracle(provider, fund_manager).await?;
+
+    // Pools quote WETH, so its feed is always set when we benchmark a pool
+    oracle::get_price(provider, price_oracle, *addresses::WETH)
+        .await?
+        .map_err(|reason| eyre::eyre!("Oracle cannot price WETH: {:?}", reason))
+}
+
+/// Tick ranges covering all of `ticks`, widened by each of `widths` spacings.
+fn candidate_ranges(ticks: &[i32], spacing: i32, widths: &[i32]) -> Vec<(i32, i32)> {
+    let (Some(min), Some(max)) = (ticks.iter().min(), ticks.iter().max()) else {
+        return Vec::new();
+    };
+
+    let lower = min.div_euclid(spacing) * spacing;
+    let upper = max.div_euclid(spacing) * spacing + spacing;
+
+    let mut ranges = widths
+        .iter()
+        .map(|width| (lower - width * spacing, upper + width * spacing))
+        .filter(|(lower, upper)| *lower >= MIN_TICK && *upper <= MAX_TICK)
+        .collect::<Vec<_>>();
+    ranges.dedup();
+    ranges
+}
+
+/// USD value of the gas used, with 8 decimals.
+fn gas_cost(gas_used: u64, gas_price: u128, eth_price: U256) -> U256 {
+    U256::from(gas_used) * U256::from(gas_price) * eth_price / U256::from(10).pow(U256::from(18))
+}
+
+/// Profit of a simulated `[frontrun, victim, backrun]` bundle in USD with 8 decimals.
+fn score(simulation: &BundleSimulation, gas_price: u128, eth_price: U256) -> Option<I256> {
+    let frontrun = simulation.results.first()?.as_ref().ok()?;
+    let backrun = simulation.results.last()?.as_ref().ok()?;
+
+    let change = fundmanager::fund_changes(backrun.result.logs())
+        .into_iter()
+        .next()?;
+
+    let cost = gas_cost(
+        frontrun.gas_used() + backrun.gas_used(),
+        gas_price,
+        eth_price,
+    );
+
+    Some(
+        I256::from_raw(change.valueAfter)
+            - I256::from_raw(change.valueBefore)
+            - I256::from_raw(cost),
+    )
+}
+
+fn to_i24(tick: i32) -> Result<I24> {
+    I24::try_from(tick).map_err(|_| eyre::eyre!("Tick {} does not fit into int24", tick))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{primitives::LogData, sol_types::SolEvent};
+    use revm::primitives::Log;
+
+    use crate::simulation::outcome::TxOutcome;
+
+    use super::*;
+
+    fn success(gas_used: u64, logs: Vec<Log>) -> Result<TxOutcome> {
+        Ok(TxOutcome::success(gas_used, logs))
+    }
+
+    #[test]
+    fn test_candidate_ranges() {
+        // Swap within a single spacing
+        assert_eq!(
+            candidate_ranges(&[193677, 193679], 10, &[0, 1]),
+            vec![(193670, 193680), (193660, 193690)]
+        );
+
+        // Swap crossing spacings downwards
+        assert_eq!(
+            candidate_ranges(&[193677, 193652], 10, &[0]),
+            vec![(193650, 193680)]
+        );
+
+        // Negative ticks round towards negative infinity
+        assert_eq!(candidate_ranges(&[-5, -15], 10, &[0]), vec![(-20, 0)]);
+
+        // Ranges outside the valid ticks are dropped
+        assert_eq!(
+            candidate_ranges(&[MAX_TICK - 100], 60, &[0, 10]),
+            vec![(887100, 887160)]
+        );
+
+        // Several swaps in the same pool, the middle one moving furthest
+        assert_eq!(
+            candidate_ranges(&[193677, 193612, 193640], 10, &[0]),
+            vec![(193610, 193680)]
+        );
+        assert!(candidate_ranges(&[], 10, &[0]).is_empty());
+    }
+
+    #[test]
+    fn test_gas_cost() {
+        // 200k gas at 10 gwei and 2000 USD per ETH is 4 USD
+        let cost = gas_cost(
+            200_000,
+            10_000_000_000,
+            U256::from(2000) * U256::from(10).pow(U256::from(8)),
+        );
+        assert_eq!(cost, U256::from(4) * U256::from(10).pow(U256::from(8)));
+    }
+
+    #[test]
+    fn test_score() {
+        let usd = U256::from(10).pow(U256::from(8));
+        let change = FundChange {
+            valueBefore: U256::from(1000) * usd,
+            valueAfter: U256::from(1010) * usd,
+        };
+        let log = Log {
+            address: Address::repeat_byte(0x01),
+            data: LogData::new_unchecked(
+                vec![FundChange::SIGNATURE_HASH],
+                change.encode_data().into(),
+            ),
+        };
+
+        let simulation = BundleSimulation {
+            results: vec![
+                success(100_000, Vec::new()),
+                success(150_000, Vec::new()),
+                success(100_000, vec![log]),
+            ],
+            deltas: Vec::new(),
+        };
+
+        // 10 USD gained minus 4 USD of gas for our two transactions
+        let profit = score(&simulation, 10_000_000_000, U256::from(2000) * usd);
+        assert_eq!(profit, Some(I256::from_raw(U256::from(6) * usd)));
+
+        // No benchmark, no profit
+        let simulation = BundleSimulation {
+            results: vec![success(100_000, Vec::new())],
+            deltas: Vec::new(),
+        };
+        assert_eq!(score(&simulation, 0, U256::ZERO), None);
+    }
+}
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use alloy::{
-    network::EthereumWallet,
+    network::{Ethereum, EthereumWallet, NetworkWallet},
     providers::Provider,
     rpc::types::{mev::SimBundleResponse, Transaction},
     signers::Signer,
@@ -24,7 +24,8 @@ pub struct FlashBotMev<
     tx_wallet: &'a EthereumWallet,
     flashbot_signer: S,
     bundler: B,
-    sandwich_tx: Transaction,
+    /// Ordered victims which share a single frontrun and backrun
+    victims: Vec<Transaction>,
 }
 
 impl<'a, P, B, S> FlashBotMev<'a, P, B, S>
@@ -39,7 +40,7 @@ where
         tx_wallet: &'a EthereumWallet,
         flashbot_signer: S,
         bundler: B,
-        sandwich_tx: Transaction,
+        victims: Vec<Transaction>,
     ) -> Self {
         Self {
             provider,
@@ -47,27 +48,36 @@ where
             tx_wallet,
             flashbot_signer,
             bundler,
-            sandwich_tx,
+            victims,
         }
     }
 
-    async fn build_forwarder(
+    pub async fn build_forwarder(
         self,
         block_number: u64,
     ) -> Result<BundleForwarder<P, S, Http<Client>>> {
-        let sandwich = self
+        let requests = self.victims.iter().cloned().map(Into::into).collect();
+        let group = self
             .bundler
-            .build(self.provider, self.sandwich_tx.clone().into())
+            .build_group(self.provider.clone(), requests)
             .await?;
 
-        let bundle = bundler::create_bundle(
-            self.tx_wallet,
-            sandwich.0,
-            self.sandwich_tx,
-            sandwich.1,
-            block_number,
-        )
-        .await?;
+        // Only the victims which did not make the bundle revert are included
+        let victims = group
+            .victims
+            .iter()
+            .map(|index| self.victims[*index].clone())
+            .collect();
+
+        // Fill in nonces, gas and fees so that our transactions can be signed
+        let from = NetworkWallet::<Ethereum>::default_signer_address(self.tx_wallet);
+        let (frontrun, backrun) =
+            bundler::prepare_requests(self.provider.as_ref(), from, group.frontrun, group.backrun)
+                .await?;
+
+        let bundle =
+            bundler::create_bundle(self.tx_wallet, frontrun, victims, backrun, block_number)
+                .await?;
 
         Ok(BundleForwarder::new(
             self.flashbot_provider,
@@ -1,5 +1,7 @@
-mod bundle_forwarder;
+pub mod bundle_forwarder;
 mod bundler;
 pub mod jit_bundler;
+pub mod jit_optimizer;
 pub mod mev;
 mod sandwich_bundler;
+pub mod swap_group;
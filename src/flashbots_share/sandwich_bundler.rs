@@ -10,6 +10,15 @@ use alloy::{
 
 use eyre::Result;
 
+/// Our transactions around a group of victims.
+#[derive(Debug, Clone)]
+pub struct GroupBundle {
+    pub frontrun: Vec<TransactionRequest>,
+    /// Indices of the victims kept in the bundle, in bundle order
+    pub victims: Vec<usize>,
+    pub backrun: Vec<TransactionRequest>,
+}
+
 #[async_trait]
 pub trait SandwichBundler<
     P: Provider<T, N>,
@@ -22,4 +31,23 @@ pub trait SandwichBundler<
         provider: Arc<P>,
         tx: TransactionRequest,
     ) -> Result<(Vec<TransactionRequest>, Vec<TransactionRequest>)>;
+
+    /// Wrap several ordered victims with a single frontrun and backrun.
+    ///
+    /// Bundlers which cannot group victims only accept a single one.
+    async fn build_group(
+        &self,
+        provider: Arc<P>,
+        txs: Vec<TransactionRequest>,
+    ) -> Result<GroupBundle> {
+        let [tx] = <[TransactionRequest; 1]>::try_from(txs)
+            .map_err(|txs| eyre::eyre!("Expected a single victim, got {}", txs.len()))?;
+        let (frontrun, backrun) = self.build(provider, tx).await?;
+
+        Ok(GroupBundle {
+            frontrun,
+            victims: vec![0],
+            backrun,
+        })
+    }
 }
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use alloy::{
+    consensus::Transaction as _,
+    primitives::{Address, B256},
+    rpc::types::Transaction,
+};
+
+/// A pending swap and the block it was first seen at.
+struct PendingSwap {
+    tx: Transaction,
+    seen_at: u64,
+}
+
+/// Pending swaps grouped by the pool they swap in, so that a single JIT position
+/// can earn the fees of all of them.
+pub struct SwapGroups {
+    // Number of blocks a pending swap is kept for
+    window: u64,
+    groups: HashMap<Address, Vec<PendingSwap>>,
+}
+
+impl SwapGroups {
+    pub fn new(window: u64) -> Self {
+        Self {
+            window,
+            groups: HashMap::new(),
+        }
+    }
+
+    /// Add a pending swap and return the ordered group of its pool.
+    ///
+    /// A swap which replaces an earlier one, i.e. has the same sender and nonce,
+    /// takes its place in the group.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - The pool the swap targets.
+    /// * `tx` - The pending swap.
+    /// * `block` - The current block number.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Transaction>` - The swaps on the pool in the order they should be bundled.
+    pub fn insert(&mut self, pool: Address, tx: Transaction, block: u64) -> Vec<Transaction> {
+        let group = self.groups.entry(pool).or_default();
+        group.retain(|swap| !(swap.tx.from == tx.from && swap.tx.nonce() == tx.nonce()));
+        group.push(PendingSwap { tx, seen_at: block });
+
+        order_victims(group.iter().map(|swap| swap.tx.clone()).collect())
+    }
+
+    /// Forget swaps which were first seen more than `window` blocks before `block`.
+    pub fn prune(&mut self, block: u64) {
+        for group in self.groups.values_mut() {
+            group.retain(|swap| swap.seen_at + self.window >= block);
+        }
+        self.groups.retain(|_, group| !group.is_empty());
+    }
+
+    /// Forget a swap which is no longer pending, e.g. because it was mined or replaced.
+    pub fn remove(&mut self, hash: &B256) {
+        for group in self.groups.values_mut() {
+            group.retain(|swap| swap.tx.inner.tx_hash() != hash);
+        }
+        self.groups.retain(|_, group| !group.is_empty());
+    }
+
+    /// Number of pending swaps on `pool`.
+    pub fn count(&self, pool: &Address) -> usize {
+        self.groups.get(pool).map_or(0, Vec::len)
+    }
+}
+
+/// Order victims the way a builder would, highest priority fee first.
+///
+/// Transactions of the same sender keep their nonce order, otherwise all but the
+/// first of them would fail.
+pub fn order_victims(txs: Vec<Transaction>) -> Vec<Transaction> {
+    let keys = txs
+        .iter()
+        .map(|tx| {
+            let tip = tx
+                .max_priority_fee_per_gas()
+                .or_else(|| tx.gas_price())
+                .unwrap_or_default();
+            (tx.from, tx.nonce(), tip)
+        })
+        .collect::<Vec<_>>();
+
+    let mut txs = txs.into_iter().map(Some).collect::<Vec<_>>();
+    fee_order(&keys)
+        .into_iter()
+        .filter_map(|index| txs[index].take())
+        .collect()
+}
+
+/// Indices of `(sender, nonce, tip)` keys sorted by tip, with every sender's
+/// transactions moved into nonce order within the slots they occupy.
+fn fee_order(keys: &[(Address, u64, u128)]) -> Vec<usize> {
+    let mut order = (0..keys.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| keys[*b].2.cmp(&keys[*a].2));
+
+    let mut slots: HashMap<Address, Vec<usize>> = HashMap::new();
+    for (slot, index) in order.iter().enumerate() {
+        slots.entry(keys[*index].0).or_default().push(slot);
+    }
+
+    for slots in slots.values() {
+        let mut indices = slots.iter().map(|slot| order[*slot]).collect::<Vec<_>>();
+        indices.sort_by_key(|index| keys[*index].1);
+        for (slot, index) in slots.iter().zip(indices) {
+            order[*slot] = index;
+        }
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        network::TransactionBuilder, rpc::types::TransactionRequest,
+        signers::local::PrivateKeySigner,
+    };
+
+    use crate::utils::testtx;
+
+    use super::*;
+
+    async fn pending(signer: &PrivateKeySigner, nonce: u64, tip: u128) -> Transaction {
+        let request = TransactionRequest::default()
+            .with_to(Address::repeat_byte(0x02))
+            .with_nonce(nonce)
+            .with_chain_id(1)
+            .with_gas_limit(21_000)
+            .with_max_fee_per_gas(100)
+            .with_max_priority_fee_per_gas(tip);
+        testtx::sign_pending(signer, request).await
+    }
+
+    #[test]
+    fn test_fee_order() {
+        let a = Address::repeat_byte(0x01);
+        let b = Address::repeat_byte(0x02);
+
+        // Sorted by tip
+        assert_eq!(fee_order(&[(a, 0, 1), (b, 0, 3)]), vec![1, 0]);
+
+        // The later nonce of a pays more, but has to follow the earlier one
+        assert_eq!(fee_order(&[(a, 0, 1), (b, 0, 2), (a, 1, 5)]), vec![0, 1, 2]);
+
+        assert!(fee_order(&[]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_swap_groups() {
+        let alice = PrivateKeySigner::random();
+        let bob = PrivateKeySigner::random();
+        let pool = Address::repeat_byte(0x11);
+        let other = Address::repeat_byte(0x22);
+
+        let mut groups = SwapGroups::new(1);
+
+        let first = pending(&alice, 0, 1).await;
+        let second = pending(&bob, 0, 2).await;
+        groups.insert(pool, first.clone(), 10);
+        let group = groups.insert(pool, second.clone(), 11);
+
+        assert_eq!(group, vec![second.clone(), first]);
+        assert_eq!(groups.count(&other), 0);
+
+        // A replacement takes the place of the original
+        let replacement = pending(&alice, 0, 3).await;
+        let group = groups.insert(pool, replacement.clone(), 11);
+        assert_eq!(group, vec![replacement, second]);
+
+        // Both swaps were first seen more than a block ago
+        groups.prune(13);
+        assert_eq!(groups.count(&pool), 0);
+
+        let later = pending(&bob, 1, 1).await;
+        groups.insert(pool, later.clone(), 13);
+        groups.prune(14);
+        assert_eq!(groups.count(&pool), 1);
+
+        // A swap mined in the meantime leaves its group
+        groups.remove(later.inner.tx_hash());
+        assert_eq!(groups.count(&pool), 0);
+    }
+}
@@ -0,0 +1,168 @@
+use std::{fs, path::Path};
+
+use alloy::{
+    network::{Network, ReceiptResponse, TransactionBuilder},
+    primitives::{Address, Bytes, B256},
+    providers::Provider,
+    transports::Transport,
+};
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+/// Directory `forge build` writes the artifacts of `contracts/` to.
+pub const ARTIFACTS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/contracts/out");
+
+#[derive(Deserialize)]
+struct ForgeBytecode {
+    object: Bytes,
+}
+
+#[derive(Deserialize)]
+struct ForgeArtifact {
+    bytecode: ForgeBytecode,
+}
+
+/// A contract deployed from an artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deployment {
+    pub address: Address,
+    pub tx_hash: B256,
+    /// Block the deployment was mined in
+    pub block: u64,
+}
+
+/// Creation code of a compiled contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artifact {
+    pub name: String,
+    pub bytecode: Bytes,
+}
+
+impl Artifact {
+    /// Load the artifact of `name`, which is declared in `<name>.sol`, from `ARTIFACTS_DIR`.
+    pub fn load(name: &str) -> Result<Self> {
+        Self::load_from(Path::new(ARTIFACTS_DIR), name)
+    }
+
+    /// Load the artifact of `name` from a forge output directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The forge output directory.
+    /// * `name` - The contract name, which is also the name of its source file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Artifact>` - The artifact, or an error if it was not built.
+    pub fn load_from(dir: &Path, name: &str) -> Result<Self> {
+        let path = dir.join(format!("{name}.sol")).join(format!("{name}.json"));
+        let json = fs::read_to_string(&path).map_err(|e| {
+            eyre::eyre!(
+                "Could not read {}, run `forge build`: {}",
+                path.display(),
+                e
+            )
+        })?;
+        let artifact: ForgeArtifact = serde_json::from_str(&json)?;
+
+        if artifact.bytecode.object.is_empty() {
+            return Err(eyre::eyre!("{} is abstract or an interface", name));
+        }
+
+        Ok(Self {
+            name: name.to_owned(),
+            bytecode: artifact.bytecode.object,
+        })
+    }
+
+    /// Creation code followed by the ABI encoded constructor arguments.
+    pub fn deploy_code(&self, constructor_args: &[u8]) -> Bytes {
+        [self.bytecode.as_ref(), constructor_args].concat().into()
+    }
+
+    /// Deploy the contract and wait for it to be mined.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - A provider with a wallet to deploy from.
+    /// * `constructor_args` - The ABI encoded constructor arguments, empty if there are none.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Address>` - The address of the deployed contract.
+    pub async fn deploy<P, T, N>(&self, provider: &P, constructor_args: &[u8]) -> Result<Address>
+    where
+        P: Provider<T, N>,
+        T: Transport + Clone,
+        N: Network,
+    {
+        Ok(self
+            .deploy_tracked(provider, constructor_args)
+            .await?
+            .address)
+    }
+
+    /// Deploy the contract like `deploy`, and keep the transaction it was deployed in.
+    pub async fn deploy_tracked<P, T, N>(
+        &self,
+        provider: &P,
+        constructor_args: &[u8],
+    ) -> Result<Deployment>
+    where
+        P: Provider<T, N>,
+        T: Transport + Clone,
+        N: Network,
+    {
+        let tx =
+            N::TransactionRequest::default().with_deploy_code(self.deploy_code(constructor_args));
+        let receipt = provider.send_transaction(tx).await?.get_receipt().await?;
+
+        if !receipt.status() {
+            return Err(eyre::eyre!(
+                "Deployment of {} reverted in {}",
+                self.name,
+                receipt.transaction_hash()
+            ));
+        }
+
+        Ok(Deployment {
+            address: receipt.contract_address().ok_or_else(|| {
+                eyre::eyre!("Deployment of {} did not create a contract", self.name)
+            })?,
+            tx_hash: receipt.transaction_hash(),
+            block: receipt.block_number().unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_from() {
+        let dir = std::env::temp_dir().join("artifact_test_load_from");
+        fs::create_dir_all(dir.join("Counter.sol")).unwrap();
+        fs::create_dir_all(dir.join("ICounter.sol")).unwrap();
+        fs::write(
+            dir.join("Counter.sol/Counter.json"),
+            r#"{"abi":[],"bytecode":{"object":"0x6080","sourceMap":""}}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("ICounter.sol/ICounter.json"),
+            r#"{"abi":[],"bytecode":{"object":"0x","sourceMap":""}}"#,
+        )
+        .unwrap();
+
+        let artifact = Artifact::load_from(&dir, "Counter").unwrap();
+        assert_eq!(artifact.bytecode, Bytes::from_static(&[0x60, 0x80]));
+        assert_eq!(
+            artifact.deploy_code(&[0x01]),
+            Bytes::from_static(&[0x60, 0x80, 0x01])
+        );
+
+        assert!(Artifact::load_from(&dir, "ICounter").is_err());
+        assert!(Artifact::load_from(&dir, "Missing").is_err());
+    }
+}
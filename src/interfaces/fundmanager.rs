@@ -0,0 +1,230 @@
+use alloy::{
+    network::Network,
+    primitives::{Address, FixedBytes, Log, U256},
+    providers::Provider,
+    sol,
+    sol_types::{SolEvent, SolValue},
+    transports::Transport,
+};
+use eyre::Result;
+
+use super::{
+    artifact::{Artifact, Deployment},
+    executor::Executor,
+};
+
+sol! {
+    #[sol(rpc, all_derives)]
+    contract FundManager {
+        // Emitted by `endBenchmark` with the USD value of the client's holdings
+        event FundChange(uint256 valueBefore, uint256 valueAfter);
+
+        function oracle() external view returns (address);
+        function usd_value() external view returns (uint256);
+        function setOracle(address _oracle) external;
+        function calculateUSDValue(address client, address[] calldata tokens) external view returns (uint256);
+        function startBenchmark(address client, address[] calldata tokens) external;
+        function endBenchmark(address client, address[] calldata tokens) external returns (bool);
+    }
+}
+
+pub use FundManager::FundChange;
+
+/// Number of decimals of the USD values reported by the fund manager.
+pub const USD_DECIMALS: u8 = 8;
+
+/// Deploy a `FundManager` owned by `owner`, which has to be the executor using it.
+pub async fn deploy<P, T, N>(provider: &P, owner: Address) -> Result<Deployment>
+where
+    P: Provider<T, N>,
+    T: Transport + Clone,
+    N: Network,
+{
+    Artifact::load("FundManager")?
+        .deploy_tracked(provider, &owner.abi_encode())
+        .await
+}
+
+/// Point the fund manager at the oracle which prices the benchmarked tokens.
+pub async fn set_oracle<P, T, N>(
+    provider: &P,
+    fund_manager: Address,
+    oracle: Address,
+) -> Result<FixedBytes<32>>
+where
+    P: Provider<T, N>,
+    T: Transport + Clone,
+    N: Network,
+{
+    let tx = FundManager::new(fund_manager, provider)
+        .setOracle(oracle)
+        .into_transaction_request();
+
+    Executor::new(provider, tx).send().await
+}
+
+/// The oracle the fund manager prices tokens with.
+pub async fn oracle<P, T, N>(provider: &P, fund_manager: Address) -> Result<Address>
+where
+    P: Provider<T, N>,
+    T: Transport + Clone,
+    N: Network,
+{
+    Ok(FundManager::new(fund_manager, provider)
+        .oracle()
+        .call()
+        .await?
+        ._0)
+}
+
+/// Calculate the USD value of a client's holdings.
+///
+/// # Arguments
+///
+/// * `provider` - A reference to the provider.
+/// * `fund_manager` - The fund manager address.
+/// * `client` - The holder of the tokens.
+/// * `tokens` - The tokens to value, the zero address stands for ETH.
+///
+/// # Returns
+///
+/// * `Result<U256>` - The value in USD with `USD_DECIMALS` decimals.
+pub async fn calculate_usd_value<P, T, N>(
+    provider: &P,
+    fund_manager: Address,
+    client: Address,
+    tokens: Vec<Address>,
+) -> Result<U256>
+where
+    P: Provider<T, N>,
+    T: Transport + Clone,
+    N: Network,
+{
+    Ok(FundManager::new(fund_manager, provider)
+        .calculateUSDValue(client, tokens)
+        .call()
+        .await?
+        ._0)
+}
+
+/// Decode the `FundChange` events among `logs`, in order.
+pub fn fund_changes<'a>(logs: impl IntoIterator<Item = &'a Log>) -> Vec<FundChange> {
+    logs.into_iter()
+        .filter_map(|log| FundChange::decode_log(log, true).ok())
+        .map(|log| log.data)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{network::ReceiptResponse, primitives::LogData, rpc::types::TransactionReceipt};
+
+    use crate::utils::setup;
+
+    use super::*;
+
+    sol! {
+        #[sol(rpc)]
+        interface IMockOracle {
+            function setPrice(address token, uint256 _price) external;
+        }
+    }
+
+    #[test]
+    fn test_fund_changes() {
+        let change = FundChange {
+            valueBefore: U256::from(100),
+            valueAfter: U256::from(105),
+        };
+        let logs = vec![
+            Log {
+                address: Address::repeat_byte(0x01),
+                data: LogData::new_unchecked(Vec::new(), Default::default()),
+            },
+            Log {
+                address: Address::repeat_byte(0x02),
+                data: change.encode_log_data(),
+            },
+        ];
+
+        assert_eq!(fund_changes(&logs), vec![change]);
+        assert!(fund_changes(&logs[..1]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fund_manager() {
+        let provider = setup::setup_provider_with_anvil(None).await.unwrap();
+        let owner = provider.get_accounts().await.unwrap()[0];
+        let ten = U256::from(10);
+
+        // 10 tokens with 18 decimals, worth 2 USD each
+        let token = Artifact::load("MockERC20")
+            .unwrap()
+            .deploy(
+                &provider,
+                &(
+                    "Mock".to_owned(),
+                    "MCK".to_owned(),
+                    U256::from(18),
+                    ten * ten.pow(U256::from(18)),
+                )
+                    .abi_encode_params(),
+            )
+            .await
+            .unwrap();
+        let oracle = Artifact::load("MockOracle")
+            .unwrap()
+            .deploy(&provider, &[])
+            .await
+            .unwrap();
+        IMockOracle::new(oracle, &provider)
+            .setPrice(token, U256::from(2) * ten.pow(U256::from(USD_DECIMALS)))
+            .send()
+            .await
+            .unwrap()
+            .watch()
+            .await
+            .unwrap();
+
+        let fund_manager = deploy(&provider, owner).await.unwrap().address;
+        set_oracle(&provider, fund_manager, oracle).await.unwrap();
+        assert_eq!(
+            super::oracle(&provider, fund_manager).await.unwrap(),
+            oracle
+        );
+
+        let value = calculate_usd_value(&provider, fund_manager, owner, vec![token])
+            .await
+            .unwrap();
+        assert_eq!(value, U256::from(20) * ten.pow(U256::from(USD_DECIMALS)));
+
+        // Nothing moves between the two benchmarks
+        let contract = FundManager::new(fund_manager, &provider);
+        contract
+            .startBenchmark(owner, vec![token])
+            .send()
+            .await
+            .unwrap()
+            .watch()
+            .await
+            .unwrap();
+        let receipt: TransactionReceipt = contract
+            .endBenchmark(owner, vec![token])
+            .send()
+            .await
+            .unwrap()
+            .get_receipt()
+            .await
+            .unwrap();
+
+        assert!(receipt.status());
+        let logs = receipt.inner.logs().iter().map(|log| &log.inner);
+        assert_eq!(
+            fund_changes(logs),
+            vec![FundChange {
+                valueBefore: value,
+                valueAfter: value,
+            }]
+        );
+    }
+}
@@ -1,4 +1,10 @@
+pub mod artifact;
 pub mod erc20;
 pub mod executor;
+pub mod fundmanager;
+pub mod oracle;
 pub mod router02;
 mod router02interface;
+pub mod swaprouter;
+pub mod uniswapv3pool;
+pub mod whitelist;
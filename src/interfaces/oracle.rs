@@ -0,0 +1,184 @@
+use alloy::{
+    network::Network,
+    primitives::{Address, FixedBytes, U256},
+    providers::Provider,
+    sol,
+    sol_types::SolInterface,
+    transports::Transport,
+};
+use eyre::Result;
+
+use crate::simulation::revert::{self, OracleErrors};
+
+use super::{
+    artifact::{Artifact, Deployment},
+    executor::Executor,
+};
+
+sol! {
+    #[sol(rpc)]
+    contract Oracle {
+        function getPrice(address token) external view returns (uint256);
+        function getDecimals() external pure returns (uint256);
+        function setFeed(address token, address oracle) external;
+    }
+}
+
+/// Deploy an `Oracle` owned by the deployer.
+pub async fn deploy<P, T, N>(provider: &P) -> Result<Deployment>
+where
+    P: Provider<T, N>,
+    T: Transport + Clone,
+    N: Network,
+{
+    Artifact::load("Oracle")?
+        .deploy_tracked(provider, &[])
+        .await
+}
+
+/// Set the Chainlink feed which prices `token` in USD, the zero address stands for ETH.
+pub async fn set_feed<P, T, N>(
+    provider: &P,
+    oracle: Address,
+    token: Address,
+    feed: Address,
+) -> Result<FixedBytes<32>>
+where
+    P: Provider<T, N>,
+    T: Transport + Clone,
+    N: Network,
+{
+    let tx = Oracle::new(oracle, provider)
+        .setFeed(token, feed)
+        .into_transaction_request();
+
+    Executor::new(provider, tx).send().await
+}
+
+/// Get the USD price of a token.
+///
+/// # Arguments
+///
+/// * `provider` - A reference to the provider.
+/// * `oracle` - The oracle address.
+/// * `token` - The token to price.
+///
+/// # Returns
+///
+/// * `Result<std::result::Result<U256, OracleErrors>>` - The price with 8 decimals, or
+///   why the oracle rejected the feed's answer, e.g. `InvalidPrice(PriceTooOld)`.
+pub async fn get_price<P, T, N>(
+    provider: &P,
+    oracle: Address,
+    token: Address,
+) -> Result<std::result::Result<U256, OracleErrors>>
+where
+    P: Provider<T, N>,
+    T: Transport + Clone,
+    N: Network,
+{
+    match Oracle::new(oracle, provider).getPrice(token).call().await {
+        Ok(price) => Ok(Ok(price._0)),
+        Err(e) => match revert::revert_data(&e)
+            .and_then(|data| OracleErrors::abi_decode(&data, true).ok())
+        {
+            Some(reason) => Ok(Err(reason)),
+            None => Err(e.into()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        eips::BlockNumberOrTag,
+        primitives::{aliases::U80, I256},
+        rpc::types::BlockTransactionsKind,
+        sol_types::SolValue,
+    };
+
+    use crate::{
+        simulation::revert::{Oracle as OracleError, OracleErrorReason},
+        utils::setup,
+    };
+
+    use super::*;
+
+    sol! {
+        #[sol(rpc)]
+        interface IMockAggregator {
+            function setRoundData(uint80 _roundId, int256 _answer, uint256 _updatedAt, uint80 _answeredInRound) external;
+        }
+    }
+
+    fn invalid(reason: OracleErrorReason) -> OracleErrors {
+        OracleErrors::InvalidPrice(OracleError::InvalidPrice { reason })
+    }
+
+    #[tokio::test]
+    async fn test_oracle() {
+        let provider = setup::setup_provider_with_anvil(None).await.unwrap();
+        let token = Address::repeat_byte(0x0a);
+        let day = 24 * 60 * 60;
+
+        let oracle = deploy(&provider).await.unwrap().address;
+        assert_eq!(
+            get_price(&provider, oracle, token).await.unwrap(),
+            Err(OracleErrors::OracleNotSet(OracleError::OracleNotSet {}))
+        );
+
+        // A feed with 6 decimals, whose answers are scaled up to 8
+        let feed = Artifact::load("MockAggregator")
+            .unwrap()
+            .deploy(&provider, &U256::from(6).abi_encode())
+            .await
+            .unwrap();
+        set_feed(&provider, oracle, token, feed).await.unwrap();
+
+        let now = provider
+            .get_block_by_number(BlockNumberOrTag::Latest, BlockTransactionsKind::Hashes)
+            .await
+            .unwrap()
+            .unwrap()
+            .header
+            .timestamp;
+        let aggregator = IMockAggregator::new(feed, &provider);
+        let cases = [
+            (
+                (1, -1, now, 1),
+                Err(invalid(OracleErrorReason::NegativePrice)),
+            ),
+            (
+                (2, 100, now, 1),
+                Err(invalid(OracleErrorReason::StaleRound)),
+            ),
+            (
+                (1, 100, 0, 1),
+                Err(invalid(OracleErrorReason::RoundNotFinished)),
+            ),
+            (
+                (1, 100, now - 2 * day, 1),
+                Err(invalid(OracleErrorReason::PriceTooOld)),
+            ),
+            ((1, 100, now, 1), Ok(U256::from(10_000))),
+        ];
+
+        for ((round, answer, updated_at, answered_in), expected) in cases {
+            aggregator
+                .setRoundData(
+                    U80::from(round),
+                    I256::try_from(answer).unwrap(),
+                    U256::from(updated_at),
+                    U80::from(answered_in),
+                )
+                .send()
+                .await
+                .unwrap()
+                .watch()
+                .await
+                .unwrap();
+
+            assert_eq!(get_price(&provider, oracle, token).await.unwrap(), expected);
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use alloy::sol;
+
+sol!(
+    #[sol(rpc)]
+    "contracts/src/interfaces/ISwapRouter.sol"
+);
+
+sol!(
+    #[sol(rpc)]
+    "contracts/src/interfaces/ISwapRouter02.sol"
+);
+
+sol!(
+    #[sol(rpc)]
+    "contracts/src/interfaces/IUniversalRouter.sol"
+);
@@ -0,0 +1,194 @@
+use alloy::{
+    network::Network,
+    primitives::{Address, FixedBytes},
+    providers::Provider,
+    sol,
+    sol_types::{SolInterface, SolValue},
+    transports::Transport,
+};
+use eyre::Result;
+
+use crate::simulation::revert;
+
+use super::{
+    artifact::{Artifact, Deployment},
+    executor::Executor,
+};
+
+sol! {
+    #[sol(rpc, all_derives)]
+    contract Whitelist {
+        error UnauthorizedPool(address expected, address actual);
+
+        function uniswapFactory() external view returns (address);
+        function checkWhitelist(address pool) external view returns (bool);
+        function addWhitelist(address pool) external returns (bool);
+        function removeWhitelist(address pool) external returns (bool);
+    }
+}
+
+pub use Whitelist::WhitelistErrors;
+
+/// Deploy a `Whitelist` which accepts the pools of `factory`.
+///
+/// # Arguments
+///
+/// * `provider` - A provider with a wallet to deploy from.
+/// * `owner` - The address allowed to change the whitelist.
+/// * `factory` - The Uniswap V3 factory pools are checked against.
+///
+/// # Returns
+///
+/// * `Result<Deployment>` - The whitelist and the transaction it was deployed in.
+pub async fn deploy<P, T, N>(provider: &P, owner: Address, factory: Address) -> Result<Deployment>
+where
+    P: Provider<T, N>,
+    T: Transport + Clone,
+    N: Network,
+{
+    Artifact::load("Whitelist")?
+        .deploy_tracked(provider, &(owner, factory).abi_encode_params())
+        .await
+}
+
+/// Check if a pool is whitelisted.
+pub async fn check_whitelist<P, T, N>(
+    provider: &P,
+    whitelist: Address,
+    pool: Address,
+) -> Result<bool>
+where
+    P: Provider<T, N>,
+    T: Transport + Clone,
+    N: Network,
+{
+    Ok(Whitelist::new(whitelist, provider)
+        .checkWhitelist(pool)
+        .call()
+        .await?
+        ._0)
+}
+
+/// Whitelist a pool, after checking that the factory deployed it.
+///
+/// # Arguments
+///
+/// * `provider` - A provider with the owner's wallet.
+/// * `whitelist` - The whitelist address.
+/// * `pool` - The pool to whitelist.
+///
+/// # Returns
+///
+/// * `Result<FixedBytes<32>>` - The transaction hash.
+pub async fn add_whitelist<P, T, N>(
+    provider: &P,
+    whitelist: Address,
+    pool: Address,
+) -> Result<FixedBytes<32>>
+where
+    P: Provider<T, N>,
+    T: Transport + Clone,
+    N: Network,
+{
+    let contract = Whitelist::new(whitelist, provider);
+    let call = contract.addWhitelist(pool);
+
+    // Simulate first, so that a pool the factory did not deploy is reported as such
+    if let Err(e) = call.call().await {
+        let reason =
+            revert::revert_data(&e).and_then(|data| WhitelistErrors::abi_decode(&data, true).ok());
+        return Err(match reason {
+            Some(WhitelistErrors::UnauthorizedPool(err)) => eyre::eyre!(
+                "{} is not a pool of the factory, which expects {}",
+                err.actual,
+                err.expected
+            ),
+            None => e.into(),
+        });
+    }
+
+    Executor::new(provider, call.into_transaction_request())
+        .send()
+        .await
+}
+
+/// Remove a pool from the whitelist.
+pub async fn remove_whitelist<P, T, N>(
+    provider: &P,
+    whitelist: Address,
+    pool: Address,
+) -> Result<FixedBytes<32>>
+where
+    P: Provider<T, N>,
+    T: Transport + Clone,
+    N: Network,
+{
+    let tx = Whitelist::new(whitelist, provider)
+        .removeWhitelist(pool)
+        .into_transaction_request();
+
+    Executor::new(provider, tx).send().await
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{aliases::U24, U256};
+
+    use crate::utils::setup;
+
+    use super::*;
+
+    sol! {
+        #[sol(rpc)]
+        interface IMockUniswapV3Factory {
+            function setPool(address token0, address token1, uint24 fee, address pool) external;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_whitelist() {
+        let provider = setup::setup_provider_with_anvil(None).await.unwrap();
+        let owner = provider.get_accounts().await.unwrap()[0];
+
+        let (token0, token1, fee) = (
+            Address::repeat_byte(0x0a),
+            Address::repeat_byte(0x0b),
+            500u32,
+        );
+        let factory = Artifact::load("MockUniswapV3Factory")
+            .unwrap()
+            .deploy(&provider, &[])
+            .await
+            .unwrap();
+        // A uint24 is encoded as a full word like any other integer
+        let pool = Artifact::load("FakeUniswapV3Pool")
+            .unwrap()
+            .deploy(
+                &provider,
+                &(token0, token1, U256::from(fee)).abi_encode_params(),
+            )
+            .await
+            .unwrap();
+        let whitelist = deploy(&provider, owner, factory).await.unwrap().address;
+
+        // The factory has not deployed the pool
+        let err = add_whitelist(&provider, whitelist, pool).await.unwrap_err();
+        assert!(err.to_string().contains("is not a pool of the factory"));
+        assert!(!check_whitelist(&provider, whitelist, pool).await.unwrap());
+
+        IMockUniswapV3Factory::new(factory, &provider)
+            .setPool(token0, token1, U24::from(fee), pool)
+            .send()
+            .await
+            .unwrap()
+            .watch()
+            .await
+            .unwrap();
+
+        add_whitelist(&provider, whitelist, pool).await.unwrap();
+        assert!(check_whitelist(&provider, whitelist, pool).await.unwrap());
+
+        remove_whitelist(&provider, whitelist, pool).await.unwrap();
+        assert!(!check_whitelist(&provider, whitelist, pool).await.unwrap());
+    }
+}
@@ -1,7 +1,11 @@
+pub mod bot;
 pub mod config;
+pub mod deploy;
+pub mod dex;
 pub mod flashbots_share;
 pub mod interfaces;
 pub mod providers;
-mod simulation;
+pub mod simulation;
+pub mod uniswap_v3;
 pub mod utils;
 pub mod watcher;
@@ -1,6 +1,155 @@
+use std::{sync::Arc, time::Duration};
+
+use alloy::{
+    network::EthereumWallet,
+    providers::{Provider, ProviderBuilder},
+    pubsub::PubSubFrontend,
+    transports::http::reqwest::Url,
+};
 use eyre::Result;
 
+use jit_liquidity_rust::{
+    bot::jitbot::JitBot,
+    config::runconfig,
+    providers::alchemy::AlchemyProvider,
+    simulation::{statecache::StateCache, workerpool::WorkerPool},
+    watcher::{
+        connection::Connector,
+        membuilder::{create_ws_provider, MemPoolBuilder},
+        pendingpool::PendingPool,
+        shutdownconfig::ShutdownConfig,
+        subscribefilter::ShallowFilterType,
+    },
+};
+
+const BLOCK_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let config = runconfig::Config::load();
+
+    let rpc_url = config
+        .rpc_url
+        .ok_or_else(|| eyre::eyre!("RPC_URL must be set"))?;
+    let ws_url = config
+        .rpc_url_ws
+        .ok_or_else(|| eyre::eyre!("RPC_WS_URL must be set"))?;
+    let executor = config
+        .executor
+        .ok_or_else(|| eyre::eyre!("EXECUTOR_ADDRESS must be set"))?;
+    let flashbot_signer = config
+        .flashbot_signer
+        .ok_or_else(|| eyre::eyre!("FLASHBOT_PRIVATE_KEY must be set"))?;
+
+    // Providers for simulation and for the relay
+    let wallet = EthereumWallet::from(config.signer);
+    let provider = Arc::new(
+        ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(wallet.clone())
+            .on_http(Url::parse(&rpc_url)?),
+    );
+    let flashbot_provider = Arc::new(
+        ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(EthereumWallet::from(flashbot_signer.clone()))
+            .on_http(Url::parse(&config.relay_url)?),
+    );
+
+    // Alchemy requires its own subscription for full pending transactions, the
+    // connector is called again to reconnect whenever the connection drops
+    let ws_alchemy = config.ws_alchemy;
+    let connector: Connector = Arc::new(move || {
+        let ws_url = ws_url.clone();
+        Box::pin(async move {
+            let ws_provider = create_ws_provider(&ws_url).await?;
+            let ws_provider: Arc<dyn Provider<PubSubFrontend>> = if ws_alchemy {
+                Arc::new(AlchemyProvider::new(ws_provider))
+            } else {
+                ws_provider
+            };
+            Ok(ws_provider)
+        })
+    });
+
+    let shutdown_config = ShutdownConfig::default();
+    let pending_pool = Arc::new(PendingPool::default());
+    let mempool = MemPoolBuilder::default()
+        .with_connector(connector)
+        .with_pending_pool(pending_pool.clone())
+        .with_shutdown_config(shutdown_config.clone())
+        .build()
+        .await?;
+
+    // Report the feed going down and coming back
+    let mut health = mempool.health();
+    tokio::spawn(async move {
+        while let Some(state) = health.changed().await {
+            println!("Mempool connection: {:?}", state);
+        }
+    });
+
+    // Every pending transaction is simulated, the bundler discards anything without a V3 swap
+    let (handle, recv, shutdown) = mempool.subscribe(ShallowFilterType::None).await?;
+    let metrics = mempool.metrics();
+
+    // Shutdown cleanly on ctrl-c
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            println!("Shutting down...");
+            shutdown.shutdown();
+        }
+    });
+
+    // Learn which pending transactions are mined, replaced or dropped
+    {
+        let pending_pool = pending_pool.clone();
+        let provider = provider.clone();
+        let shutdown_config = shutdown_config.clone();
+        tokio::spawn(async move {
+            if let Err(e) = pending_pool
+                .track_blocks(provider.as_ref(), BLOCK_POLL_INTERVAL, shutdown_config)
+                .await
+            {
+                println!("Stopped tracking blocks: {:?}", e);
+            }
+        });
+    }
+
+    // Reuse state fetched by a previous run, bytecode stays valid across blocks
+    let state_cache = Arc::new(match &config.state_cache_path {
+        Some(path) if path.exists() => StateCache::load(path)?,
+        _ => StateCache::new(),
+    });
+
+    let bot = JitBot::new(
+        provider,
+        flashbot_provider,
+        wallet,
+        flashbot_signer,
+        executor,
+        config.target_blocks,
+        shutdown_config,
+    )
+    .with_state_cache(state_cache.clone())
+    .with_hot_pools(config.hot_pools)
+    .with_pending_pool(pending_pool)
+    .with_worker_pool(
+        config
+            .simulation_workers
+            .map_or_else(WorkerPool::default, WorkerPool::new),
+    );
+    bot.run(recv).await?;
+
+    if let Some(path) = &config.state_cache_path {
+        state_cache.persist(path)?;
+    }
+
+    // Wait for the mempool to finish
+    let _ = handle.await;
+    println!("Mempool: {}", metrics);
+
+    println!("Done!");
+
     Ok(())
 }
@@ -0,0 +1,341 @@
+use std::{collections::HashSet, marker::PhantomData, sync::Arc};
+
+use alloy::{
+    eips::{BlockId, BlockNumberOrTag},
+    network::{Ethereum, Network},
+    primitives::{Address, I256},
+    providers::Provider,
+    rpc::types::TransactionRequest,
+    transports::{BoxTransport, Transport},
+};
+use eyre::Result;
+use futures_util::future::join_all;
+use revm::{
+    db::CacheDB,
+    primitives::{BlockEnv, CfgEnv},
+    DatabaseRef,
+};
+
+use super::{
+    engine::{self, BundleSimulation},
+    statecache::{StateCache, StateCacheDB},
+    tracing,
+    workerpool::WorkerPool,
+};
+
+/// An independent bundle competing with other candidates for the same block.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub bundle: Vec<TransactionRequest>,
+    /// Tokens whose balance of the tracked account is reported, `Address::ZERO` tracks ETH
+    pub tokens: Vec<Address>,
+    /// Indices of transactions whose state is committed even if they revert
+    pub can_revert: HashSet<usize>,
+}
+
+impl Candidate {
+    pub fn new(bundle: Vec<TransactionRequest>, tokens: Vec<Address>) -> Self {
+        Self {
+            bundle,
+            tokens,
+            can_revert: HashSet::new(),
+        }
+    }
+
+    pub fn with_can_revert(mut self, index: usize) -> Self {
+        self.can_revert.insert(index);
+        self
+    }
+}
+
+/// Simulation of a single candidate, see `BatchSimulator::simulate`.
+#[derive(Debug)]
+pub struct RankedSimulation {
+    /// Position of the candidate in the batch
+    pub index: usize,
+    pub simulation: Result<BundleSimulation>,
+    /// Only set if every transaction in the bundle succeeded
+    pub profit: Option<I256>,
+}
+
+/// Profit measured as the change in the tracked account's balance of `token`.
+pub fn token_profit(token: Address) -> impl Fn(&BundleSimulation) -> Option<I256> + Send + Sync {
+    move |simulation| simulation.delta(token)
+}
+
+/// Simulates many independent candidate bundles on top of the same state.
+///
+/// Every candidate runs on its own copy-on-write fork of one base database, so
+/// candidates cannot see each other's changes while state fetched over RPC is
+/// shared between all of them.
+pub struct BatchSimulator<P, T = BoxTransport, N = Ethereum>
+where
+    P: Provider<T, N>,
+    T: Transport + Clone,
+    N: Network,
+{
+    provider: Arc<P>,
+    // Account whose balances determine the profit of a candidate
+    account: Address,
+    block: BlockId,
+    block_env: BlockEnv,
+    state_cache: Option<Arc<StateCache>>,
+    worker_pool: WorkerPool,
+    _marker: PhantomData<(T, N)>,
+}
+
+impl<P, T, N> BatchSimulator<P, T, N>
+where
+    P: Provider<T, N>,
+    T: Transport + Clone,
+    N: Network,
+{
+    pub fn new(provider: Arc<P>, account: Address) -> Self {
+        Self {
+            provider,
+            account,
+            block: BlockNumberOrTag::Latest.into(),
+            block_env: BlockEnv::default(),
+            state_cache: None,
+            worker_pool: WorkerPool::default(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Execute the candidates on top of the state at the end of `block`.
+    pub fn with_block(mut self, block: BlockId) -> Self {
+        self.block = block;
+        self
+    }
+
+    /// Set the block environment the candidates execute in.
+    pub fn with_block_env(mut self, block_env: BlockEnv) -> Self {
+        self.block_env = block_env;
+        self
+    }
+
+    /// Share fetched state with other simulations, see `EngineTask::with_state_cache`.
+    pub fn with_state_cache(mut self, cache: Arc<StateCache>) -> Self {
+        self.state_cache = Some(cache);
+        self
+    }
+
+    /// Run the candidates on `pool`, which bounds how many are simulated in parallel.
+    pub fn with_worker_pool(mut self, pool: WorkerPool) -> Self {
+        self.worker_pool = pool;
+        self
+    }
+
+    /// Base database the candidates fork, reading through the shared state cache.
+    fn base_db(&self) -> CacheDB<StateCacheDB<T, N, P>> {
+        let cache = match (&self.state_cache, self.block) {
+            (Some(cache), BlockId::Number(BlockNumberOrTag::Number(number))) => {
+                (cache.clone(), number)
+            }
+            // Without a pinned block the forks still share what they fetch during the batch
+            _ => {
+                let cache = Arc::new(StateCache::new());
+                cache.advance(0);
+                (cache, 0)
+            }
+        };
+        CacheDB::new(StateCacheDB::new(
+            self.provider.clone(),
+            self.block,
+            Some(cache),
+        ))
+    }
+
+    /// Simulate every candidate in isolation on the calling thread and rank them by profit.
+    ///
+    /// # Arguments
+    ///
+    /// * `candidates` - The candidate bundles.
+    /// * `profit` - Scores a successful simulation, e.g. `token_profit`.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<RankedSimulation>` - The simulations, most profitable first and failed candidates last.
+    pub fn simulate<F>(&self, candidates: Vec<Candidate>, profit: F) -> Vec<RankedSimulation>
+    where
+        F: Fn(&BundleSimulation) -> Option<I256>,
+    {
+        let base = self.base_db();
+        // Without the chain id only transactions which do not commit to a chain execute
+        let cfg_env = tracing::fetch_cfg_env_blocking(self.provider.as_ref()).unwrap_or_else(|e| {
+            println!("Could not fetch the chain id: {:?}", e);
+            CfgEnv::default()
+        });
+        let ranked = candidates
+            .into_iter()
+            .enumerate()
+            .map(|(index, candidate)| {
+                simulate_candidate(
+                    &base,
+                    &cfg_env,
+                    &self.block_env,
+                    self.account,
+                    index,
+                    candidate,
+                    &profit,
+                )
+            })
+            .collect();
+        rank(ranked)
+    }
+}
+
+impl<P, T, N> BatchSimulator<P, T, N>
+where
+    P: Provider<T, N> + 'static,
+    T: Transport + Clone,
+    N: Network,
+{
+    /// Async version of `simulate` which runs every candidate as a job on the worker pool.
+    pub async fn simulate_async<F>(
+        self,
+        candidates: Vec<Candidate>,
+        profit: F,
+    ) -> Result<Vec<RankedSimulation>>
+    where
+        F: Fn(&BundleSimulation) -> Option<I256> + Send + Sync + 'static,
+    {
+        let base = Arc::new(self.base_db());
+        let cfg_env = tracing::fetch_cfg_env(self.provider.as_ref()).await?;
+        let profit = Arc::new(profit);
+
+        let jobs = candidates
+            .into_iter()
+            .enumerate()
+            .map(|(index, candidate)| {
+                let base = base.clone();
+                let cfg_env = cfg_env.clone();
+                let block_env = self.block_env.clone();
+                let account = self.account;
+                let profit = profit.clone();
+                self.worker_pool.spawn(move || {
+                    simulate_candidate(
+                        base.as_ref(),
+                        &cfg_env,
+                        &block_env,
+                        account,
+                        index,
+                        candidate,
+                        profit.as_ref(),
+                    )
+                })
+            });
+
+        let ranked = join_all(jobs).await.into_iter().collect::<Result<_>>()?;
+        Ok(rank(ranked))
+    }
+}
+
+/// Simulate a candidate on its own fork of `base`.
+fn simulate_candidate<DB, F>(
+    base: &CacheDB<DB>,
+    cfg_env: &CfgEnv,
+    block_env: &BlockEnv,
+    account: Address,
+    index: usize,
+    candidate: Candidate,
+    profit: &F,
+) -> RankedSimulation
+where
+    DB: DatabaseRef,
+    DB::Error: std::fmt::Debug,
+    F: Fn(&BundleSimulation) -> Option<I256>,
+{
+    // Writes stay in the fork, reads fall through to the shared base
+    let mut fork = CacheDB::new(base);
+    let simulation = engine::simulate_on(
+        &mut fork,
+        cfg_env,
+        block_env,
+        candidate.bundle,
+        &candidate.can_revert,
+        account,
+        &candidate.tokens,
+    );
+
+    let profit = simulation
+        .as_ref()
+        .ok()
+        .filter(|simulation| simulation.is_success())
+        .and_then(profit);
+
+    RankedSimulation {
+        index,
+        simulation,
+        profit,
+    }
+}
+
+/// Order simulations by profit, most profitable first and failed candidates last.
+fn rank(mut ranked: Vec<RankedSimulation>) -> Vec<RankedSimulation> {
+    // `None` orders before any profit, so failed candidates end up last
+    ranked.sort_by(|a, b| b.profit.cmp(&a.profit).then(a.index.cmp(&b.index)));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use revm::primitives::{TxKind, U256};
+
+    use crate::utils::{addresses, setup};
+
+    use super::*;
+
+    fn wrap(from: Address, value: u64) -> TransactionRequest {
+        TransactionRequest {
+            from: Some(from),
+            to: Some(TxKind::Call(*addresses::WETH)),
+            value: Some(U256::from(value)),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_batch_simulate() {
+        let (provider, client) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+
+        let weth = *addresses::WETH;
+        let usdc = *addresses::USDC_ADDR;
+
+        let candidates = vec![
+            Candidate::new(vec![wrap(client, 100)], vec![weth]),
+            Candidate::new(vec![wrap(client, 300), wrap(client, 200)], vec![weth]),
+            // Our client holds no USDC, so this candidate reverts
+            Candidate::new(
+                vec![TransactionRequest {
+                    from: Some(client),
+                    to: Some(TxKind::Call(usdc)),
+                    value: Some(U256::from(1)),
+                    ..Default::default()
+                }],
+                vec![weth],
+            ),
+            Candidate::new(vec![wrap(client, 250)], vec![weth]),
+        ];
+
+        let ranked = BatchSimulator::new(provider, client)
+            .with_worker_pool(WorkerPool::new(2))
+            .simulate_async(candidates, token_profit(weth))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            ranked.iter().map(|r| r.index).collect::<Vec<_>>(),
+            vec![1, 3, 0, 2]
+        );
+
+        // Candidates are isolated, the second one only sees its own wraps
+        assert_eq!(ranked[0].profit, Some(I256::try_from(500).unwrap()));
+        assert_eq!(ranked[1].profit, Some(I256::try_from(250).unwrap()));
+        assert_eq!(ranked[2].profit, Some(I256::try_from(100).unwrap()));
+
+        assert_eq!(ranked[3].profit, None);
+        assert!(!ranked[3].simulation.as_ref().unwrap().is_success());
+    }
+}
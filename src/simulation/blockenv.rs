@@ -0,0 +1,143 @@
+use alloy::{
+    consensus::Header,
+    eips::{
+        eip1559::{calc_next_block_base_fee, BaseFeeParams},
+        eip4844::calc_excess_blob_gas,
+        BlockNumberOrTag,
+    },
+    network::Ethereum,
+    providers::Provider,
+    rpc::types::BlockTransactionsKind,
+    transports::Transport,
+};
+use eyre::{eyre, Result};
+use revm::primitives::{BlobExcessGasAndPrice, BlockEnv, U256};
+
+// Seconds between two consecutive slots on mainnet
+const SLOT_TIME: u64 = 12;
+
+/// Synthesize the block environment of the block following `parent`.
+///
+/// The base fee and excess blob gas follow from the parent, while the timestamp
+/// assumes no missed slots. The next prevrandao is unknown ahead of time, so the
+/// parent's is reused.
+///
+/// # Arguments
+///
+/// * `parent` - The header of the parent block.
+///
+/// # Returns
+///
+/// * `BlockEnv` - The block environment of the next block.
+pub fn next_block_env(parent: &Header) -> BlockEnv {
+    let basefee = parent.base_fee_per_gas.map_or(0, |base_fee| {
+        calc_next_block_base_fee(
+            parent.gas_used,
+            parent.gas_limit,
+            base_fee,
+            BaseFeeParams::ethereum(),
+        )
+    });
+
+    let blob_excess_gas_and_price =
+        parent
+            .excess_blob_gas
+            .zip(parent.blob_gas_used)
+            .map(|(excess, used)| {
+                BlobExcessGasAndPrice::new(calc_excess_blob_gas(excess, used), false)
+            });
+
+    BlockEnv {
+        number: U256::from(parent.number + 1),
+        coinbase: parent.beneficiary,
+        timestamp: U256::from(parent.timestamp + SLOT_TIME),
+        gas_limit: U256::from(parent.gas_limit),
+        basefee: U256::from(basefee),
+        difficulty: U256::ZERO,
+        prevrandao: Some(parent.mix_hash),
+        blob_excess_gas_and_price,
+    }
+}
+
+/// Fetch a parent block and synthesize the block environment of the block after it.
+///
+/// # Arguments
+///
+/// * `provider` - A reference to the provider.
+/// * `parent` - The parent block.
+///
+/// # Returns
+///
+/// * `Result<BlockEnv>` - The block environment of the next block.
+pub async fn fetch_next_block_env<T, P>(provider: &P, parent: BlockNumberOrTag) -> Result<BlockEnv>
+where
+    T: Transport + Clone,
+    P: Provider<T, Ethereum>,
+{
+    let block = provider
+        .get_block_by_number(parent, BlockTransactionsKind::Hashes)
+        .await
+        .map_err(|e| eyre!("GET_BLOCK_BY_NUMBER failed: {:?}", e))?
+        .ok_or_else(|| eyre!("Block not found"))?;
+
+    Ok(next_block_env(&block.header.inner))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Address, B256};
+
+    use super::*;
+
+    const GAS_LIMIT: u64 = 30_000_000;
+    const BASE_FEE: u64 = 10_000_000_000;
+
+    fn parent(gas_used: u64) -> Header {
+        Header {
+            number: 100,
+            timestamp: 1_000,
+            gas_limit: GAS_LIMIT,
+            gas_used,
+            base_fee_per_gas: Some(BASE_FEE),
+            beneficiary: Address::repeat_byte(0x11),
+            mix_hash: B256::repeat_byte(0x22),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_next_block_env_fields() {
+        let env = next_block_env(&parent(GAS_LIMIT / 2));
+
+        assert_eq!(env.number, U256::from(101));
+        assert_eq!(env.timestamp, U256::from(1_000 + SLOT_TIME));
+        assert_eq!(env.gas_limit, U256::from(GAS_LIMIT));
+        assert_eq!(env.coinbase, Address::repeat_byte(0x11));
+        assert_eq!(env.prevrandao, Some(B256::repeat_byte(0x22)));
+        assert!(env.blob_excess_gas_and_price.is_none());
+    }
+
+    #[test]
+    fn test_next_block_base_fee() {
+        // Base fee is unchanged at the gas target
+        let env = next_block_env(&parent(GAS_LIMIT / 2));
+        assert_eq!(env.basefee, U256::from(BASE_FEE));
+
+        // Base fee rises by 12.5% on a full block
+        let env = next_block_env(&parent(GAS_LIMIT));
+        assert_eq!(env.basefee, U256::from(BASE_FEE + BASE_FEE / 8));
+
+        // Base fee falls by 12.5% on an empty block
+        let env = next_block_env(&parent(0));
+        assert_eq!(env.basefee, U256::from(BASE_FEE - BASE_FEE / 8));
+    }
+
+    #[test]
+    fn test_next_block_pre_london() {
+        let mut header = parent(GAS_LIMIT);
+        header.base_fee_per_gas = None;
+
+        let env = next_block_env(&header);
+        assert_eq!(env.basefee, U256::ZERO);
+    }
+}
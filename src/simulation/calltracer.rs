@@ -0,0 +1,310 @@
+use std::collections::HashSet;
+
+use revm::{
+    interpreter::{CallInputs, CallOutcome, CallScheme, CreateInputs, CreateOutcome, Interpreter},
+    primitives::{Address, Bytes, FixedBytes, Log, U256},
+    Database, EvmContext, Inspector,
+};
+
+/// Kind of a call frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    Call,
+    StaticCall,
+    DelegateCall,
+    CallCode,
+    Create,
+}
+
+/// A single frame in the call tree of a simulated transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallFrame {
+    pub kind: FrameKind,
+    pub from: Address,
+    /// Account whose storage is used, the created contract for creates
+    pub to: Address,
+    /// Account whose code is executed, differs from `to` for delegate calls
+    pub code_address: Address,
+    pub input: Bytes,
+    pub value: U256,
+    /// Depth of the frame, the top level call has depth 0
+    pub depth: usize,
+    pub success: bool,
+    pub gas_used: u64,
+    /// Logs emitted directly by this frame, also kept if the frame reverted
+    pub logs: Vec<Log>,
+    pub calls: Vec<CallFrame>,
+}
+
+impl CallFrame {
+    /// First four bytes of the input, if any.
+    pub fn selector(&self) -> Option<FixedBytes<4>> {
+        self.input.get(..4).map(FixedBytes::from_slice)
+    }
+
+    /// Iterate over this frame and all of its subcalls, depth first.
+    pub fn frames(&self) -> Vec<&CallFrame> {
+        let mut frames = vec![self];
+        for call in &self.calls {
+            frames.extend(call.frames());
+        }
+        frames
+    }
+
+    /// Every account called anywhere in the tree, including this frame.
+    pub fn touched_addresses(&self) -> HashSet<Address> {
+        self.frames().into_iter().map(|frame| frame.to).collect()
+    }
+
+    /// Logs of all frames which succeeded, and whose parents did, in emission order.
+    ///
+    /// Logs are returned per frame depth first, which matches emission order
+    /// as long as a frame does not log both before and after a subcall.
+    pub fn logs(&self) -> Vec<&Log> {
+        if !self.success {
+            return Vec::new();
+        }
+
+        let mut logs = self.logs.iter().collect::<Vec<_>>();
+        for call in &self.calls {
+            logs.extend(call.logs());
+        }
+        logs
+    }
+
+    /// Successful logs with the given first topic, e.g. a `Swap` event signature.
+    pub fn logs_with_topic(&self, topic: FixedBytes<32>) -> Vec<&Log> {
+        self.logs()
+            .into_iter()
+            .filter(|log| log.topics().first() == Some(&topic))
+            .collect()
+    }
+}
+
+/// Inspector which records the call tree of a transaction.
+#[derive(Debug, Default)]
+pub struct CallTracer {
+    // Frames which have not returned yet, the last one is executing
+    stack: Vec<CallFrame>,
+    root: Option<CallFrame>,
+}
+
+impl CallTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The top level frame, only set once the transaction finished.
+    pub fn into_root(self) -> Option<CallFrame> {
+        self.root
+    }
+
+    fn push(&mut self, kind: FrameKind, from: Address, to: Address, input: Bytes, value: U256) {
+        self.stack.push(CallFrame {
+            kind,
+            from,
+            to,
+            code_address: to,
+            input,
+            value,
+            depth: self.stack.len(),
+            success: false,
+            gas_used: 0,
+            logs: Vec::new(),
+            calls: Vec::new(),
+        });
+    }
+
+    fn pop(&mut self, success: bool, gas_used: u64) -> Option<&mut CallFrame> {
+        let mut frame = self.stack.pop()?;
+        frame.success = success;
+        frame.gas_used = gas_used;
+
+        let calls = match self.stack.last_mut() {
+            Some(parent) => &mut parent.calls,
+            None => return Some(self.root.insert(frame)),
+        };
+        calls.push(frame);
+        calls.last_mut()
+    }
+}
+
+impl<DB: Database> Inspector<DB> for CallTracer {
+    fn log(&mut self, _interp: &mut Interpreter, _context: &mut EvmContext<DB>, log: &Log) {
+        if let Some(frame) = self.stack.last_mut() {
+            frame.logs.push(log.clone());
+        }
+    }
+
+    fn call(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        let kind = match inputs.scheme {
+            CallScheme::StaticCall => FrameKind::StaticCall,
+            CallScheme::DelegateCall => FrameKind::DelegateCall,
+            CallScheme::CallCode => FrameKind::CallCode,
+            _ => FrameKind::Call,
+        };
+
+        self.push(
+            kind,
+            inputs.caller,
+            inputs.target_address,
+            inputs.input.clone(),
+            inputs.call_value(),
+        );
+        if let Some(frame) = self.stack.last_mut() {
+            frame.code_address = inputs.bytecode_address;
+        }
+
+        None
+    }
+
+    fn call_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CallInputs,
+        outcome: CallOutcome,
+    ) -> CallOutcome {
+        self.pop(outcome.result.is_ok(), outcome.result.gas.spent());
+        outcome
+    }
+
+    fn create(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        // The address is only known once the contract is created
+        self.push(
+            FrameKind::Create,
+            inputs.caller,
+            Address::ZERO,
+            inputs.init_code.clone(),
+            inputs.value,
+        );
+
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        _inputs: &CreateInputs,
+        outcome: CreateOutcome,
+    ) -> CreateOutcome {
+        if let Some(frame) = self.pop(outcome.result.is_ok(), outcome.result.gas.spent()) {
+            let address = outcome.address.unwrap_or_default();
+            frame.to = address;
+            frame.code_address = address;
+        }
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(to: Address, success: bool, logs: Vec<Log>, calls: Vec<CallFrame>) -> CallFrame {
+        CallFrame {
+            kind: FrameKind::Call,
+            from: Address::ZERO,
+            to,
+            code_address: to,
+            input: Bytes::from_static(&[0xde, 0xad, 0xbe, 0xef, 0x00]),
+            value: U256::ZERO,
+            depth: 0,
+            success,
+            gas_used: 0,
+            logs,
+            calls,
+        }
+    }
+
+    fn log(address: Address, topic: FixedBytes<32>) -> Log {
+        Log::new_unchecked(address, vec![topic], Bytes::new())
+    }
+
+    #[test]
+    fn test_call_frame_tree() {
+        let router = Address::repeat_byte(0x01);
+        let pool = Address::repeat_byte(0x02);
+        let token = Address::repeat_byte(0x03);
+        let reverted = Address::repeat_byte(0x04);
+
+        let swap = FixedBytes::repeat_byte(0xaa);
+        let transfer = FixedBytes::repeat_byte(0xbb);
+
+        let root = frame(
+            router,
+            true,
+            Vec::new(),
+            vec![
+                frame(
+                    pool,
+                    true,
+                    vec![log(pool, swap)],
+                    vec![frame(token, true, vec![log(token, transfer)], Vec::new())],
+                ),
+                frame(reverted, false, vec![log(reverted, swap)], Vec::new()),
+            ],
+        );
+
+        assert_eq!(
+            root.selector(),
+            Some(FixedBytes::from([0xde, 0xad, 0xbe, 0xef]))
+        );
+        assert_eq!(root.frames().len(), 4);
+        assert_eq!(
+            root.touched_addresses(),
+            HashSet::from([router, pool, token, reverted])
+        );
+
+        // Logs of the reverted frame are discarded
+        let logs = root.logs();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].address, pool);
+        assert_eq!(logs[1].address, token);
+
+        let swaps = root.logs_with_topic(swap);
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(swaps[0].address, pool);
+    }
+
+    #[test]
+    fn test_call_tracer_nesting() {
+        let mut tracer = CallTracer::new();
+        let outer = Address::repeat_byte(0x01);
+        let inner = Address::repeat_byte(0x02);
+
+        tracer.push(
+            FrameKind::Call,
+            Address::ZERO,
+            outer,
+            Bytes::new(),
+            U256::ZERO,
+        );
+        tracer.push(
+            FrameKind::StaticCall,
+            outer,
+            inner,
+            Bytes::new(),
+            U256::ZERO,
+        );
+        tracer.pop(false, 100);
+        tracer.pop(true, 500);
+
+        let root = tracer.into_root().unwrap();
+        assert_eq!(root.to, outer);
+        assert!(root.success);
+        assert_eq!(root.gas_used, 500);
+        assert_eq!(root.calls.len(), 1);
+        assert_eq!(root.calls[0].to, inner);
+        assert_eq!(root.calls[0].depth, 1);
+        assert_eq!(root.calls[0].kind, FrameKind::StaticCall);
+        assert!(!root.calls[0].success);
+    }
+}
@@ -1,20 +1,79 @@
-use std::{marker::PhantomData, sync::Arc};
+use std::{collections::HashSet, fmt::Debug, marker::PhantomData, sync::Arc};
 
 use alloy::{
-    eips::BlockNumberOrTag,
+    eips::{BlockId, BlockNumberOrTag},
     network::{Ethereum, Network},
+    primitives::{Address, I256, U256},
     providers::Provider,
-    rpc::types::TransactionRequest,
+    rpc::types::{TransactionInput, TransactionRequest},
+    sol_types::SolCall,
     transports::{BoxTransport, Transport},
 };
-use revm::primitives::ResultAndState;
+use revm::{
+    db::CacheDB,
+    primitives::{BlockEnv, CfgEnv},
+    Database, DatabaseCommit,
+};
+use tokio::runtime::{Builder, Handle, RuntimeFlavor};
 
 use eyre::Result;
 
-use super::tracing;
+use crate::interfaces::erc20::IERC20Token;
+
+use super::{
+    engine_filter::EngineFilter,
+    outcome::TxOutcome,
+    statecache::{self, StateCache, StateCacheDB},
+    tracing,
+    workerpool::WorkerPool,
+};
+
+// Plenty for an ERC20 balanceOf, and below the gas limit of any block
+const BALANCE_OF_GAS_LIMIT: u64 = 100_000;
 
 type TransactionBundle = Vec<TransactionRequest>;
-pub type EngineResultBundle = Vec<Result<ResultAndState>>;
+pub type EngineResultBundle = Vec<Result<TxOutcome>>;
+
+/// Balance of an account before and after a bundle, `Address::ZERO` denotes ETH.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceDelta {
+    pub token: Address,
+    pub before: U256,
+    pub after: U256,
+}
+
+impl BalanceDelta {
+    pub fn delta(&self) -> I256 {
+        I256::from_raw(self.after) - I256::from_raw(self.before)
+    }
+}
+
+/// Result of simulating a full bundle on top of a single state.
+#[derive(Debug)]
+pub struct BundleSimulation {
+    pub results: EngineResultBundle,
+    pub deltas: Vec<BalanceDelta>,
+}
+
+impl BundleSimulation {
+    /// Whether every transaction in the bundle executed successfully.
+    ///
+    /// `Executor::finish` reverts if the fund manager reports a loss, so an
+    /// unprofitable JIT bundle also fails this check.
+    pub fn is_success(&self) -> bool {
+        self.results
+            .iter()
+            .all(|result| result.as_ref().is_ok_and(TxOutcome::is_success))
+    }
+
+    /// Net balance change of a token, `Address::ZERO` denotes ETH.
+    pub fn delta(&self, token: Address) -> Option<I256> {
+        self.deltas
+            .iter()
+            .find(|delta| delta.token == token)
+            .map(BalanceDelta::delta)
+    }
+}
 
 pub struct EngineTask<P, T = BoxTransport, N = Ethereum>
 where
@@ -24,6 +83,13 @@ where
 {
     provider: Arc<P>,
     bundle: TransactionBundle,
+    // Indices of transactions whose state is committed even if they revert
+    can_revert: HashSet<usize>,
+    // State which the bundle executes on top of
+    block: BlockId,
+    block_env: BlockEnv,
+    state_cache: Option<Arc<StateCache>>,
+    worker_pool: Option<WorkerPool>,
     // Cursed!
     _marker: PhantomData<(T, N)>,
 }
@@ -38,27 +104,329 @@ where
         Self {
             provider,
             bundle,
+            can_revert: HashSet::new(),
+            block: BlockNumberOrTag::Latest.into(),
+            block_env: BlockEnv::default(),
+            state_cache: None,
+            worker_pool: None,
             _marker: PhantomData,
         }
     }
 
+    /// Execute the bundle on top of the state at the end of `block`.
+    pub fn with_block(mut self, block: BlockId) -> Self {
+        self.block = block;
+        self
+    }
+
+    /// Set the block environment the bundle executes in, see `blockenv::next_block_env`.
+    pub fn with_block_env(mut self, block_env: BlockEnv) -> Self {
+        self.block_env = block_env;
+        self
+    }
+
+    /// Mark the transaction at `index` as allowed to revert, like a `can_revert` bundle item.
+    ///
+    /// Its state changes (nonce and gas payment) are committed even if it reverts.
+    pub fn with_can_revert(mut self, index: usize) -> Self {
+        self.can_revert.insert(index);
+        self
+    }
+
+    /// Share fetched state with other tasks through `cache`.
+    ///
+    /// Only used if the task is pinned to a block number with `with_block`.
+    pub fn with_state_cache(mut self, cache: Arc<StateCache>) -> Self {
+        self.state_cache = Some(cache);
+        self
+    }
+
+    /// Run the async simulation APIs on `pool` instead of tokio's unbounded blocking threads.
+    pub fn with_worker_pool(mut self, pool: WorkerPool) -> Self {
+        self.worker_pool = Some(pool);
+        self
+    }
+
+    fn init_db(&self) -> CacheDB<StateCacheDB<T, N, P>> {
+        statecache::init_state_cache_db(self.provider.clone(), self.block, self.state_cache.clone())
+    }
+
+    /// Fetch the chain configuration once for the whole bundle.
+    fn init_cfg_env(&self) -> Result<CfgEnv> {
+        tracing::fetch_cfg_env_blocking(self.provider.as_ref())
+    }
+
     pub fn consume(self) -> EngineResultBundle {
-        let mut cache_db =
-            tracing::init_cache_db(self.provider.clone(), BlockNumberOrTag::Latest.into());
+        self.consume_with(|_, results| results)
+    }
+
+    /// Execute the bundle and hand the results to `f` together with the state the
+    /// bundle left behind, e.g. to read contracts the bundle touched.
+    pub fn consume_with<F, R>(self, f: F) -> R
+    where
+        F: FnOnce(&mut CacheDB<StateCacheDB<T, N, P>>, EngineResultBundle) -> R,
+    {
+        let mut cache_db = self.init_db();
+        // Without the chain id only transactions which do not commit to a chain execute
+        let cfg_env = self.init_cfg_env().unwrap_or_else(|e| {
+            println!("Could not fetch the chain id: {:?}", e);
+            CfgEnv::default()
+        });
         let mut results = Vec::new();
-        for tx in self.bundle {
-            let result = tracing::revm_call_write(&mut cache_db, tx);
+        for (index, tx) in self.bundle.into_iter().enumerate() {
+            let result = execute(
+                &mut cache_db,
+                &cfg_env,
+                &self.block_env,
+                tx,
+                self.can_revert.contains(&index),
+            );
             results.push(result);
         }
-        results
+        f(&mut cache_db, results)
+    }
+
+    /// Execute the bundle and keep the outcomes which pass the filter.
+    ///
+    /// Transactions which could not be executed at all are dropped. Every outcome is
+    /// filtered on its own, so it stays paired with its index in the bundle.
+    pub fn consume_filtered<F: EngineFilter>(self, filter: &F) -> Vec<(usize, TxOutcome)> {
+        self.consume()
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, result)| Some((index, result.ok()?)))
+            .flat_map(|(index, outcome)| {
+                filter
+                    .filter(vec![outcome])
+                    .into_iter()
+                    .map(move |outcome| (index, outcome))
+            })
+            .collect()
+    }
+
+    /// Simulate the whole bundle on one state and track the balances of an account.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The account to track, usually our executor.
+    /// * `tokens` - The ERC20 tokens to track, `Address::ZERO` tracks ETH.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<BundleSimulation>` - The per transaction results and balance deltas.
+    pub fn simulate_bundle(
+        self,
+        account: Address,
+        tokens: Vec<Address>,
+    ) -> Result<BundleSimulation> {
+        let mut cache_db = self.init_db();
+        let cfg_env = self.init_cfg_env()?;
+        simulate_on(
+            &mut cache_db,
+            &cfg_env,
+            &self.block_env,
+            self.bundle,
+            &self.can_revert,
+            account,
+            &tokens,
+        )
+    }
+}
+
+impl<P, T, N> EngineTask<P, T, N>
+where
+    P: Provider<T, N> + 'static,
+    T: Transport + Clone,
+    N: Network,
+{
+    /// Run blocking work off the async runtime, on the worker pool if one is set.
+    ///
+    /// Works on either runtime flavor, see `with_blocking_runtime`.
+    async fn offload<F, R>(self, f: F) -> Result<R>
+    where
+        F: FnOnce(Self) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let pool = self.worker_pool.clone();
+        let work = move || with_blocking_runtime(move || f(self));
+        match pool {
+            Some(pool) => pool.spawn(work).await?,
+            None => tokio::task::spawn_blocking(work).await?,
+        }
+    }
+
+    /// Async version of `consume` which does not block the runtime.
+    pub async fn consume_async(self) -> Result<EngineResultBundle> {
+        self.offload(Self::consume).await
+    }
+
+    /// Async version of `consume_with` which does not block the runtime.
+    pub async fn consume_with_async<F, R>(self, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut CacheDB<StateCacheDB<T, N, P>>, EngineResultBundle) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        self.offload(move |task| task.consume_with(f)).await
+    }
+
+    /// Async version of `simulate_bundle` which does not block the runtime.
+    pub async fn simulate_bundle_async(
+        self,
+        account: Address,
+        tokens: Vec<Address>,
+    ) -> Result<BundleSimulation> {
+        self.offload(move |task| task.simulate_bundle(account, tokens))
+            .await?
     }
 }
 
+/// Run `f` in a runtime context `AlloyDB` can block on.
+///
+/// `AlloyDB` drives its RPC calls through the current runtime, which has to be
+/// multi-threaded. On a current-thread runtime, `f` runs in a runtime of its own.
+///
+/// # Arguments
+///
+/// * `f` - The blocking work, called off the async workers.
+///
+/// # Returns
+///
+/// * `Result<R>` - The result of `f`, or an error if no runtime could be built.
+fn with_blocking_runtime<F, R>(f: F) -> Result<R>
+where
+    F: FnOnce() -> R,
+{
+    let current_thread = Handle::try_current()
+        .is_ok_and(|handle| handle.runtime_flavor() == RuntimeFlavor::CurrentThread);
+    if !current_thread {
+        return Ok(f());
+    }
+
+    let runtime = Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()?;
+    let _guard = runtime.enter();
+    Ok(f())
+}
+
+/// Simulate a bundle on top of `cache_db` and track the balances of an account.
+pub(super) fn simulate_on<DB>(
+    cache_db: &mut DB,
+    cfg_env: &CfgEnv,
+    block_env: &BlockEnv,
+    bundle: TransactionBundle,
+    can_revert: &HashSet<usize>,
+    account: Address,
+    tokens: &[Address],
+) -> Result<BundleSimulation>
+where
+    DB: Database + DatabaseCommit,
+    DB::Error: Debug,
+{
+    let before = fetch_balances(cache_db, cfg_env, block_env, account, tokens)?;
+
+    let results = bundle
+        .into_iter()
+        .enumerate()
+        .map(|(index, tx)| {
+            execute(
+                cache_db,
+                cfg_env,
+                block_env,
+                tx,
+                can_revert.contains(&index),
+            )
+        })
+        .collect::<EngineResultBundle>();
+
+    let after = fetch_balances(cache_db, cfg_env, block_env, account, tokens)?;
+
+    let deltas = tokens
+        .iter()
+        .zip(before.into_iter().zip(after))
+        .map(|(token, (before, after))| BalanceDelta {
+            token: *token,
+            before,
+            after,
+        })
+        .collect();
+
+    Ok(BundleSimulation { results, deltas })
+}
+
+fn execute<DB>(
+    cache_db: &mut DB,
+    cfg_env: &CfgEnv,
+    block_env: &BlockEnv,
+    tx: TransactionRequest,
+    can_revert: bool,
+) -> Result<TxOutcome>
+where
+    DB: Database + DatabaseCommit,
+    DB::Error: Debug,
+{
+    if can_revert {
+        tracing::revm_call_write_can_revert(cache_db, cfg_env, block_env, tx)
+    } else {
+        tracing::revm_call_write(cache_db, cfg_env, block_env, tx)
+    }
+}
+
+/// Read the balances of an account from the simulated state, `Address::ZERO` reads ETH.
+fn fetch_balances<DB>(
+    cache_db: &mut DB,
+    cfg_env: &CfgEnv,
+    block_env: &BlockEnv,
+    account: Address,
+    tokens: &[Address],
+) -> Result<Vec<U256>>
+where
+    DB: Database + DatabaseCommit,
+    DB::Error: Debug,
+{
+    // Balance reads are free calls, so they must not be charged the base fee
+    let block_env = BlockEnv {
+        basefee: U256::ZERO,
+        ..block_env.clone()
+    };
+
+    tokens
+        .iter()
+        .map(|token| {
+            if token.is_zero() {
+                let info = cache_db
+                    .basic(account)
+                    .map_err(|e| eyre::eyre!("Could not fetch account: {:?}", e))?;
+                return Ok(info.map_or(U256::ZERO, |info| info.balance));
+            }
+
+            let call = IERC20Token::balanceOfCall { recipient: account };
+            let tx = TransactionRequest::default()
+                .to(*token)
+                .gas_limit(BALANCE_OF_GAS_LIMIT)
+                .input(TransactionInput::new(call.abi_encode().into()));
+
+            let result = tracing::revm_call_read_only(cache_db, cfg_env, &block_env, tx)?;
+            let output = result
+                .result
+                .output()
+                .ok_or_else(|| eyre::eyre!("No output from balanceOf"))?;
+
+            Ok(IERC20Token::balanceOfCall::abi_decode_returns(output, true)?.amount)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use revm::primitives::{TxKind, U256};
+    use revm::primitives::{InvalidTransaction, TxKind, U256};
 
-    use crate::utils::{addresses, setup};
+    use crate::{
+        interfaces::erc20,
+        simulation::{blockenv, engine_filter::TxEngineFilterType},
+        utils::{addresses, setup},
+    };
 
     use super::*;
 
@@ -111,4 +479,301 @@ mod tests {
             U256::from(VALUE)
         );
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_engine_simulate_bundle() {
+        let (provider, client) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+
+        let weth = *addresses::WETH;
+        const VALUE: i32 = 1000;
+
+        // Wrap ETH by sending it to the WETH contract, then wrap some more
+        let bundle = vec![
+            TransactionRequest {
+                from: Some(client),
+                to: Some(TxKind::Call(weth)),
+                value: Some(U256::from(VALUE)),
+                ..Default::default()
+            },
+            TransactionRequest {
+                from: Some(client),
+                to: Some(TxKind::Call(weth)),
+                value: Some(U256::from(2 * VALUE)),
+                ..Default::default()
+            },
+        ];
+
+        let task = EngineTask::new(provider, bundle);
+        let simulation = task
+            .simulate_bundle(client, vec![Address::ZERO, weth])
+            .unwrap();
+
+        assert_eq!(simulation.results.len(), 2);
+        assert!(simulation.is_success());
+
+        assert_eq!(simulation.deltas.len(), 2);
+        assert_eq!(
+            simulation.delta(weth),
+            Some(I256::try_from(3 * VALUE).unwrap())
+        );
+        assert_eq!(
+            simulation.delta(Address::ZERO),
+            Some(I256::try_from(-3 * VALUE).unwrap())
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_engine_simulate_bundle_failure() {
+        let (provider, client) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+
+        let alice = addresses::get_address("0x390e206254c9777C01d017B22eBDC7E2959fE3E8").unwrap();
+
+        // Alice cannot send funds she does not have
+        let bundle = vec![TransactionRequest {
+            from: Some(alice),
+            to: Some(TxKind::Call(client)),
+            value: Some(U256::MAX),
+            ..Default::default()
+        }];
+
+        let task = EngineTask::new(provider, bundle);
+        let simulation = task.simulate_bundle(alice, vec![Address::ZERO]).unwrap();
+
+        assert!(!simulation.is_success());
+        assert_eq!(simulation.delta(Address::ZERO), Some(I256::ZERO));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_engine_task_next_block() {
+        let (provider, client) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+
+        let alice = addresses::get_address("0x390e206254c9777C01d017B22eBDC7E2959fE3E8").unwrap();
+        let parent = provider.get_block_number().await.unwrap();
+        let block_env = blockenv::fetch_next_block_env(provider.as_ref(), parent.into())
+            .await
+            .unwrap();
+        assert_eq!(block_env.number, U256::from(parent + 1));
+
+        // Transactions must now pay at least the base fee
+        let max_fee = block_env.basefee.to::<u128>() * 2;
+        let bundle = vec![
+            TransactionRequest {
+                from: Some(client),
+                to: Some(TxKind::Call(alice)),
+                value: Some(U256::from(100)),
+                gas: Some(21_000),
+                max_fee_per_gas: Some(max_fee),
+                max_priority_fee_per_gas: Some(0),
+                ..Default::default()
+            },
+            TransactionRequest {
+                from: Some(client),
+                to: Some(TxKind::Call(alice)),
+                value: Some(U256::from(100)),
+                gas: Some(21_000),
+                ..Default::default()
+            },
+        ];
+
+        let results = EngineTask::new(provider, bundle)
+            .with_block(parent.into())
+            .with_block_env(block_env)
+            .consume();
+
+        assert!(results[0].as_ref().unwrap().is_success());
+        // Zero gas price is below the base fee
+        assert_eq!(
+            results[1]
+                .as_ref()
+                .unwrap_err()
+                .downcast_ref::<InvalidTransaction>(),
+            Some(&InvalidTransaction::GasPriceLessThanBasefee)
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_engine_task_can_revert() {
+        let (provider, client) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+
+        let bob = addresses::get_address("0x7E219AAf9339eA8f08c381632DEe3CeC94AA4054").unwrap();
+        let transfer = erc20::transfer(&provider, *addresses::WETH, bob, U256::from(100))
+            .from(client)
+            .nonce(provider.get_transaction_count(client).await.unwrap());
+
+        // The first transfer reverts as we hold no WETH, its nonce is only used up
+        // if the transaction is allowed to revert
+        let bundle = vec![transfer.clone(), transfer];
+
+        let results = EngineTask::new(provider.clone(), bundle.clone()).consume();
+        let first = results[0].as_ref().unwrap();
+        assert!(first.is_revert());
+        assert!(!first.committed);
+        assert!(results[1].as_ref().unwrap().is_revert());
+
+        let results = EngineTask::new(provider, bundle)
+            .with_can_revert(0)
+            .consume();
+        assert!(results[0].as_ref().unwrap().committed);
+        // Second transaction reuses the nonce
+        assert!(results[1].is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_engine_task_filtered() {
+        let (provider, client) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+
+        let weth = *addresses::WETH;
+        let bob = addresses::get_address("0x7E219AAf9339eA8f08c381632DEe3CeC94AA4054").unwrap();
+
+        // Wrapping ETH emits a Deposit event from WETH, the transfer reverts
+        let bundle = vec![
+            TransactionRequest {
+                from: Some(client),
+                to: Some(TxKind::Call(weth)),
+                value: Some(U256::from(1000)),
+                ..Default::default()
+            },
+            erc20::transfer(&provider, weth, bob, U256::from(u64::MAX)).from(client),
+        ];
+
+        let filter = TxEngineFilterType::TouchesPool(HashSet::from([weth]))
+            .and(TxEngineFilterType::IsSuccess)
+            .and(TxEngineFilterType::Reverted.not());
+
+        let outcomes = EngineTask::new(provider, bundle).consume_filtered(&filter);
+
+        assert_eq!(outcomes.len(), 1);
+        let (index, outcome) = &outcomes[0];
+        assert_eq!(*index, 0);
+        assert!(outcome.is_success());
+        assert!(filter.matches(outcome));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_engine_state_cache() {
+        let (provider, client) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+
+        let block = provider.get_block_number().await.unwrap();
+        let cache = Arc::new(StateCache::new());
+        cache.advance(block);
+
+        let weth = *addresses::WETH;
+        let bundle = vec![TransactionRequest {
+            from: Some(client),
+            to: Some(TxKind::Call(weth)),
+            value: Some(U256::from(1000)),
+            ..Default::default()
+        }];
+
+        let first = EngineTask::new(provider.clone(), bundle.clone())
+            .with_block(block.into())
+            .with_state_cache(cache.clone())
+            .consume();
+
+        // Everything the first task fetched is now shared
+        assert!(cache.account(block, &weth).is_some());
+        assert!(cache.account(block, &client).is_some());
+
+        let second = EngineTask::new(provider, bundle)
+            .with_block(block.into())
+            .with_state_cache(cache)
+            .consume();
+
+        assert_eq!(
+            first[0].as_ref().unwrap().state,
+            second[0].as_ref().unwrap().state
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_engine_consume_async() {
+        let (provider, client) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+
+        let weth = *addresses::WETH;
+        let bundle = vec![TransactionRequest {
+            from: Some(client),
+            to: Some(TxKind::Call(weth)),
+            value: Some(U256::from(1000)),
+            ..Default::default()
+        }];
+
+        let pool = WorkerPool::new(1);
+        let results = EngineTask::new(provider.clone(), bundle.clone())
+            .with_worker_pool(pool.clone())
+            .consume_async()
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert!(results[0].as_ref().unwrap().is_success());
+        assert_eq!(pool.available(), 1);
+
+        let simulation = EngineTask::new(provider, bundle)
+            .simulate_bundle_async(client, vec![weth])
+            .await
+            .unwrap();
+
+        assert!(simulation.is_success());
+        assert_eq!(simulation.delta(weth), Some(I256::try_from(1000).unwrap()));
+    }
+
+    #[tokio::test]
+    pub async fn test_engine_consume_async_current_thread() {
+        let (provider, client) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+
+        let bundle = vec![TransactionRequest {
+            from: Some(client),
+            to: Some(TxKind::Call(*addresses::WETH)),
+            value: Some(U256::from(1000)),
+            ..Default::default()
+        }];
+
+        // `AlloyDB` cannot block on a current-thread runtime, so the task brings its own
+        let results = EngineTask::new(provider.clone(), bundle.clone())
+            .consume_async()
+            .await
+            .unwrap();
+        assert!(results[0].as_ref().unwrap().is_success());
+
+        let results = EngineTask::new(provider, bundle)
+            .with_worker_pool(WorkerPool::new(1))
+            .consume_async()
+            .await
+            .unwrap();
+        assert!(results[0].as_ref().unwrap().is_success());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_engine_consume_with_async() {
+        let (provider, client) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+        let nonce = provider.get_transaction_count(client).await.unwrap();
+
+        let bundle = vec![TransactionRequest {
+            from: Some(client),
+            to: Some(TxKind::Call(*addresses::WETH)),
+            value: Some(U256::from(1000)),
+            ..Default::default()
+        }];
+
+        // The state handed over includes the committed deposit
+        let (results, after) = EngineTask::new(provider, bundle)
+            .consume_with_async(move |cache_db, results| {
+                let info = cache_db.basic(client).unwrap().unwrap();
+                (results, info.nonce)
+            })
+            .await
+            .unwrap();
+
+        assert!(results[0].as_ref().unwrap().is_success());
+        assert_eq!(after, nonce + 1);
+    }
 }
@@ -1,18 +1,47 @@
-use revm::primitives::{Address, ExecutionResult, ResultAndState};
+use std::collections::HashSet;
 
-trait EngineFilter {
-    fn filter(&self, tx: Vec<ResultAndState>) -> Vec<ResultAndState>;
+use alloy::primitives::{B256, I256, U256};
+use revm::primitives::{Address, ExecutionResult};
+
+use super::outcome::TxOutcome;
+
+pub trait EngineFilter {
+    fn filter(&self, tx: Vec<TxOutcome>) -> Vec<TxOutcome>;
 }
 
-enum TxEngineFilterType {
+/// Predicate over a simulated transaction, predicates can be nested with `Not`, `And` and `Or`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxEngineFilterType {
     AccountSlotModified(Address),
     Identity,
     IsSuccess,
     Reverted,
+    /// A log with `topic` as its first topic, optionally emitted by `address`
+    EmitsTopic {
+        address: Option<Address>,
+        topic: B256,
+    },
+    /// A storage slot of an account was written to a new value
+    StorageSlotWritten(Address, U256),
+    /// The ETH balance of an account increased by at least `min`
+    BalanceDelta {
+        account: Address,
+        min: I256,
+    },
+    /// Gas used lies within `[min, max]`
+    GasUsed {
+        min: u64,
+        max: u64,
+    },
+    /// One of the pools was touched or emitted a log
+    TouchesPool(HashSet<Address>),
+    Not(Box<TxEngineFilterType>),
+    And(Vec<TxEngineFilterType>),
+    Or(Vec<TxEngineFilterType>),
 }
 
 impl TxEngineFilterType {
-    fn filter(&self, tx: &ResultAndState) -> bool {
+    pub fn matches(&self, tx: &TxOutcome) -> bool {
         match self {
             TxEngineFilterType::AccountSlotModified(addr) => {
                 tx.state.get(addr).is_some_and(|acc| acc.is_touched())
@@ -20,47 +49,103 @@ impl TxEngineFilterType {
             TxEngineFilterType::Identity => true,
             TxEngineFilterType::IsSuccess => tx.result.is_success(),
             TxEngineFilterType::Reverted => matches!(&tx.result, ExecutionResult::Revert { .. }),
+            TxEngineFilterType::EmitsTopic { address, topic } => {
+                tx.result.logs().iter().any(|log| {
+                    address.is_none_or(|addr| addr == log.address)
+                        && log.topics().first() == Some(topic)
+                })
+            }
+            TxEngineFilterType::StorageSlotWritten(addr, slot) => tx
+                .state
+                .get(addr)
+                .and_then(|acc| acc.storage.get(slot))
+                .is_some_and(|value| value.is_changed()),
+            TxEngineFilterType::BalanceDelta { account, min } => {
+                tx.balance_delta(account).is_some_and(|delta| delta >= *min)
+            }
+            TxEngineFilterType::GasUsed { min, max } => (*min..=*max).contains(&tx.gas_used()),
+            TxEngineFilterType::TouchesPool(pools) => {
+                tx.state
+                    .iter()
+                    .any(|(addr, acc)| acc.is_touched() && pools.contains(addr))
+                    || tx
+                        .result
+                        .logs()
+                        .iter()
+                        .any(|log| pools.contains(&log.address))
+            }
+            TxEngineFilterType::Not(inner) => !inner.matches(tx),
+            TxEngineFilterType::And(filters) => filters.iter().all(|f| f.matches(tx)),
+            TxEngineFilterType::Or(filters) => filters.iter().any(|f| f.matches(tx)),
+        }
+    }
+
+    pub fn not(self) -> Self {
+        TxEngineFilterType::Not(Box::new(self))
+    }
+
+    pub fn and(self, other: TxEngineFilterType) -> Self {
+        match self {
+            TxEngineFilterType::And(mut filters) => {
+                filters.push(other);
+                TxEngineFilterType::And(filters)
+            }
+            filter => TxEngineFilterType::And(vec![filter, other]),
+        }
+    }
+
+    pub fn or(self, other: TxEngineFilterType) -> Self {
+        match self {
+            TxEngineFilterType::Or(mut filters) => {
+                filters.push(other);
+                TxEngineFilterType::Or(filters)
+            }
+            filter => TxEngineFilterType::Or(vec![filter, other]),
         }
     }
 }
 
-struct RootFilter {
-    filter: TxEngineFilterType,
+impl EngineFilter for TxEngineFilterType {
+    fn filter(&self, txs: Vec<TxOutcome>) -> Vec<TxOutcome> {
+        txs.into_iter().filter(|tx| self.matches(tx)).collect()
+    }
 }
 
-struct OrFilter<T: EngineFilter> {
-    inner: T,
-    filters: Vec<TxEngineFilterType>,
+pub struct RootFilter {
+    pub filter: TxEngineFilterType,
 }
 
-struct AndFilter<T: EngineFilter> {
-    inner: T,
-    filters: Vec<TxEngineFilterType>,
+pub struct OrFilter<T: EngineFilter> {
+    pub inner: T,
+    pub filters: Vec<TxEngineFilterType>,
+}
+
+pub struct AndFilter<T: EngineFilter> {
+    pub inner: T,
+    pub filters: Vec<TxEngineFilterType>,
 }
 
 impl EngineFilter for RootFilter {
-    fn filter(&self, txs: Vec<ResultAndState>) -> Vec<ResultAndState> {
-        txs.into_iter()
-            .filter(|tx| self.filter.filter(tx))
-            .collect()
+    fn filter(&self, txs: Vec<TxOutcome>) -> Vec<TxOutcome> {
+        self.filter.filter(txs)
     }
 }
 
 impl<T: EngineFilter> EngineFilter for OrFilter<T> {
-    fn filter(&self, txs: Vec<ResultAndState>) -> Vec<ResultAndState> {
+    fn filter(&self, txs: Vec<TxOutcome>) -> Vec<TxOutcome> {
         let input = txs
             .into_iter()
-            .filter(|tx| self.filters.iter().any(|f| f.filter(tx)))
+            .filter(|tx| self.filters.iter().any(|f| f.matches(tx)))
             .collect();
         self.inner.filter(input)
     }
 }
 
 impl<T: EngineFilter> EngineFilter for AndFilter<T> {
-    fn filter(&self, txs: Vec<ResultAndState>) -> Vec<ResultAndState> {
+    fn filter(&self, txs: Vec<TxOutcome>) -> Vec<TxOutcome> {
         let input = txs
             .into_iter()
-            .filter(|tx| self.filters.iter().all(|f| f.filter(tx)))
+            .filter(|tx| self.filters.iter().all(|f| f.matches(tx)))
             .collect();
         self.inner.filter(input)
     }
@@ -69,18 +154,21 @@ impl<T: EngineFilter> EngineFilter for AndFilter<T> {
 #[cfg(test)]
 mod tests {
     use alloy::hex::FromHex;
-    use revm::primitives::{Account, Bytes, HashMap, Output, SuccessReason};
+    use revm::primitives::{
+        Account, Bytes, EvmStorageSlot, HashMap, Log, LogData, Output, ResultAndState,
+        SuccessReason,
+    };
 
     use crate::utils::addresses;
 
     use super::*;
 
-    fn setup() -> (Bytes, Bytes, Vec<ResultAndState>) {
+    fn setup() -> (Bytes, Bytes, Vec<TxOutcome>) {
         let alice = Bytes::from_hex("0xdd0d0426504F32593B4435e0A5006bfA7b187ef8").unwrap();
         let zero = Bytes::from_hex("0x00").unwrap();
 
         let txs = vec![
-            ResultAndState {
+            TxOutcome::from(ResultAndState {
                 state: Default::default(),
                 result: ExecutionResult::Success {
                     output: Output::Call(alice.clone()),
@@ -89,14 +177,14 @@ mod tests {
                     logs: Vec::new(),
                     gas_refunded: 0,
                 },
-            },
-            ResultAndState {
+            }),
+            TxOutcome::from(ResultAndState {
                 state: Default::default(),
                 result: ExecutionResult::Revert {
                     output: zero.clone(),
                     gas_used: 0,
                 },
-            },
+            }),
         ];
 
         (alice, zero, txs)
@@ -163,7 +251,7 @@ mod tests {
         state_bob.insert(bob, bob_account);
 
         let txs = vec![
-            ResultAndState {
+            TxOutcome::from(ResultAndState {
                 state: state_alice,
                 result: ExecutionResult::Success {
                     output: Output::Call(alice_bytes.clone()),
@@ -172,14 +260,14 @@ mod tests {
                     logs: Vec::new(),
                     gas_refunded: 0,
                 },
-            },
-            ResultAndState {
+            }),
+            TxOutcome::from(ResultAndState {
                 state: state_bob,
                 result: ExecutionResult::Revert {
                     output: zero,
                     gas_used: 0,
                 },
-            },
+            }),
         ];
 
         let filter = RootFilter {
@@ -222,7 +310,7 @@ mod tests {
         state_bob.insert(bob, bob_account);
 
         let txs = vec![
-            ResultAndState {
+            TxOutcome::from(ResultAndState {
                 state: state_alice,
                 result: ExecutionResult::Success {
                     output: Output::Call(alice_bytes.clone()),
@@ -231,14 +319,14 @@ mod tests {
                     logs: Vec::new(),
                     gas_refunded: 0,
                 },
-            },
-            ResultAndState {
+            }),
+            TxOutcome::from(ResultAndState {
                 state: state_bob,
                 result: ExecutionResult::Revert {
                     output: zero,
                     gas_used: 0,
                 },
-            },
+            }),
         ];
 
         let filter = AndFilter {
@@ -290,7 +378,7 @@ mod tests {
         state_bob.insert(bob, bob_account);
 
         let txs = vec![
-            ResultAndState {
+            TxOutcome::from(ResultAndState {
                 state: state_alice,
                 result: ExecutionResult::Success {
                     output: Output::Call(alice_bytes.clone()),
@@ -299,14 +387,14 @@ mod tests {
                     logs: Vec::new(),
                     gas_refunded: 0,
                 },
-            },
-            ResultAndState {
+            }),
+            TxOutcome::from(ResultAndState {
                 state: state_bob,
                 result: ExecutionResult::Revert {
                     output: zero,
                     gas_used: 0,
                 },
-            },
+            }),
         ];
 
         let filter = OrFilter {
@@ -326,4 +414,140 @@ mod tests {
             ExecutionResult::Success { .. }
         ));
     }
+
+    fn success_with(state: HashMap<Address, Account>, logs: Vec<Log>, gas_used: u64) -> TxOutcome {
+        TxOutcome::from(ResultAndState {
+            state,
+            result: ExecutionResult::Success {
+                output: Output::Call(Bytes::new()),
+                gas_used,
+                reason: SuccessReason::Return,
+                logs,
+                gas_refunded: 0,
+            },
+        })
+    }
+
+    #[test]
+    fn test_not_filter() {
+        let (_alice, _zero, txs) = setup();
+
+        let filter = TxEngineFilterType::IsSuccess.not();
+
+        let result = filter.filter(txs);
+        assert_eq!(result.len(), 1);
+        assert!(matches!(&result[0].result, ExecutionResult::Revert { .. }));
+    }
+
+    #[test]
+    fn test_nested_filter() {
+        let (_alice, _zero, txs) = setup();
+
+        // (IsSuccess AND NOT Reverted) OR (Reverted AND NOT Identity)
+        let filter = TxEngineFilterType::IsSuccess
+            .and(TxEngineFilterType::Reverted.not())
+            .or(TxEngineFilterType::Reverted.and(TxEngineFilterType::Identity.not()));
+
+        let result = filter.filter(txs);
+        assert_eq!(result.len(), 1);
+        assert!(matches!(&result[0].result, ExecutionResult::Success { .. }));
+    }
+
+    #[test]
+    fn test_emits_topic_filter() {
+        let pool = addresses::get_address("0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640").unwrap();
+        let other = addresses::get_address("0x97e750a788C14b62b9d8b84ED2c10b912EDf52F9").unwrap();
+        let topic = B256::repeat_byte(0xaa);
+
+        let log = Log {
+            address: pool,
+            data: LogData::new_unchecked(vec![topic], Bytes::new()),
+        };
+        let txs = vec![
+            success_with(HashMap::default(), vec![log], 0),
+            success_with(HashMap::default(), Vec::new(), 0),
+        ];
+
+        let filter = TxEngineFilterType::EmitsTopic {
+            address: None,
+            topic,
+        };
+        assert_eq!(filter.filter(txs.clone()).len(), 1);
+
+        let filter = TxEngineFilterType::EmitsTopic {
+            address: Some(pool),
+            topic,
+        };
+        assert_eq!(filter.filter(txs.clone()).len(), 1);
+
+        let filter = TxEngineFilterType::EmitsTopic {
+            address: Some(other),
+            topic,
+        };
+        assert_eq!(filter.filter(txs.clone()).len(), 0);
+
+        // Pools emitting logs are touched
+        let filter = TxEngineFilterType::TouchesPool(HashSet::from([pool]));
+        assert_eq!(filter.filter(txs).len(), 1);
+    }
+
+    #[test]
+    fn test_storage_and_balance_filter() {
+        let alice = addresses::get_address("0xE0106B0a4ead5A806e9059BD0EB4Acfad15E9740").unwrap();
+        let slot = U256::from(3);
+
+        let mut alice_account = Account::new_not_existing();
+        alice_account.mark_touch();
+        alice_account.info.balance = U256::from(500);
+        alice_account
+            .storage
+            .insert(slot, EvmStorageSlot::new_changed(U256::ZERO, U256::from(1)));
+
+        let mut state = HashMap::default();
+        state.insert(alice, alice_account);
+
+        let txs = vec![
+            success_with(state, Vec::new(), 0)
+                .with_pre_balances(std::collections::HashMap::from([(alice, U256::from(100))])),
+            success_with(HashMap::default(), Vec::new(), 0),
+        ];
+
+        let filter = TxEngineFilterType::StorageSlotWritten(alice, slot);
+        assert_eq!(filter.filter(txs.clone()).len(), 1);
+
+        let filter = TxEngineFilterType::StorageSlotWritten(alice, U256::from(4));
+        assert_eq!(filter.filter(txs.clone()).len(), 0);
+
+        let filter = TxEngineFilterType::BalanceDelta {
+            account: alice,
+            min: I256::try_from(400).unwrap(),
+        };
+        assert_eq!(filter.filter(txs.clone()).len(), 1);
+
+        let filter = TxEngineFilterType::BalanceDelta {
+            account: alice,
+            min: I256::try_from(401).unwrap(),
+        };
+        assert_eq!(filter.filter(txs.clone()).len(), 0);
+
+        let filter = TxEngineFilterType::TouchesPool(HashSet::from([alice]));
+        assert_eq!(filter.filter(txs).len(), 1);
+    }
+
+    #[test]
+    fn test_gas_used_filter() {
+        let txs = vec![
+            success_with(HashMap::default(), Vec::new(), 21_000),
+            success_with(HashMap::default(), Vec::new(), 150_000),
+        ];
+
+        let filter = TxEngineFilterType::GasUsed {
+            min: 21_000,
+            max: 100_000,
+        };
+
+        let result = filter.filter(txs);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].gas_used(), 21_000);
+    }
 }
@@ -1,3 +1,11 @@
-// mod engine_filter;
+pub mod batch;
+pub mod blockenv;
+pub mod calltracer;
 pub mod engine;
-mod tracing;
+pub mod engine_filter;
+pub mod outcome;
+pub mod revert;
+pub mod statecache;
+pub mod tracing;
+pub mod txenv;
+pub mod workerpool;
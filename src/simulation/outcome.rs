@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use alloy::primitives::I256;
+use revm::primitives::{Address, EvmState, ExecutionResult, HaltReason, ResultAndState, U256};
+#[cfg(test)]
+use revm::primitives::{Log, Output, SuccessReason};
+
+use super::revert::RevertReason;
+
+/// Outcome of a simulated transaction which executed, whether it succeeded,
+/// reverted or halted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TxOutcome {
+    pub result: ExecutionResult,
+    pub state: EvmState,
+    /// Decoded revert data, only set if the transaction reverted
+    pub revert_reason: Option<RevertReason>,
+    /// Whether the state changes were committed to the database
+    pub committed: bool,
+    /// ETH balances of the touched accounts before the transaction
+    pub pre_balances: HashMap<Address, U256>,
+}
+
+impl TxOutcome {
+    pub fn new(result: ExecutionResult, state: EvmState, committed: bool) -> Self {
+        let revert_reason = match &result {
+            ExecutionResult::Revert { output, .. } => Some(RevertReason::decode(output)),
+            _ => None,
+        };
+
+        Self {
+            result,
+            state,
+            revert_reason,
+            committed,
+            pre_balances: HashMap::new(),
+        }
+    }
+
+    pub fn with_pre_balances(mut self, pre_balances: HashMap<Address, U256>) -> Self {
+        self.pre_balances = pre_balances;
+        self
+    }
+
+    /// Change in the ETH balance of a touched account.
+    pub fn balance_delta(&self, account: &Address) -> Option<I256> {
+        let before = self.pre_balances.get(account)?;
+        let after = self.state.get(account)?.info.balance;
+
+        Some(I256::from_raw(after) - I256::from_raw(*before))
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.result.is_success()
+    }
+
+    pub fn is_revert(&self) -> bool {
+        matches!(self.result, ExecutionResult::Revert { .. })
+    }
+
+    /// The reason the transaction halted, e.g. out of gas.
+    pub fn halt_reason(&self) -> Option<&HaltReason> {
+        match &self.result {
+            ExecutionResult::Halt { reason, .. } => Some(reason),
+            _ => None,
+        }
+    }
+
+    pub fn gas_used(&self) -> u64 {
+        self.result.gas_used()
+    }
+}
+
+#[cfg(test)]
+impl TxOutcome {
+    /// A committed call which returned after using `gas_used` and emitting `logs`.
+    pub fn success(gas_used: u64, logs: Vec<Log>) -> Self {
+        Self::new(
+            ExecutionResult::Success {
+                reason: SuccessReason::Return,
+                gas_used,
+                gas_refunded: 0,
+                logs,
+                output: Output::Call(Default::default()),
+            },
+            Default::default(),
+            true,
+        )
+    }
+}
+
+impl From<ResultAndState> for TxOutcome {
+    fn from(value: ResultAndState) -> Self {
+        Self::new(value.result, value.state, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::sol_types::{Revert, SolError};
+    use revm::primitives::Account;
+
+    use super::*;
+
+    #[test]
+    fn test_outcome_success() {
+        let outcome = TxOutcome::success(21000, Vec::new());
+
+        assert!(outcome.is_success());
+        assert!(!outcome.is_revert());
+        assert!(outcome.halt_reason().is_none());
+        assert!(outcome.revert_reason.is_none());
+        assert_eq!(outcome.gas_used(), 21000);
+    }
+
+    #[test]
+    fn test_outcome_revert() {
+        let output = Revert::from("EXECUTOR: Not executing").abi_encode();
+        let outcome = TxOutcome::new(
+            ExecutionResult::Revert {
+                gas_used: 30000,
+                output: output.into(),
+            },
+            Default::default(),
+            false,
+        );
+
+        assert!(!outcome.is_success());
+        assert!(outcome.is_revert());
+        assert_eq!(outcome.gas_used(), 30000);
+        assert_eq!(
+            outcome.revert_reason,
+            Some(RevertReason::Error("EXECUTOR: Not executing".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_outcome_halt() {
+        let outcome = TxOutcome::new(
+            ExecutionResult::Halt {
+                reason: HaltReason::OutOfFunds,
+                gas_used: 50000,
+            },
+            Default::default(),
+            false,
+        );
+
+        assert!(!outcome.is_success());
+        assert!(!outcome.is_revert());
+        assert_eq!(outcome.halt_reason(), Some(&HaltReason::OutOfFunds));
+        assert!(outcome.revert_reason.is_none());
+    }
+
+    #[test]
+    fn test_outcome_balance_delta() {
+        let alice = Address::repeat_byte(0x01);
+        let bob = Address::repeat_byte(0x02);
+
+        let mut account = Account::new_not_existing();
+        account.info.balance = U256::from(100);
+
+        let mut state = EvmState::default();
+        state.insert(alice, account);
+
+        let outcome = TxOutcome::new(
+            ExecutionResult::Halt {
+                reason: HaltReason::OutOfFunds,
+                gas_used: 50000,
+            },
+            state,
+            false,
+        )
+        .with_pre_balances(HashMap::from([(alice, U256::from(250))]));
+
+        assert_eq!(
+            outcome.balance_delta(&alice),
+            Some(I256::try_from(-150).unwrap())
+        );
+        assert_eq!(outcome.balance_delta(&bob), None);
+    }
+}
@@ -0,0 +1,175 @@
+use alloy::{
+    contract,
+    primitives::{Bytes, U256},
+    sol,
+    sol_types::{Panic, Revert, SolError, SolInterface},
+};
+
+sol! {
+    #[sol(all_derives)]
+    contract Executor {
+        error BenchMarkFailure();
+        error UnauthorizedPool(address expected, address actual);
+        error InvalidRange(int24 tickLower, int24 tickUpper);
+    }
+
+    #[sol(all_derives)]
+    contract Oracle {
+        enum ErrorReason {
+            NegativePrice,
+            StaleRound,
+            RoundNotFinished,
+            PriceTooOld
+        }
+
+        error OracleNotSet();
+        error InvalidPrice(ErrorReason reason);
+    }
+
+    #[sol(all_derives)]
+    contract FundManager {
+        error BenchmarkNotStarted();
+    }
+}
+
+pub use Executor::ExecutorErrors;
+pub use FundManager::FundManagerErrors;
+pub use Oracle::{ErrorReason as OracleErrorReason, OracleErrors};
+
+/// Decoded reason for a reverted transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevertReason {
+    /// `Error(string)`, emitted by `require` and `revert` with a message.
+    Error(String),
+    /// `Panic(uint256)`, emitted on assertion failures, overflows, etc.
+    Panic(U256),
+    Executor(ExecutorErrors),
+    Oracle(OracleErrors),
+    FundManager(FundManagerErrors),
+    /// Revert data which does not match any known error.
+    Unknown(Bytes),
+}
+
+impl RevertReason {
+    /// Decode revert data returned by the EVM.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The revert data.
+    ///
+    /// # Returns
+    ///
+    /// * `RevertReason` - The decoded reason, `Unknown` if no known error matches.
+    pub fn decode(output: &Bytes) -> Self {
+        if let Ok(revert) = Revert::abi_decode(output, true) {
+            return Self::Error(revert.reason);
+        }
+        if let Ok(panic) = Panic::abi_decode(output, true) {
+            return Self::Panic(panic.code);
+        }
+        if let Ok(err) = ExecutorErrors::abi_decode(output, true) {
+            return Self::Executor(err);
+        }
+        if let Ok(err) = OracleErrors::abi_decode(output, true) {
+            return Self::Oracle(err);
+        }
+        if let Ok(err) = FundManagerErrors::abi_decode(output, true) {
+            return Self::FundManager(err);
+        }
+        Self::Unknown(output.clone())
+    }
+}
+
+/// Revert data of a failed contract call, if the node returned any.
+///
+/// # Arguments
+///
+/// * `err` - The error of an `eth_call` or gas estimation.
+///
+/// # Returns
+///
+/// * `Option<Bytes>` - The revert data, `None` if the call failed for another reason.
+pub fn revert_data(err: &contract::Error) -> Option<Bytes> {
+    match err {
+        contract::Error::TransportError(e) => e.as_error_resp()?.as_revert_data(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::Address;
+
+    use super::*;
+
+    #[test]
+    fn test_decode_error_string() {
+        let output = Revert::from("EXECUTOR: Pool not whitelisted").abi_encode();
+
+        assert_eq!(
+            RevertReason::decode(&output.into()),
+            RevertReason::Error("EXECUTOR: Pool not whitelisted".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_decode_panic() {
+        // Arithmetic overflow
+        let output = Panic {
+            code: U256::from(0x11),
+        }
+        .abi_encode();
+
+        assert_eq!(
+            RevertReason::decode(&output.into()),
+            RevertReason::Panic(U256::from(0x11))
+        );
+    }
+
+    #[test]
+    fn test_decode_custom_errors() {
+        let expected = Address::repeat_byte(0x01);
+        let actual = Address::repeat_byte(0x02);
+
+        let output = Executor::UnauthorizedPool { expected, actual }.abi_encode();
+        assert_eq!(
+            RevertReason::decode(&output.into()),
+            RevertReason::Executor(ExecutorErrors::UnauthorizedPool(
+                Executor::UnauthorizedPool { expected, actual }
+            ))
+        );
+
+        let output = Oracle::InvalidPrice {
+            reason: OracleErrorReason::PriceTooOld,
+        }
+        .abi_encode();
+        assert_eq!(
+            RevertReason::decode(&output.into()),
+            RevertReason::Oracle(OracleErrors::InvalidPrice(Oracle::InvalidPrice {
+                reason: OracleErrorReason::PriceTooOld
+            }))
+        );
+
+        let output = FundManager::BenchmarkNotStarted {}.abi_encode();
+        assert_eq!(
+            RevertReason::decode(&output.into()),
+            RevertReason::FundManager(FundManagerErrors::BenchmarkNotStarted(
+                FundManager::BenchmarkNotStarted {}
+            ))
+        );
+    }
+
+    #[test]
+    fn test_decode_unknown() {
+        let output = Bytes::from_static(&[0xde, 0xad, 0xbe, 0xef]);
+
+        assert_eq!(
+            RevertReason::decode(&output),
+            RevertReason::Unknown(output.clone())
+        );
+        assert_eq!(
+            RevertReason::decode(&Bytes::new()),
+            RevertReason::Unknown(Bytes::new())
+        );
+    }
+}
@@ -0,0 +1,448 @@
+use std::{
+    collections::HashMap,
+    fs,
+    future::IntoFuture,
+    path::Path,
+    sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use alloy::{
+    eips::{BlockId, BlockNumberOrTag},
+    network::Network,
+    providers::Provider,
+    transports::Transport,
+};
+use eyre::Result;
+use futures_util::future::{try_join3, try_join_all};
+use revm::{
+    db::{AlloyDB, CacheDB},
+    primitives::{AccountInfo, Address, Bytecode, B256, U256},
+    DatabaseRef,
+};
+use serde::{Deserialize, Serialize};
+
+/// Chain state fetched at a single block.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct Snapshot {
+    block: Option<u64>,
+    accounts: HashMap<Address, Option<AccountInfo>>,
+    storage: HashMap<Address, HashMap<U256, U256>>,
+    // Code and block hashes never change, so they survive new blocks
+    contracts: HashMap<B256, Bytecode>,
+    block_hashes: HashMap<u64, B256>,
+}
+
+/// State cache shared between simulations, keyed by the block it was fetched at.
+///
+/// Simulations pinned to the cached block read from the cache before falling back
+/// to RPC, and store whatever they fetch. Call `advance` whenever a new block
+/// arrives, this drops all account and storage state of the previous block.
+#[derive(Debug, Default)]
+pub struct StateCache {
+    snapshot: RwLock<Snapshot>,
+}
+
+impl StateCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a cache previously written with `persist`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let snapshot = serde_json::from_slice(&fs::read(path)?)?;
+
+        Ok(Self {
+            snapshot: RwLock::new(snapshot),
+        })
+    }
+
+    /// Write the cache to disk, e.g. to keep fetched bytecode across restarts.
+    pub fn persist(&self, path: impl AsRef<Path>) -> Result<()> {
+        let json = serde_json::to_vec(&*self.read())?;
+        fs::write(path, json)?;
+
+        Ok(())
+    }
+
+    /// The block the cached state belongs to.
+    pub fn block(&self) -> Option<u64> {
+        self.read().block
+    }
+
+    /// Move the cache to a new block, dropping the state of the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `block` - The latest block number.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the cached state was invalidated.
+    pub fn advance(&self, block: u64) -> bool {
+        let mut snapshot = self.write();
+        if snapshot.block == Some(block) {
+            return false;
+        }
+
+        snapshot.block = Some(block);
+        snapshot.accounts.clear();
+        snapshot.storage.clear();
+        true
+    }
+
+    pub fn account(&self, block: u64, address: &Address) -> Option<Option<AccountInfo>> {
+        let snapshot = self.read();
+        if snapshot.block != Some(block) {
+            return None;
+        }
+
+        snapshot.accounts.get(address).cloned()
+    }
+
+    pub fn storage(&self, block: u64, address: &Address, index: &U256) -> Option<U256> {
+        let snapshot = self.read();
+        if snapshot.block != Some(block) {
+            return None;
+        }
+
+        snapshot.storage.get(address)?.get(index).copied()
+    }
+
+    pub fn contract(&self, code_hash: &B256) -> Option<Bytecode> {
+        self.read().contracts.get(code_hash).cloned()
+    }
+
+    pub fn block_hash(&self, number: u64) -> Option<B256> {
+        self.read().block_hashes.get(&number).copied()
+    }
+
+    fn insert_account(&self, block: u64, address: Address, info: Option<AccountInfo>) {
+        let mut snapshot = self.write();
+        if let Some(info) = &info {
+            if let Some(code) = &info.code {
+                snapshot.contracts.insert(info.code_hash, code.clone());
+            }
+        }
+
+        // State of an older block must not end up in the new one
+        if snapshot.block == Some(block) {
+            snapshot.accounts.insert(address, info);
+        }
+    }
+
+    fn insert_storage(&self, block: u64, address: Address, index: U256, value: U256) {
+        let mut snapshot = self.write();
+        if snapshot.block == Some(block) {
+            snapshot
+                .storage
+                .entry(address)
+                .or_default()
+                .insert(index, value);
+        }
+    }
+
+    fn insert_block_hash(&self, number: u64, hash: B256) {
+        self.write().block_hashes.insert(number, hash);
+    }
+
+    /// Fetch the accounts and storage slots of hot contracts, e.g. the pools we
+    /// expect to bundle, so simulations at `block` find them in the cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The provider to fetch the state with.
+    /// * `block` - The block to fetch the state at, see `advance`.
+    /// * `accounts` - The accounts to load, including their bytecode.
+    /// * `slots` - The storage slots to load.
+    pub async fn preload<T, N, P>(
+        &self,
+        provider: &P,
+        block: u64,
+        accounts: &[Address],
+        slots: &[(Address, U256)],
+    ) -> Result<()>
+    where
+        T: Transport + Clone,
+        N: Network,
+        P: Provider<T, N>,
+    {
+        let block_id = BlockId::number(block);
+
+        let infos = try_join_all(accounts.iter().map(|address| async move {
+            let (balance, nonce, code) = try_join3(
+                provider
+                    .get_balance(*address)
+                    .block_id(block_id)
+                    .into_future(),
+                provider
+                    .get_transaction_count(*address)
+                    .block_id(block_id)
+                    .into_future(),
+                provider
+                    .get_code_at(*address)
+                    .block_id(block_id)
+                    .into_future(),
+            )
+            .await?;
+
+            let code = Bytecode::new_raw(code);
+            let info = AccountInfo::new(balance, nonce, code.hash_slow(), code);
+            Ok::<_, eyre::Report>((*address, info))
+        }))
+        .await?;
+
+        let values = try_join_all(slots.iter().map(|(address, index)| async move {
+            let value = provider
+                .get_storage_at(*address, *index)
+                .block_id(block_id)
+                .await?;
+            Ok::<_, eyre::Report>((*address, *index, value))
+        }))
+        .await?;
+
+        for (address, info) in infos {
+            self.insert_account(block, address, Some(info));
+        }
+        for (address, index, value) in values {
+            self.insert_storage(block, address, index, value);
+        }
+
+        Ok(())
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Snapshot> {
+        // The snapshot is never left half written, so a poisoned lock is still usable
+        self.snapshot.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Snapshot> {
+        self.snapshot
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Database which reads through a shared `StateCache` before falling back to RPC.
+pub struct StateCacheDB<T: Transport + Clone, N: Network, P: Provider<T, N>> {
+    db: AlloyDB<T, N, Arc<P>>,
+    // Only set when the database reads a fixed block
+    cache: Option<(Arc<StateCache>, u64)>,
+}
+
+impl<T: Transport + Clone, N: Network, P: Provider<T, N>> StateCacheDB<T, N, P> {
+    /// Database reading `block` which caches under `key` in `cache`.
+    ///
+    /// The caller guarantees that `key` identifies the state at `block`.
+    pub(super) fn new(
+        provider: Arc<P>,
+        block: BlockId,
+        cache: Option<(Arc<StateCache>, u64)>,
+    ) -> Self {
+        Self {
+            db: AlloyDB::new(provider, block).unwrap(),
+            cache,
+        }
+    }
+}
+
+impl<T: Transport + Clone, N: Network, P: Provider<T, N>> DatabaseRef for StateCacheDB<T, N, P> {
+    type Error = <AlloyDB<T, N, Arc<P>> as DatabaseRef>::Error;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let Some((cache, block)) = &self.cache else {
+            return self.db.basic_ref(address);
+        };
+
+        if let Some(info) = cache.account(*block, &address) {
+            return Ok(info);
+        }
+
+        let info = self.db.basic_ref(address)?;
+        cache.insert_account(*block, address, info.clone());
+        Ok(info)
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        if let Some(code) = self
+            .cache
+            .as_ref()
+            .and_then(|(cache, _)| cache.contract(&code_hash))
+        {
+            return Ok(code);
+        }
+
+        self.db.code_by_hash_ref(code_hash)
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        let Some((cache, block)) = &self.cache else {
+            return self.db.storage_ref(address, index);
+        };
+
+        if let Some(value) = cache.storage(*block, &address, &index) {
+            return Ok(value);
+        }
+
+        let value = self.db.storage_ref(address, index)?;
+        cache.insert_storage(*block, address, index, value);
+        Ok(value)
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        let Some((cache, _)) = &self.cache else {
+            return self.db.block_hash_ref(number);
+        };
+
+        if let Some(hash) = cache.block_hash(number) {
+            return Ok(hash);
+        }
+
+        let hash = self.db.block_hash_ref(number)?;
+        cache.insert_block_hash(number, hash);
+        Ok(hash)
+    }
+}
+
+/// Create a database for simulations at `block` which shares fetched state through `cache`.
+///
+/// Only blocks given by number are cached, tags such as `latest` move with the chain.
+///
+/// # Arguments
+///
+/// * `provider` - The provider to fetch missing state with.
+/// * `block` - The block to simulate on top of.
+/// * `cache` - The shared state cache, if any.
+///
+/// # Returns
+///
+/// * `CacheDB<StateCacheDB<T, N, P>>` - The database.
+pub fn init_state_cache_db<T: Transport + Clone, N: Network, P: Provider<T, N>>(
+    provider: Arc<P>,
+    block: BlockId,
+    cache: Option<Arc<StateCache>>,
+) -> CacheDB<StateCacheDB<T, N, P>> {
+    let cache = match (cache, block) {
+        (Some(cache), BlockId::Number(BlockNumberOrTag::Number(number))) => Some((cache, number)),
+        _ => None,
+    };
+
+    CacheDB::new(StateCacheDB::new(provider, block, cache))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::{addresses, setup};
+
+    use super::*;
+
+    #[test]
+    fn test_state_cache_advance() {
+        let cache = StateCache::new();
+        let address = Address::repeat_byte(0x01);
+        let code = Bytecode::new_raw(vec![0x60, 0x00].into());
+        let info = AccountInfo::new(U256::from(10), 1, code.hash_slow(), code.clone());
+
+        assert!(cache.advance(100));
+        assert!(!cache.advance(100));
+
+        cache.insert_account(100, address, Some(info.clone()));
+        cache.insert_storage(100, address, U256::from(1), U256::from(2));
+        // Stale state from an older block is ignored
+        cache.insert_storage(99, address, U256::from(3), U256::from(4));
+
+        assert_eq!(cache.account(100, &address), Some(Some(info.clone())));
+        assert_eq!(cache.account(101, &address), None);
+        assert_eq!(
+            cache.storage(100, &address, &U256::from(1)),
+            Some(U256::from(2))
+        );
+        assert_eq!(cache.storage(100, &address, &U256::from(3)), None);
+
+        // A new block drops the state but keeps the code
+        assert!(cache.advance(101));
+        assert_eq!(cache.block(), Some(101));
+        assert_eq!(cache.account(101, &address), None);
+        assert_eq!(cache.storage(101, &address, &U256::from(1)), None);
+        assert_eq!(cache.contract(&code.hash_slow()), Some(code));
+    }
+
+    #[test]
+    fn test_state_cache_persist() {
+        let cache = StateCache::new();
+        let address = Address::repeat_byte(0x01);
+        cache.advance(100);
+        cache.insert_storage(100, address, U256::from(1), U256::from(2));
+        cache.insert_block_hash(99, B256::repeat_byte(0xaa));
+
+        let path = std::env::temp_dir().join(format!("state-cache-{}.json", std::process::id()));
+        cache.persist(&path).unwrap();
+        let loaded = StateCache::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.block(), Some(100));
+        assert_eq!(
+            loaded.storage(100, &address, &U256::from(1)),
+            Some(U256::from(2))
+        );
+        assert_eq!(loaded.block_hash(99), Some(B256::repeat_byte(0xaa)));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_state_cache_db() {
+        let (provider, _) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+
+        let block = provider.get_block_number().await.unwrap();
+        let cache = Arc::new(StateCache::new());
+        cache.advance(block);
+
+        let weth = *addresses::WETH;
+        let db = init_state_cache_db(
+            provider.clone(),
+            BlockId::number(block),
+            Some(cache.clone()),
+        );
+
+        // The first read goes to RPC and fills the cache
+        let info = db.db.basic_ref(weth).unwrap();
+        assert_eq!(cache.account(block, &weth), Some(info.clone()));
+
+        let slot = U256::from(0);
+        let value = db.db.storage_ref(weth, slot).unwrap();
+        assert_eq!(cache.storage(block, &weth, &slot), Some(value));
+
+        // A second database at the same block shares the fetched state
+        let other = init_state_cache_db(
+            provider.clone(),
+            BlockId::number(block),
+            Some(cache.clone()),
+        );
+        assert_eq!(other.db.basic_ref(weth).unwrap(), info);
+
+        // Tags are never cached
+        let latest = init_state_cache_db(provider, BlockNumberOrTag::Latest.into(), Some(cache));
+        assert!(latest.db.cache.is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_state_cache_preload() {
+        let (provider, _) = setup::test_setup().await;
+
+        let block = provider.get_block_number().await.unwrap();
+        let cache = StateCache::new();
+        cache.advance(block);
+
+        let pool = addresses::get_address("0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640").unwrap();
+        let slot0 = U256::ZERO;
+
+        cache
+            .preload(&provider, block, &[pool], &[(pool, slot0)])
+            .await
+            .unwrap();
+
+        let info = cache.account(block, &pool).unwrap().unwrap();
+        assert!(!info.is_empty_code_hash());
+        assert!(cache.contract(&info.code_hash).is_some());
+        assert!(cache.storage(block, &pool, &slot0).unwrap() > U256::ZERO);
+    }
+}
@@ -1,5 +1,6 @@
 use eyre::Result;
-use std::sync::Arc;
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
+use tokio::runtime::Handle;
 
 use alloy::{
     eips::BlockId, network::Network, providers::Provider, rpc::types::TransactionRequest,
@@ -7,90 +8,277 @@ use alloy::{
 };
 use revm::{
     db::{AlloyDB, CacheDB},
-    primitives::{ExecutionResult, Output, ResultAndState},
-    DatabaseCommit, Evm,
+    inspector_handle_register,
+    primitives::{BlockEnv, CfgEnv, EVMError, ResultAndState, U256},
+    Database, DatabaseCommit, Evm,
 };
 
+use super::{
+    calltracer::{CallFrame, CallTracer},
+    outcome::TxOutcome,
+    txenv,
+};
+
+/// When the state changes of a simulated transaction are committed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CommitMode {
+    Never,
+    OnSuccess,
+    // Also commit reverts and halts, for bundle transactions marked `can_revert`
+    Always,
+}
+
 /// Internal function to execute a transaction with revm.
 ///
+/// Reverts and halts are returned as a `TxOutcome`, errors are reserved for
+/// transactions which could not be executed at all (e.g. invalid nonce).
+///
 /// # Arguments
 ///
 /// * `cache_db` - The cache database.
+/// * `cfg_env` - The chain configuration, see `fetch_cfg_env`.
+/// * `block_env` - The block environment to execute in.
 /// * `desired_tx` - The desired transaction.
-/// * `commit` - Whether to commit the changes to the database.
+/// * `commit` - When to commit the changes to the database.
+/// * `tracer` - Optional inspector recording the call tree.
 ///
 /// # Returns
 ///
-/// * `Result<ResultAndState>` - The result and state of the execution.
-fn revm_call_internal<T, N, P>(
-    cache_db: &mut CacheDB<AlloyDB<T, N, Arc<P>>>,
+/// * `Result<TxOutcome>` - The outcome and state of the execution.
+fn revm_call_internal<DB>(
+    cache_db: &mut DB,
+    cfg_env: &CfgEnv,
+    block_env: &BlockEnv,
     desired_tx: TransactionRequest,
-    commit: bool,
-) -> Result<ResultAndState>
+    commit: CommitMode,
+    tracer: Option<&mut CallTracer>,
+) -> Result<TxOutcome>
 where
-    T: Transport + Clone,
-    N: Network,
-    P: Provider<T, N>,
+    DB: Database + DatabaseCommit,
+    DB::Error: Debug,
 {
-    // Build a new evm instance with desired tx
-    let mut evm = Evm::builder()
-        .with_db(cache_db)
-        .modify_tx_env(|tx| {
-            tx.caller = desired_tx.from.unwrap_or(tx.caller);
-            tx.value = desired_tx.value.unwrap_or(tx.value);
-            tx.gas_limit = desired_tx.gas.unwrap_or(tx.gas_limit);
-            tx.nonce = desired_tx.nonce;
-            tx.data = desired_tx.input.input.unwrap_or(tx.data.clone());
-            tx.transact_to = desired_tx.to.unwrap_or(tx.transact_to);
-        })
-        .build();
+    // Like eth_call, a request without a gas limit may use the whole block
+    let gas = desired_tx
+        .gas
+        .unwrap_or(block_env.gas_limit.saturating_to());
+    let tx_env = txenv::tx_env_from_request(desired_tx.gas_limit(gas));
+
+    // Build a new evm instance with desired tx, the inspector changes the evm type
+    let result_and_state = match tracer {
+        Some(tracer) => Evm::builder()
+            .with_db(&mut *cache_db)
+            .with_external_context(tracer)
+            .modify_cfg_env(|cfg| *cfg = cfg_env.clone())
+            .modify_block_env(|block| *block = block_env.clone())
+            .modify_tx_env(|tx| *tx = tx_env)
+            .append_handler_register(inspector_handle_register)
+            .build()
+            .transact(),
+        None => Evm::builder()
+            .with_db(&mut *cache_db)
+            .modify_cfg_env(|cfg| *cfg = cfg_env.clone())
+            .modify_block_env(|block| *block = block_env.clone())
+            .modify_tx_env(|tx| *tx = tx_env)
+            .build()
+            .transact(),
+    };
 
     // Execute the transaction and view results
-    let ref_tx = evm
-        .transact()
-        .map_err(|e| eyre::eyre!("Transaction failed: {:?}", e))?;
-    let result = ref_tx.result.clone();
-
-    let ret = match result {
-        ExecutionResult::Success {
-            output: Output::Call(_value),
-            ..
-        } => ref_tx,
-        result => {
-            return Err(eyre::eyre!("Execution failed: {:?}", result));
-        }
+    let ResultAndState { result, state } = result_and_state.map_err(|e| match e {
+        // Keep the reason so callers can tell why the transaction is invalid
+        EVMError::Transaction(invalid) => eyre::Report::new(invalid).wrap_err("Transaction failed"),
+        e => eyre::eyre!("Transaction failed: {:?}", e),
+    })?;
+
+    // Nothing is committed yet, so the database still holds the balances before the transaction
+    let pre_balances = state
+        .keys()
+        .map(|addr| {
+            let info = cache_db
+                .basic(*addr)
+                .map_err(|e| eyre::eyre!("Could not fetch account: {:?}", e))?;
+            Ok((*addr, info.map_or(U256::ZERO, |info| info.balance)))
+        })
+        .collect::<Result<HashMap<_, _>>>()?;
+
+    let committed = match commit {
+        CommitMode::Never => false,
+        CommitMode::OnSuccess => result.is_success(),
+        CommitMode::Always => true,
     };
 
     // Commit change to db if we have to
-    if commit {
-        evm.db_mut().commit(ret.state.clone());
+    if committed {
+        cache_db.commit(state.clone());
     }
 
-    Ok(ret)
+    Ok(TxOutcome::new(result, state, committed).with_pre_balances(pre_balances))
 }
 
-pub fn revm_call_write<T, N, P>(
-    cache_db: &mut CacheDB<AlloyDB<T, N, Arc<P>>>,
+/// Internal function to execute a transaction and record its call tree.
+fn revm_trace_internal<DB>(
+    cache_db: &mut DB,
+    cfg_env: &CfgEnv,
+    block_env: &BlockEnv,
     desired_tx: TransactionRequest,
-) -> Result<ResultAndState>
+    commit: CommitMode,
+) -> Result<(TxOutcome, CallFrame)>
 where
-    T: Transport + Clone,
-    N: Network,
-    P: Provider<T, N>,
+    DB: Database + DatabaseCommit,
+    DB::Error: Debug,
 {
-    revm_call_internal(cache_db, desired_tx, true)
+    let mut tracer = CallTracer::new();
+    let outcome = revm_call_internal(
+        cache_db,
+        cfg_env,
+        block_env,
+        desired_tx,
+        commit,
+        Some(&mut tracer),
+    )?;
+    let trace = tracer
+        .into_root()
+        .ok_or_else(|| eyre::eyre!("No call frame was recorded"))?;
+
+    Ok((outcome, trace))
 }
 
-pub fn revm_call_read_only<T, N, P>(
-    cache_db: &mut CacheDB<AlloyDB<T, N, Arc<P>>>,
+/// Execute a transaction and commit its changes if it succeeds.
+pub fn revm_call_write<DB>(
+    cache_db: &mut DB,
+    cfg_env: &CfgEnv,
+    block_env: &BlockEnv,
     desired_tx: TransactionRequest,
-) -> Result<ResultAndState>
+) -> Result<TxOutcome>
 where
-    T: Transport + Clone,
-    N: Network,
-    P: Provider<T, N>,
+    DB: Database + DatabaseCommit,
+    DB::Error: Debug,
 {
-    revm_call_internal(cache_db, desired_tx, false)
+    revm_call_internal(
+        cache_db,
+        cfg_env,
+        block_env,
+        desired_tx,
+        CommitMode::OnSuccess,
+        None,
+    )
+}
+
+/// Execute a transaction and commit its changes even if it reverts or halts.
+pub fn revm_call_write_can_revert<DB>(
+    cache_db: &mut DB,
+    cfg_env: &CfgEnv,
+    block_env: &BlockEnv,
+    desired_tx: TransactionRequest,
+) -> Result<TxOutcome>
+where
+    DB: Database + DatabaseCommit,
+    DB::Error: Debug,
+{
+    revm_call_internal(
+        cache_db,
+        cfg_env,
+        block_env,
+        desired_tx,
+        CommitMode::Always,
+        None,
+    )
+}
+
+/// Execute a transaction without committing any changes.
+pub fn revm_call_read_only<DB>(
+    cache_db: &mut DB,
+    cfg_env: &CfgEnv,
+    block_env: &BlockEnv,
+    desired_tx: TransactionRequest,
+) -> Result<TxOutcome>
+where
+    DB: Database + DatabaseCommit,
+    DB::Error: Debug,
+{
+    revm_call_internal(
+        cache_db,
+        cfg_env,
+        block_env,
+        desired_tx,
+        CommitMode::Never,
+        None,
+    )
+}
+
+/// Execute a transaction without committing any changes and record its call tree.
+///
+/// The trace reveals the pools a transaction touches through routers and
+/// multicalls, even if no event is emitted by the top level call.
+///
+/// # Arguments
+///
+/// * `cache_db` - The cache database.
+/// * `cfg_env` - The chain configuration, see `fetch_cfg_env`.
+/// * `block_env` - The block environment to execute in.
+/// * `desired_tx` - The desired transaction.
+///
+/// # Returns
+///
+/// * `Result<(TxOutcome, CallFrame)>` - The outcome and the top level call frame.
+pub fn revm_trace_read_only<DB>(
+    cache_db: &mut DB,
+    cfg_env: &CfgEnv,
+    block_env: &BlockEnv,
+    desired_tx: TransactionRequest,
+) -> Result<(TxOutcome, CallFrame)>
+where
+    DB: Database + DatabaseCommit,
+    DB::Error: Debug,
+{
+    revm_trace_internal(cache_db, cfg_env, block_env, desired_tx, CommitMode::Never)
+}
+
+/// Execute a transaction, commit its changes if it succeeds and record its call tree.
+pub fn revm_trace_write<DB>(
+    cache_db: &mut DB,
+    cfg_env: &CfgEnv,
+    block_env: &BlockEnv,
+    desired_tx: TransactionRequest,
+) -> Result<(TxOutcome, CallFrame)>
+where
+    DB: Database + DatabaseCommit,
+    DB::Error: Debug,
+{
+    revm_trace_internal(
+        cache_db,
+        cfg_env,
+        block_env,
+        desired_tx,
+        CommitMode::OnSuccess,
+    )
+}
+
+/// Configure the EVM for the chain `provider` is connected to.
+///
+/// Signed transactions are only valid on their own chain, so the chain id has to
+/// match the node's.
+///
+/// # Arguments
+///
+/// * `provider` - The provider the simulations fetch state with.
+///
+/// # Returns
+///
+/// * `Result<CfgEnv>` - The EVM configuration.
+pub async fn fetch_cfg_env<T: Transport + Clone, N: Network, P: Provider<T, N>>(
+    provider: &P,
+) -> Result<CfgEnv> {
+    let mut cfg_env = CfgEnv::default();
+    cfg_env.chain_id = provider.get_chain_id().await?;
+    Ok(cfg_env)
+}
+
+/// Blocking version of `fetch_cfg_env`, which blocks on the current runtime like `AlloyDB`.
+pub fn fetch_cfg_env_blocking<T: Transport + Clone, N: Network, P: Provider<T, N>>(
+    provider: &P,
+) -> Result<CfgEnv> {
+    tokio::task::block_in_place(|| Handle::current().block_on(fetch_cfg_env(provider)))
 }
 
 pub fn init_cache_db<T: Transport + Clone, N: Network, P: Provider<T, N>>(
@@ -106,11 +294,13 @@ mod tests {
 
     use alloy::{
         eips::BlockNumberOrTag,
-        primitives::{Address, FixedBytes, U256},
+        primitives::{Address, Bytes, FixedBytes, U256},
     };
+    use revm::primitives::{AccountInfo, Bytecode, InvalidTransaction};
 
     use crate::{
-        interfaces::erc20,
+        interfaces::{erc20, router02},
+        simulation::revert::RevertReason,
         utils::{addresses, blockchain_utils, setup},
     };
 
@@ -134,7 +324,12 @@ mod tests {
             .value(U256::from(VALUE));
 
         // Execute with revm, do not commit to the database
-        let output = revm_call_read_only(&mut cache_db, desired_tx);
+        let output = revm_call_read_only(
+            &mut cache_db,
+            &CfgEnv::default(),
+            &BlockEnv::default(),
+            desired_tx,
+        );
         assert!(output.is_ok());
         let output = output.unwrap();
 
@@ -149,6 +344,44 @@ mod tests {
         );
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_revm_call_chain_id() {
+        let (provider, client) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+        let chain_id = provider.get_chain_id().await.unwrap();
+
+        let mut cache_db = init_cache_db(provider.clone(), BlockNumberOrTag::Latest.into());
+        let cfg_env = fetch_cfg_env(provider.as_ref()).await.unwrap();
+        assert_eq!(cfg_env.chain_id, chain_id);
+
+        let bob = Address::from_str("0x098a1A2009184D4D24E57F4bD58C144E8C037933").unwrap();
+        let tx = TransactionRequest::default()
+            .to(bob)
+            .from(client)
+            .value(U256::from(150));
+
+        // A transaction for the node's chain executes
+        let on_chain = TransactionRequest {
+            chain_id: Some(chain_id),
+            ..tx.clone()
+        };
+        let outcome =
+            revm_call_read_only(&mut cache_db, &cfg_env, &BlockEnv::default(), on_chain).unwrap();
+        assert!(outcome.is_success());
+
+        // A transaction signed for another chain is invalid
+        let other_chain = TransactionRequest {
+            chain_id: Some(chain_id + 1),
+            ..tx
+        };
+        let error = revm_call_read_only(&mut cache_db, &cfg_env, &BlockEnv::default(), other_chain)
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<InvalidTransaction>(),
+            Some(&InvalidTransaction::InvalidChainId)
+        );
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
     pub async fn test_revm_call_write() {
         let (provider, client) = setup::test_setup().await;
@@ -172,7 +405,7 @@ mod tests {
             .value(U256::from(VALUE * 2));
 
         // Execute with revm, commit to the database
-        let first = revm_call_write(&mut cache_db, tx1);
+        let first = revm_call_write(&mut cache_db, &CfgEnv::default(), &BlockEnv::default(), tx1);
         assert!(first.is_ok());
 
         let first = first.unwrap();
@@ -185,7 +418,7 @@ mod tests {
         );
 
         // Execute with revm, commit to the database
-        let second = revm_call_write(&mut cache_db, tx2);
+        let second = revm_call_write(&mut cache_db, &CfgEnv::default(), &BlockEnv::default(), tx2);
         assert!(second.is_ok());
 
         let second = second.unwrap();
@@ -222,7 +455,8 @@ mod tests {
         // Create an erc20 transaction request and execute it with revm
         let tx = erc20::transfer(&provider, weth, bob, U256::from(VALUE));
 
-        let result = revm_call_read_only(&mut cache_db, tx);
+        let result =
+            revm_call_read_only(&mut cache_db, &CfgEnv::default(), &BlockEnv::default(), tx);
         assert!(result.is_ok());
 
         let log = result.unwrap();
@@ -247,4 +481,116 @@ mod tests {
         assert_eq!(slot_changed.original_value, U256::ZERO);
         assert_eq!(slot_changed.present_value, U256::from(VALUE));
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_revm_call_block_env() {
+        let (provider, client) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+
+        let mut cache_db = init_cache_db(provider.clone(), BlockNumberOrTag::Latest.into());
+
+        // Contract which returns block.timestamp: TIMESTAMP PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN
+        let contract =
+            addresses::get_address("0x4242424242424242424242424242424242424242").unwrap();
+        cache_db.insert_account_info(
+            contract,
+            AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(&[
+                0x42, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+            ]))),
+        );
+
+        const TIMESTAMP: u64 = 1_700_000_000;
+        let block_env = BlockEnv {
+            timestamp: U256::from(TIMESTAMP),
+            ..Default::default()
+        };
+
+        let tx = TransactionRequest::default().to(contract).from(client);
+        let result =
+            revm_call_read_only(&mut cache_db, &CfgEnv::default(), &block_env, tx).unwrap();
+
+        let output = result.result.output().unwrap();
+        assert_eq!(U256::from_be_slice(output), U256::from(TIMESTAMP));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_revm_call_revert() {
+        let (provider, client) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+
+        let mut cache_db = init_cache_db(provider.clone(), BlockNumberOrTag::Latest.into());
+        let block_env = BlockEnv::default();
+
+        let bob = addresses::get_address("0x7E219AAf9339eA8f08c381632DEe3CeC94AA4054").unwrap();
+        let weth = *addresses::WETH;
+
+        // Our client holds no WETH so the transfer reverts
+        let tx = erc20::transfer(&provider, weth, bob, U256::from(150)).from(client);
+        let nonce = cache_db.basic(client).unwrap().unwrap().nonce;
+
+        let outcome =
+            revm_call_write(&mut cache_db, &CfgEnv::default(), &block_env, tx.clone()).unwrap();
+        assert!(outcome.is_revert());
+        assert!(!outcome.committed);
+        assert!(outcome.gas_used() > 0);
+        // WETH reverts without a reason
+        assert_eq!(
+            outcome.revert_reason,
+            Some(RevertReason::Unknown(Bytes::new()))
+        );
+        assert_eq!(cache_db.basic(client).unwrap().unwrap().nonce, nonce);
+
+        // Reverting transactions marked can_revert still bump the nonce
+        let outcome =
+            revm_call_write_can_revert(&mut cache_db, &CfgEnv::default(), &block_env, tx).unwrap();
+        assert!(outcome.is_revert());
+        assert!(outcome.committed);
+        assert_eq!(cache_db.basic(client).unwrap().unwrap().nonce, nonce + 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_revm_trace_read_only() {
+        let (provider, client) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+
+        let mut cache_db = init_cache_db(provider.clone(), BlockNumberOrTag::Latest.into());
+
+        // The router emits no event itself, the swap only shows up in the pair
+        let tx = router02::swap_exact_ethfor_tokens(
+            &provider,
+            *addresses::UNISWAP_V2_ROUTER,
+            *addresses::USDC_ADDR,
+            U256::from(1e18 as u64),
+            U256::ZERO,
+            client,
+            U256::MAX,
+        )
+        .from(client);
+
+        let (outcome, trace) =
+            revm_trace_read_only(&mut cache_db, &CfgEnv::default(), &BlockEnv::default(), tx)
+                .unwrap();
+        assert!(outcome.is_success());
+        assert!(!outcome.committed);
+
+        assert_eq!(trace.depth, 0);
+        assert_eq!(trace.from, client);
+        assert_eq!(trace.to, *addresses::UNISWAP_V2_ROUTER);
+        assert!(trace.success);
+        assert!(trace.logs.is_empty());
+        assert!(!trace.calls.is_empty());
+
+        let touched = trace.touched_addresses();
+        assert!(touched.contains(&*addresses::WETH_USDC_PAIR));
+        assert!(touched.contains(&*addresses::WETH));
+
+        let swap =
+            alloy::primitives::keccak256("Swap(address,uint256,uint256,uint256,uint256,address)");
+        let swaps = trace.logs_with_topic(swap);
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(swaps[0].address, *addresses::WETH_USDC_PAIR);
+
+        // Same logs as the execution result
+        assert_eq!(trace.logs().len(), outcome.result.logs().len());
+    }
 }
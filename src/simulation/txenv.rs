@@ -0,0 +1,229 @@
+use alloy::{
+    consensus::Transaction as _,
+    primitives::{B256, U256},
+    rpc::types::{Transaction, TransactionRequest},
+};
+use revm::primitives::TxEnv;
+
+/// Convert a transaction request into a revm transaction environment.
+///
+/// Legacy and EIP-2930 requests pay `gas_price`, EIP-1559 and EIP-4844 requests
+/// pay at most `max_fee_per_gas` with a tip of `max_priority_fee_per_gas`, so
+/// revm charges the same effective gas price a builder would. Unset fields keep
+/// the revm defaults.
+///
+/// # Arguments
+///
+/// * `request` - The transaction request.
+///
+/// # Returns
+///
+/// * `TxEnv` - The transaction environment.
+pub fn tx_env_from_request(request: TransactionRequest) -> TxEnv {
+    let default = TxEnv::default();
+
+    // A max fee marks a dynamic fee request, the priority fee is meaningless without it
+    let (gas_price, gas_priority_fee) = match (request.max_fee_per_gas, request.gas_price) {
+        (Some(max_fee_per_gas), _) => (
+            U256::from(max_fee_per_gas),
+            request.max_priority_fee_per_gas.map(U256::from),
+        ),
+        (None, Some(gas_price)) => (U256::from(gas_price), None),
+        (None, None) => (default.gas_price, None),
+    };
+
+    // Blob hashes can also be derived from the sidecar
+    let blob_hashes = match (request.blob_versioned_hashes, request.sidecar) {
+        (Some(hashes), _) => hashes,
+        (None, Some(sidecar)) => sidecar.versioned_hashes().collect(),
+        (None, None) => Vec::new(),
+    };
+
+    TxEnv {
+        caller: request.from.unwrap_or(default.caller),
+        gas_limit: request.gas.unwrap_or(default.gas_limit),
+        gas_price,
+        gas_priority_fee,
+        transact_to: request.to.unwrap_or(default.transact_to),
+        value: request.value.unwrap_or(default.value),
+        data: request.input.into_input().unwrap_or_default(),
+        nonce: request.nonce,
+        chain_id: request.chain_id,
+        access_list: request.access_list.map(|list| list.0).unwrap_or_default(),
+        blob_hashes,
+        max_fee_per_blob_gas: request.max_fee_per_blob_gas.map(U256::from),
+        ..default
+    }
+}
+
+/// Convert a signed transaction, e.g. a pending one from the mempool, into a revm
+/// transaction environment.
+///
+/// # Arguments
+///
+/// * `tx` - The signed transaction.
+///
+/// # Returns
+///
+/// * `TxEnv` - The transaction environment.
+pub fn tx_env_from_transaction(tx: &Transaction) -> TxEnv {
+    // Only legacy and EIP-2930 transactions have a fixed gas price
+    let (gas_price, gas_priority_fee) = match tx.gas_price() {
+        Some(gas_price) => (gas_price, None),
+        None => (tx.max_fee_per_gas(), tx.max_priority_fee_per_gas()),
+    };
+
+    TxEnv {
+        caller: tx.from,
+        gas_limit: tx.gas_limit(),
+        gas_price: U256::from(gas_price),
+        gas_priority_fee: gas_priority_fee.map(U256::from),
+        transact_to: tx.kind(),
+        value: tx.value(),
+        data: tx.input().clone(),
+        nonce: Some(tx.nonce()),
+        chain_id: tx.chain_id(),
+        access_list: tx
+            .access_list()
+            .map(|list| list.0.clone())
+            .unwrap_or_default(),
+        blob_hashes: tx
+            .blob_versioned_hashes()
+            .map(<[B256]>::to_vec)
+            .unwrap_or_default(),
+        max_fee_per_blob_gas: tx.max_fee_per_blob_gas().map(U256::from),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        eips::eip2930::{AccessList, AccessListItem},
+        network::TransactionBuilder,
+        primitives::{Address, Bytes, TxKind},
+        signers::local::PrivateKeySigner,
+    };
+
+    use crate::utils::testtx;
+
+    use super::*;
+
+    fn access_list() -> AccessList {
+        AccessList(vec![AccessListItem {
+            address: Address::repeat_byte(0x11),
+            storage_keys: vec![B256::repeat_byte(0x22)],
+        }])
+    }
+
+    #[test]
+    fn test_legacy_request() {
+        let from = Address::repeat_byte(0x01);
+        let to = Address::repeat_byte(0x02);
+
+        let request = TransactionRequest::default()
+            .from(from)
+            .to(to)
+            .value(U256::from(150))
+            .nonce(7)
+            .gas_limit(50_000)
+            .with_gas_price(20_000_000_000)
+            .with_chain_id(1)
+            .input(Bytes::from_static(&[0x01, 0x02]).into());
+
+        let tx_env = tx_env_from_request(request);
+
+        assert_eq!(tx_env.caller, from);
+        assert_eq!(tx_env.transact_to, TxKind::Call(to));
+        assert_eq!(tx_env.value, U256::from(150));
+        assert_eq!(tx_env.nonce, Some(7));
+        assert_eq!(tx_env.gas_limit, 50_000);
+        assert_eq!(tx_env.gas_price, U256::from(20_000_000_000u64));
+        assert_eq!(tx_env.gas_priority_fee, None);
+        assert_eq!(tx_env.chain_id, Some(1));
+        assert_eq!(tx_env.data, Bytes::from_static(&[0x01, 0x02]));
+    }
+
+    #[test]
+    fn test_eip2930_request() {
+        let request = TransactionRequest::default()
+            .with_gas_price(10)
+            .access_list(access_list());
+
+        let tx_env = tx_env_from_request(request);
+
+        assert_eq!(tx_env.gas_price, U256::from(10));
+        assert_eq!(tx_env.access_list, access_list().0);
+    }
+
+    #[test]
+    fn test_eip1559_request() {
+        let request = TransactionRequest::default()
+            .max_fee_per_gas(100)
+            .max_priority_fee_per_gas(2);
+
+        let tx_env = tx_env_from_request(request);
+
+        assert_eq!(tx_env.gas_price, U256::from(100));
+        assert_eq!(tx_env.gas_priority_fee, Some(U256::from(2)));
+    }
+
+    #[test]
+    fn test_eip4844_request() {
+        let hashes = vec![B256::repeat_byte(0x01), B256::repeat_byte(0x02)];
+        let request = TransactionRequest {
+            blob_versioned_hashes: Some(hashes.clone()),
+            max_fee_per_blob_gas: Some(5),
+            ..Default::default()
+        }
+        .max_fee_per_gas(100)
+        .max_priority_fee_per_gas(2);
+
+        let tx_env = tx_env_from_request(request);
+
+        assert_eq!(tx_env.blob_hashes, hashes);
+        assert_eq!(tx_env.max_fee_per_blob_gas, Some(U256::from(5)));
+    }
+
+    #[test]
+    fn test_empty_request_keeps_defaults() {
+        let tx_env = tx_env_from_request(TransactionRequest::default());
+        let default = TxEnv::default();
+
+        assert_eq!(tx_env.caller, default.caller);
+        assert_eq!(tx_env.gas_limit, default.gas_limit);
+        assert_eq!(tx_env.gas_price, default.gas_price);
+        assert_eq!(tx_env.transact_to, default.transact_to);
+        assert_eq!(tx_env.data, default.data);
+    }
+
+    #[tokio::test]
+    async fn test_signed_transaction() {
+        let signer = PrivateKeySigner::random();
+        let from = signer.address();
+        let to = Address::repeat_byte(0x02);
+
+        let request = TransactionRequest::default()
+            .with_to(to)
+            .with_value(U256::from(150))
+            .with_nonce(3)
+            .with_chain_id(1)
+            .with_gas_limit(21_000)
+            .with_max_fee_per_gas(100)
+            .with_max_priority_fee_per_gas(2)
+            .access_list(access_list());
+        let tx = testtx::sign_pending(&signer, request).await;
+
+        let tx_env = tx_env_from_transaction(&tx);
+
+        assert_eq!(tx_env.caller, from);
+        assert_eq!(tx_env.transact_to, TxKind::Call(to));
+        assert_eq!(tx_env.value, U256::from(150));
+        assert_eq!(tx_env.nonce, Some(3));
+        assert_eq!(tx_env.chain_id, Some(1));
+        assert_eq!(tx_env.gas_limit, 21_000);
+        assert_eq!(tx_env.gas_price, U256::from(100));
+        assert_eq!(tx_env.gas_priority_fee, Some(U256::from(2)));
+        assert_eq!(tx_env.access_list, access_list().0);
+    }
+}
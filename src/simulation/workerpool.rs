@@ -0,0 +1,119 @@
+use std::{num::NonZeroUsize, sync::Arc, thread};
+
+use eyre::Result;
+use tokio::sync::Semaphore;
+
+/// Bounded pool for blocking simulations.
+///
+/// `AlloyDB` blocks on RPC calls, so simulations run on tokio's blocking threads
+/// instead of the runtime workers. The pool caps how many run at once, so a burst
+/// of candidates does not exhaust the blocking threads or flood the RPC node.
+#[derive(Debug, Clone)]
+pub struct WorkerPool {
+    permits: Arc<Semaphore>,
+}
+
+impl WorkerPool {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(workers.max(1))),
+        }
+    }
+
+    /// Number of workers which are currently idle.
+    pub fn available(&self) -> usize {
+        self.permits.available_permits()
+    }
+
+    /// Run a blocking closure once a worker is free.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The blocking work, e.g. `EngineTask::consume`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<R>` - The result of the closure, or an error if it panicked.
+    pub async fn spawn<F, R>(&self, f: F) -> Result<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let permit = self.permits.clone().acquire_owned().await?;
+
+        let result = tokio::task::spawn_blocking(move || {
+            // Hold the worker until the simulation is done
+            let _permit = permit;
+            f()
+        })
+        .await?;
+
+        Ok(result)
+    }
+}
+
+impl Default for WorkerPool {
+    /// One worker per available core.
+    fn default() -> Self {
+        Self::new(thread::available_parallelism().map_or(1, NonZeroUsize::get))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_worker_pool_spawn() {
+        let pool = WorkerPool::new(2);
+
+        let result = pool.spawn(|| 1 + 1).await.unwrap();
+        assert_eq!(result, 2);
+        assert_eq!(pool.available(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_worker_pool_bounded() {
+        const WORKERS: usize = 2;
+
+        let pool = WorkerPool::new(WORKERS);
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let jobs = (0..8).map(|_| {
+            let running = running.clone();
+            let peak = peak.clone();
+            pool.spawn(move || {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(20));
+                running.fetch_sub(1, Ordering::SeqCst);
+            })
+        });
+
+        for result in futures_util::future::join_all(jobs).await {
+            assert!(result.is_ok());
+        }
+
+        assert_eq!(peak.load(Ordering::SeqCst), WORKERS);
+        assert_eq!(pool.available(), WORKERS);
+    }
+
+    #[tokio::test]
+    async fn test_worker_pool_panic() {
+        let pool = WorkerPool::new(1);
+
+        let result = pool
+            .spawn(|| -> u64 { panic!("simulation panicked") })
+            .await;
+        assert!(result.is_err());
+
+        // The worker is released again
+        assert_eq!(pool.available(), 1);
+    }
+}
@@ -0,0 +1,127 @@
+use alloy::primitives::{U256, U512};
+use eyre::{eyre, Result};
+
+/// Calculate `floor(a * b / denominator)` with full precision, mirroring `FullMath.mulDiv`.
+///
+/// # Arguments
+///
+/// * `a` - The multiplicand.
+/// * `b` - The multiplier.
+/// * `denominator` - The divisor.
+///
+/// # Returns
+///
+/// * `Result<U256>` - The result, or an error if the denominator is zero or the result overflows.
+pub fn mul_div(a: U256, b: U256, denominator: U256) -> Result<U256> {
+    if denominator.is_zero() {
+        return Err(eyre!("mul_div by zero"));
+    }
+
+    let quotient = U512::from(a) * U512::from(b) / U512::from(denominator);
+    if quotient > U512::from(U256::MAX) {
+        return Err(eyre!("mul_div overflow"));
+    }
+
+    Ok(U256::from(quotient))
+}
+
+/// Calculate `ceil(a * b / denominator)` with full precision, mirroring `FullMath.mulDivRoundingUp`.
+///
+/// # Arguments
+///
+/// * `a` - The multiplicand.
+/// * `b` - The multiplier.
+/// * `denominator` - The divisor.
+///
+/// # Returns
+///
+/// * `Result<U256>` - The result, or an error if the denominator is zero or the result overflows.
+pub fn mul_div_rounding_up(a: U256, b: U256, denominator: U256) -> Result<U256> {
+    let result = mul_div(a, b, denominator)?;
+    if a.mul_mod(b, denominator).is_zero() {
+        return Ok(result);
+    }
+
+    result
+        .checked_add(U256::from(1))
+        .ok_or_else(|| eyre!("mul_div_rounding_up overflow"))
+}
+
+/// Calculate `ceil(x / y)`, mirroring `UnsafeMath.divRoundingUp`.
+///
+/// Unlike the Solidity version, dividing by zero is an error instead of returning zero.
+pub fn div_rounding_up(x: U256, y: U256) -> Result<U256> {
+    if y.is_zero() {
+        return Err(eyre!("div_rounding_up by zero"));
+    }
+
+    let quotient = x / y;
+    Ok(if (x % y).is_zero() {
+        quotient
+    } else {
+        quotient + U256::from(1)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const Q128: U256 = U256::from_limbs([0, 0, 1, 0]);
+
+    #[test]
+    fn test_mul_div() {
+        assert_eq!(
+            mul_div(Q128, U256::from(50), U256::from(100)).unwrap(),
+            Q128 / U256::from(2)
+        );
+
+        // The intermediate product overflows 256 bits but the result does not
+        assert_eq!(mul_div(U256::MAX, U256::MAX, U256::MAX).unwrap(), U256::MAX);
+        assert_eq!(
+            mul_div(Q128, U256::from(1000), U256::from(3000) * Q128).unwrap(),
+            U256::ZERO
+        );
+
+        assert!(mul_div(Q128, U256::from(5), U256::ZERO).is_err());
+        assert!(mul_div(Q128, Q128, U256::from(1)).is_err());
+    }
+
+    #[test]
+    fn test_mul_div_rounding_up() {
+        assert_eq!(
+            mul_div_rounding_up(U256::from(5), U256::from(3), U256::from(2)).unwrap(),
+            U256::from(8)
+        );
+        assert_eq!(
+            mul_div_rounding_up(U256::from(6), U256::from(3), U256::from(2)).unwrap(),
+            U256::from(9)
+        );
+
+        // Rounding up would overflow
+        assert!(mul_div_rounding_up(U256::MAX, U256::MAX - U256::from(1), U256::MAX).is_ok());
+        assert!(mul_div_rounding_up(
+            U256::from(535006138814359u64),
+            U256::from_str_radix(
+                "432862656469423142931042426214547535783388063929571229938474969",
+                10
+            )
+            .unwrap(),
+            U256::from(2)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_div_rounding_up() {
+        assert_eq!(
+            div_rounding_up(U256::from(7), U256::from(2)).unwrap(),
+            U256::from(4)
+        );
+        assert_eq!(
+            div_rounding_up(U256::from(8), U256::from(2)).unwrap(),
+            U256::from(4)
+        );
+        assert!(div_rounding_up(U256::from(8), U256::ZERO).is_err());
+    }
+}
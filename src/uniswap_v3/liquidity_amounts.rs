@@ -0,0 +1,257 @@
+use alloy::primitives::U256;
+use eyre::{eyre, Result};
+
+use super::{full_math::mul_div, sqrt_price_math::Q96};
+
+fn sorted(a: U256, b: U256) -> (U256, U256) {
+    if a > b {
+        (b, a)
+    } else {
+        (a, b)
+    }
+}
+
+fn to_u128(value: U256) -> Result<u128> {
+    u128::try_from(value).map_err(|_| eyre!("Liquidity {} overflows uint128", value))
+}
+
+/// Calculate the liquidity an amount of token0 provides, mirroring
+/// `LiquidityAmounts.getLiquidityForAmount0`.
+///
+/// # Arguments
+///
+/// * `sqrt_ratio_a_x96` - One of the range's sqrt prices, in any order.
+/// * `sqrt_ratio_b_x96` - The other sqrt price.
+/// * `amount0` - The amount of token0.
+///
+/// # Returns
+///
+/// * `Result<u128>` - The liquidity.
+pub fn get_liquidity_for_amount0(
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    amount0: U256,
+) -> Result<u128> {
+    let (lower, upper) = sorted(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+    let intermediate = mul_div(lower, upper, Q96)?;
+    to_u128(mul_div(amount0, intermediate, upper - lower)?)
+}
+
+/// Calculate the liquidity an amount of token1 provides, mirroring
+/// `LiquidityAmounts.getLiquidityForAmount1`.
+///
+/// # Arguments
+///
+/// * `sqrt_ratio_a_x96` - One of the range's sqrt prices, in any order.
+/// * `sqrt_ratio_b_x96` - The other sqrt price.
+/// * `amount1` - The amount of token1.
+///
+/// # Returns
+///
+/// * `Result<u128>` - The liquidity.
+pub fn get_liquidity_for_amount1(
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    amount1: U256,
+) -> Result<u128> {
+    let (lower, upper) = sorted(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+    to_u128(mul_div(amount1, Q96, upper - lower)?)
+}
+
+/// Calculate the largest liquidity the given amounts provide at the current price,
+/// mirroring `LiquidityAmounts.getLiquidityForAmounts`.
+///
+/// # Arguments
+///
+/// * `sqrt_price_x96` - The current sqrt price of the pool.
+/// * `sqrt_ratio_a_x96` - One of the range's sqrt prices, in any order.
+/// * `sqrt_ratio_b_x96` - The other sqrt price.
+/// * `amount0` - The amount of token0 available.
+/// * `amount1` - The amount of token1 available.
+///
+/// # Returns
+///
+/// * `Result<u128>` - The liquidity.
+pub fn get_liquidity_for_amounts(
+    sqrt_price_x96: U256,
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    amount0: U256,
+    amount1: U256,
+) -> Result<u128> {
+    let (lower, upper) = sorted(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+
+    if sqrt_price_x96 <= lower {
+        get_liquidity_for_amount0(lower, upper, amount0)
+    } else if sqrt_price_x96 < upper {
+        let liquidity0 = get_liquidity_for_amount0(sqrt_price_x96, upper, amount0)?;
+        let liquidity1 = get_liquidity_for_amount1(lower, sqrt_price_x96, amount1)?;
+        Ok(liquidity0.min(liquidity1))
+    } else {
+        get_liquidity_for_amount1(lower, upper, amount1)
+    }
+}
+
+/// Calculate the amount of token0 a position holds, mirroring
+/// `LiquidityAmounts.getAmount0ForLiquidity`.
+pub fn get_amount0_for_liquidity(
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    liquidity: u128,
+) -> Result<U256> {
+    let (lower, upper) = sorted(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+    if lower.is_zero() {
+        return Err(eyre!("Sqrt price must be positive"));
+    }
+
+    Ok(mul_div(U256::from(liquidity) << 96, upper - lower, upper)? / lower)
+}
+
+/// Calculate the amount of token1 a position holds, mirroring
+/// `LiquidityAmounts.getAmount1ForLiquidity`.
+pub fn get_amount1_for_liquidity(
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    liquidity: u128,
+) -> Result<U256> {
+    let (lower, upper) = sorted(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+    mul_div(U256::from(liquidity), upper - lower, Q96)
+}
+
+/// Calculate the amounts of token0 and token1 a position holds at the current price,
+/// mirroring `LiquidityAmounts.getAmountsForLiquidity`.
+///
+/// # Arguments
+///
+/// * `sqrt_price_x96` - The current sqrt price of the pool.
+/// * `sqrt_ratio_a_x96` - One of the range's sqrt prices, in any order.
+/// * `sqrt_ratio_b_x96` - The other sqrt price.
+/// * `liquidity` - The liquidity of the position.
+///
+/// # Returns
+///
+/// * `Result<(U256, U256)>` - The amounts of token0 and token1.
+pub fn get_amounts_for_liquidity(
+    sqrt_price_x96: U256,
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    liquidity: u128,
+) -> Result<(U256, U256)> {
+    let (lower, upper) = sorted(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+
+    if sqrt_price_x96 <= lower {
+        Ok((
+            get_amount0_for_liquidity(lower, upper, liquidity)?,
+            U256::ZERO,
+        ))
+    } else if sqrt_price_x96 < upper {
+        Ok((
+            get_amount0_for_liquidity(sqrt_price_x96, upper, liquidity)?,
+            get_amount1_for_liquidity(lower, sqrt_price_x96, liquidity)?,
+        ))
+    } else {
+        Ok((
+            U256::ZERO,
+            get_amount1_for_liquidity(lower, upper, liquidity)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        interfaces::uniswapv3pool::IUniswapV3Pool,
+        uniswap_v3::tick_math,
+        utils::{addresses, setup},
+    };
+
+    use super::*;
+
+    fn u256(value: &str) -> U256 {
+        U256::from_str_radix(value, 10).unwrap()
+    }
+
+    // `encodePriceSqrt(1, 1)`, `encodePriceSqrt(100, 110)` and `encodePriceSqrt(110, 100)`
+    fn prices() -> (U256, U256, U256) {
+        (
+            Q96,
+            u256("75541088972021052632782079082"),
+            u256("83095197869223157896060286990"),
+        )
+    }
+
+    #[test]
+    fn test_get_liquidity_for_amounts() {
+        let (price, lower, upper) = prices();
+
+        // Price inside the range
+        assert_eq!(
+            get_liquidity_for_amounts(price, lower, upper, U256::from(100), U256::from(200))
+                .unwrap(),
+            2148
+        );
+
+        // Price below the range, encodePriceSqrt(99, 110), only needs token0
+        let below = u256("75162434512514379355924140470");
+        assert_eq!(
+            get_liquidity_for_amounts(below, lower, upper, U256::from(100), U256::from(200))
+                .unwrap(),
+            1048
+        );
+
+        // Price above the range only needs token1
+        let above = upper + U256::from(1);
+        assert_eq!(
+            get_liquidity_for_amounts(above, lower, upper, U256::from(100), U256::from(200))
+                .unwrap(),
+            2097
+        );
+    }
+
+    #[test]
+    fn test_get_amounts_for_liquidity() {
+        let (price, lower, upper) = prices();
+
+        assert_eq!(
+            get_amounts_for_liquidity(price, lower, upper, 2148).unwrap(),
+            (U256::from(99), U256::from(99))
+        );
+        assert_eq!(
+            get_amounts_for_liquidity(lower, lower, upper, 1048).unwrap(),
+            (U256::from(99), U256::ZERO)
+        );
+        assert_eq!(
+            get_amounts_for_liquidity(upper, lower, upper, 2097).unwrap(),
+            (U256::ZERO, U256::from(199))
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_matches_mainnet_pool() {
+        let (provider, _) = setup::test_setup().await;
+        let pool = addresses::get_address("0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640").unwrap();
+        let pool = IUniswapV3Pool::new(pool, Arc::new(provider));
+
+        let slot0 = pool.slot0().call().await.unwrap();
+        let sqrt_price = U256::from(slot0.sqrtPriceX96);
+
+        // The pool stores the tick it derived from its own sqrt price
+        assert_eq!(
+            tick_math::get_tick_at_sqrt_ratio(sqrt_price).unwrap(),
+            slot0.tick.as_i32()
+        );
+
+        let lower = tick_math::get_sqrt_ratio_at_tick(slot0.tick.as_i32() - 10).unwrap();
+        let upper = tick_math::get_sqrt_ratio_at_tick(slot0.tick.as_i32() + 10).unwrap();
+        let (amount0, amount1) =
+            get_amounts_for_liquidity(sqrt_price, lower, upper, 1_000_000_000_000).unwrap();
+        assert!(amount0 > U256::ZERO && amount1 > U256::ZERO);
+
+        // Converting the amounts back loses at most rounding dust
+        let liquidity =
+            get_liquidity_for_amounts(sqrt_price, lower, upper, amount0, amount1).unwrap();
+        assert!(liquidity <= 1_000_000_000_000 && liquidity > 999_000_000_000);
+    }
+}
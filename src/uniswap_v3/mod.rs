@@ -0,0 +1,7 @@
+pub mod full_math;
+pub mod liquidity_amounts;
+pub mod pool;
+pub mod sqrt_price_math;
+pub mod swap_math;
+pub mod tick_bitmap;
+pub mod tick_math;
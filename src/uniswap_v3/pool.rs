@@ -0,0 +1,860 @@
+use std::{collections::HashMap, fmt::Debug};
+
+use alloy::{
+    eips::BlockId,
+    network::Network,
+    primitives::{keccak256, Address, I256, U256},
+    providers::Provider,
+    rpc::types::{TransactionInput, TransactionRequest},
+    sol_types::SolCall,
+    transports::Transport,
+};
+use eyre::{eyre, Result};
+use futures_util::future::try_join_all;
+use revm::{
+    primitives::{BlockEnv, CfgEnv},
+    Database, DatabaseCommit,
+};
+
+use crate::{interfaces::uniswapv3pool::IUniswapV3Pool, simulation::tracing};
+
+use super::{
+    full_math::mul_div,
+    swap_math::compute_swap_step,
+    tick_bitmap::{self, compress, next_initialized_tick_within_one_word},
+    tick_math::{
+        get_sqrt_ratio_at_tick, get_tick_at_sqrt_ratio, MAX_SQRT_RATIO, MAX_TICK, MIN_SQRT_RATIO,
+        MIN_TICK,
+    },
+};
+
+// Storage slots of `UniswapV3Pool`, fee and tick spacing are immutables
+pub const SLOT0_SLOT: u64 = 0;
+const LIQUIDITY_SLOT: u64 = 4;
+const TICKS_SLOT: u64 = 5;
+const TICK_BITMAP_SLOT: u64 = 6;
+
+// `2^128`, the scale of fee growth
+const Q128: U256 = U256::from_limbs([0, 0, 1, 0]);
+
+/// Liquidity referencing an initialized tick, see `Tick.Info`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TickInfo {
+    /// Total liquidity of the positions using the tick as a boundary
+    pub liquidity_gross: u128,
+    /// Liquidity added when the tick is crossed upwards
+    pub liquidity_net: i128,
+}
+
+/// Liquidity moved through a single step of a swap, see `UniswapV3PoolState::swap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapStepRecord {
+    pub sqrt_price_start_x96: U256,
+    pub sqrt_price_end_x96: U256,
+    /// Pool tick while the step executed
+    pub tick: i32,
+    /// Liquidity in range while the step executed
+    pub liquidity: u128,
+    /// Fee paid to liquidity providers, after the protocol fee
+    pub fee_amount: U256,
+}
+
+/// Result of a simulated swap, from the pool's point of view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapOutcome {
+    pub zero_for_one: bool,
+    /// Positive amounts are paid into the pool, negative amounts are paid out
+    pub amount0: I256,
+    pub amount1: I256,
+    pub steps: Vec<SwapStepRecord>,
+}
+
+impl SwapOutcome {
+    /// Fees a position earns from this swap, in the input token.
+    ///
+    /// Mirrors the pool's fee growth accounting, so the result is exact if the
+    /// position was part of the pool state when the swap was simulated, see
+    /// `UniswapV3PoolState::update_position`.
+    ///
+    /// # Arguments
+    ///
+    /// * `tick_lower` - The lower tick of the position.
+    /// * `tick_upper` - The upper tick of the position.
+    /// * `liquidity` - The liquidity of the position.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<U256>` - The fees earned.
+    pub fn fees_for_position(
+        &self,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: u128,
+    ) -> Result<U256> {
+        let mut fee_growth = U256::ZERO;
+        for step in &self.steps {
+            if step.liquidity == 0 || step.tick < tick_lower || step.tick >= tick_upper {
+                continue;
+            }
+            fee_growth = fee_growth.wrapping_add(mul_div(
+                step.fee_amount,
+                Q128,
+                U256::from(step.liquidity),
+            )?);
+        }
+
+        mul_div(fee_growth, U256::from(liquidity), Q128)
+    }
+
+    /// Total fees paid to liquidity providers, in the input token.
+    pub fn fee_amount(&self) -> U256 {
+        self.steps.iter().map(|step| step.fee_amount).sum()
+    }
+}
+
+/// Tokens and fee tier which identify a pool, see `PoolAddress.PoolKey`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PoolKey {
+    pub token0: Address,
+    pub token1: Address,
+    /// Fee in hundredths of a bip
+    pub fee: u32,
+}
+
+impl PoolKey {
+    /// Read the key of a pool from simulated state.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache_db` - The database.
+    /// * `pool` - The pool address.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - The pool key.
+    pub fn load_from_db<DB>(cache_db: &mut DB, pool: Address) -> Result<Self>
+    where
+        DB: Database + DatabaseCommit,
+        DB::Error: Debug,
+    {
+        // All three are immutables which live in the code
+        Ok(Self {
+            token0: view_call(cache_db, pool, IUniswapV3Pool::token0Call {})?._0,
+            token1: view_call(cache_db, pool, IUniswapV3Pool::token1Call {})?._0,
+            fee: view_call(cache_db, pool, IUniswapV3Pool::feeCall {})?
+                ._0
+                .to::<u32>(),
+        })
+    }
+}
+
+/// Split a packed `slot0` word into the sqrt price and the tick.
+///
+/// `slot0` packs sqrtPriceX96 (160 bits), tick (24 bits) and the oracle and fee
+/// protocol fields above them.
+pub fn unpack_slot0(slot0: U256) -> (U256, i32) {
+    let raw_tick = ((slot0.as_limbs()[2] >> 32) & 0xff_ffff) as u32;
+    (slot0 & (U256::MAX >> 96), ((raw_tick << 8) as i32) >> 8)
+}
+
+/// Off-chain model of a Uniswap V3 pool which simulates swaps without the EVM.
+///
+/// Only the tick bitmap words which were loaded can be traversed, a swap which
+/// runs past them fails instead of silently skipping liquidity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniswapV3PoolState {
+    pub address: Address,
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    pub liquidity: u128,
+    /// Fee in hundredths of a bip
+    pub fee: u32,
+    pub tick_spacing: i32,
+    /// Protocol takes `1/n` of the fees, token0's `n` in the lower and token1's in the upper 4 bits
+    pub fee_protocol: u8,
+    bitmap: HashMap<i16, U256>,
+    // Every initialized tick in the loaded words
+    ticks: HashMap<i32, TickInfo>,
+}
+
+impl UniswapV3PoolState {
+    /// Load the state of a pool over RPC.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - A reference to the provider.
+    /// * `pool` - The pool address.
+    /// * `block` - The block to load the state at.
+    /// * `words` - Number of bitmap words loaded on each side of the current tick.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - The pool state.
+    pub async fn load<T, N, P>(
+        provider: &P,
+        pool: Address,
+        block: BlockId,
+        words: i16,
+    ) -> Result<Self>
+    where
+        T: Transport + Clone,
+        N: Network,
+        P: Provider<T, N>,
+    {
+        let contract = IUniswapV3Pool::new(pool, provider);
+
+        let slot0 = contract.slot0().block(block).call().await?;
+        let liquidity = contract.liquidity().block(block).call().await?._0;
+        let fee = contract.fee().block(block).call().await?._0;
+        let tick_spacing = contract
+            .tickSpacing()
+            .block(block)
+            .call()
+            .await?
+            ._0
+            .as_i32();
+
+        let mut state = Self {
+            address: pool,
+            sqrt_price_x96: U256::from(slot0.sqrtPriceX96),
+            tick: slot0.tick.as_i32(),
+            liquidity,
+            fee: fee.to::<u32>(),
+            tick_spacing,
+            fee_protocol: slot0.feeProtocol,
+            bitmap: HashMap::new(),
+            ticks: HashMap::new(),
+        };
+
+        let positions = state.word_range(words);
+        let bitmap = try_join_all(positions.clone().map(|word| {
+            let contract = &contract;
+            async move { contract.tickBitmap(word).block(block).call().await }
+        }))
+        .await?;
+        state.bitmap = positions
+            .zip(bitmap.into_iter().map(|word| word._0))
+            .collect();
+
+        let initialized = state.initialized_ticks();
+        let ticks = try_join_all(initialized.iter().map(|tick| {
+            let contract = &contract;
+            async move {
+                let info = contract
+                    .ticks((*tick).try_into()?)
+                    .block(block)
+                    .call()
+                    .await?;
+                Ok::<_, eyre::Report>(TickInfo {
+                    liquidity_gross: info.liquidityGross,
+                    liquidity_net: info.liquidityNet,
+                })
+            }
+        }))
+        .await?;
+        state.ticks = initialized.into_iter().zip(ticks).collect();
+
+        Ok(state)
+    }
+
+    /// Load the state of a pool from a simulator database, e.g. a `CacheDB` after a bundle executed.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache_db` - The database.
+    /// * `pool` - The pool address.
+    /// * `words` - Number of bitmap words loaded on each side of the current tick.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - The pool state.
+    pub fn load_from_db<DB>(cache_db: &mut DB, pool: Address, words: i16) -> Result<Self>
+    where
+        DB: Database + DatabaseCommit,
+        DB::Error: Debug,
+    {
+        // Immutables live in the code, so ask the pool for them
+        let fee = view_call(cache_db, pool, IUniswapV3Pool::feeCall {})?._0;
+        let tick_spacing = view_call(cache_db, pool, IUniswapV3Pool::tickSpacingCall {})?._0;
+
+        let slot0 = storage(cache_db, pool, U256::from(SLOT0_SLOT))?;
+        let (sqrt_price_x96, tick) = unpack_slot0(slot0);
+        let fee_protocol = ((slot0.as_limbs()[3] >> 40) & 0xff) as u8;
+
+        let liquidity = storage(cache_db, pool, U256::from(LIQUIDITY_SLOT))?;
+
+        let mut state = Self {
+            address: pool,
+            sqrt_price_x96,
+            tick,
+            liquidity: u128::try_from(liquidity & U256::from(u128::MAX))?,
+            fee: fee.to::<u32>(),
+            tick_spacing: tick_spacing.as_i32(),
+            fee_protocol,
+            bitmap: HashMap::new(),
+            ticks: HashMap::new(),
+        };
+
+        for word in state.word_range(words) {
+            let slot = mapping_slot(word as i32, TICK_BITMAP_SLOT);
+            state.bitmap.insert(word, storage(cache_db, pool, slot)?);
+        }
+
+        for tick in state.initialized_ticks() {
+            // The first slot of `Tick.Info` packs liquidityGross below liquidityNet
+            let info = storage(cache_db, pool, mapping_slot(tick, TICKS_SLOT))?;
+            state.ticks.insert(
+                tick,
+                TickInfo {
+                    liquidity_gross: u128::try_from(info & U256::from(u128::MAX))?,
+                    liquidity_net: u128::try_from(info >> 128)? as i128,
+                },
+            );
+        }
+
+        Ok(state)
+    }
+
+    /// Add or remove liquidity of a position, mirroring `UniswapV3Pool._modifyPosition`.
+    ///
+    /// Lets a JIT position be modelled before the swap it targets.
+    ///
+    /// # Arguments
+    ///
+    /// * `tick_lower` - The lower tick of the position.
+    /// * `tick_upper` - The upper tick of the position.
+    /// * `liquidity_delta` - The liquidity added, or removed if negative.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error if the range is invalid or its words are not loaded.
+    pub fn update_position(
+        &mut self,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity_delta: i128,
+    ) -> Result<()> {
+        if tick_lower >= tick_upper || tick_lower < MIN_TICK || tick_upper > MAX_TICK {
+            return Err(eyre!("Invalid range {} {}", tick_lower, tick_upper));
+        }
+
+        self.update_tick(tick_lower, liquidity_delta, false)?;
+        self.update_tick(tick_upper, liquidity_delta, true)?;
+
+        if self.tick >= tick_lower && self.tick < tick_upper {
+            self.liquidity = add_delta(self.liquidity, liquidity_delta)?;
+        }
+
+        Ok(())
+    }
+
+    /// Simulate a swap and apply it to the state, mirroring `UniswapV3Pool.swap`.
+    ///
+    /// # Arguments
+    ///
+    /// * `zero_for_one` - Whether token0 is swapped for token1.
+    /// * `amount_specified` - The amount swapped, positive for exact input and negative for exact output.
+    /// * `sqrt_price_limit_x96` - The price the swap cannot move past, no limit if `None`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<SwapOutcome>` - The amounts swapped and the liquidity of each step.
+    pub fn swap(
+        &mut self,
+        zero_for_one: bool,
+        amount_specified: I256,
+        sqrt_price_limit_x96: Option<U256>,
+    ) -> Result<SwapOutcome> {
+        if amount_specified.is_zero() {
+            return Err(eyre!("Swap amount must not be zero"));
+        }
+
+        let sqrt_price_limit_x96 = sqrt_price_limit_x96.unwrap_or(if zero_for_one {
+            MIN_SQRT_RATIO + U256::from(1)
+        } else {
+            MAX_SQRT_RATIO - U256::from(1)
+        });
+        let valid_limit = if zero_for_one {
+            sqrt_price_limit_x96 < self.sqrt_price_x96 && sqrt_price_limit_x96 > MIN_SQRT_RATIO
+        } else {
+            sqrt_price_limit_x96 > self.sqrt_price_x96 && sqrt_price_limit_x96 < MAX_SQRT_RATIO
+        };
+        if !valid_limit {
+            return Err(eyre!("Invalid price limit {}", sqrt_price_limit_x96));
+        }
+
+        let exact_input = amount_specified > I256::ZERO;
+        // The protocol fee is charged in the input token
+        let fee_protocol = if zero_for_one {
+            self.fee_protocol % 16
+        } else {
+            self.fee_protocol >> 4
+        };
+        let mut amount_remaining = amount_specified;
+        let mut amount_calculated = I256::ZERO;
+        let mut steps = Vec::new();
+
+        // Work on a copy so a failed swap leaves the state untouched
+        let mut sqrt_price_x96 = self.sqrt_price_x96;
+        let mut tick = self.tick;
+        let mut liquidity = self.liquidity;
+
+        while !amount_remaining.is_zero() && sqrt_price_x96 != sqrt_price_limit_x96 {
+            let sqrt_price_start_x96 = sqrt_price_x96;
+
+            let (tick_next, initialized) = next_initialized_tick_within_one_word(
+                &self.bitmap,
+                tick,
+                self.tick_spacing,
+                zero_for_one,
+            )?;
+            let tick_next = tick_next.clamp(MIN_TICK, MAX_TICK);
+            let sqrt_price_next_x96 = get_sqrt_ratio_at_tick(tick_next)?;
+
+            let target = if zero_for_one {
+                sqrt_price_next_x96.max(sqrt_price_limit_x96)
+            } else {
+                sqrt_price_next_x96.min(sqrt_price_limit_x96)
+            };
+
+            let step = compute_swap_step(
+                sqrt_price_x96,
+                target,
+                liquidity,
+                amount_remaining,
+                self.fee,
+            )?;
+            sqrt_price_x96 = step.sqrt_price_next_x96;
+
+            let amount_in = I256::try_from(step.amount_in + step.fee_amount)?;
+            let amount_out = I256::try_from(step.amount_out)?;
+            if exact_input {
+                amount_remaining -= amount_in;
+                amount_calculated -= amount_out;
+            } else {
+                amount_remaining += amount_out;
+                amount_calculated += amount_in;
+            }
+
+            let mut fee_amount = step.fee_amount;
+            if fee_protocol > 0 {
+                fee_amount -= fee_amount / U256::from(fee_protocol);
+            }
+
+            steps.push(SwapStepRecord {
+                sqrt_price_start_x96,
+                sqrt_price_end_x96: sqrt_price_x96,
+                tick,
+                liquidity,
+                fee_amount,
+            });
+
+            if sqrt_price_x96 == sqrt_price_next_x96 {
+                if initialized {
+                    let liquidity_net = self
+                        .ticks
+                        .get(&tick_next)
+                        .ok_or_else(|| eyre!("Tick {} is not loaded", tick_next))?
+                        .liquidity_net;
+                    let liquidity_net = if zero_for_one {
+                        -liquidity_net
+                    } else {
+                        liquidity_net
+                    };
+                    liquidity = add_delta(liquidity, liquidity_net)?;
+                }
+                tick = if zero_for_one {
+                    tick_next - 1
+                } else {
+                    tick_next
+                };
+            } else if sqrt_price_x96 != sqrt_price_start_x96 {
+                tick = get_tick_at_sqrt_ratio(sqrt_price_x96)?;
+            }
+        }
+
+        self.sqrt_price_x96 = sqrt_price_x96;
+        self.tick = tick;
+        self.liquidity = liquidity;
+
+        let (amount0, amount1) = if zero_for_one == exact_input {
+            (amount_specified - amount_remaining, amount_calculated)
+        } else {
+            (amount_calculated, amount_specified - amount_remaining)
+        };
+
+        Ok(SwapOutcome {
+            zero_for_one,
+            amount0,
+            amount1,
+            steps,
+        })
+    }
+
+    /// Initialized ticks of the loaded bitmap words, in ascending order.
+    pub fn initialized_ticks(&self) -> Vec<i32> {
+        let mut ticks = self
+            .bitmap
+            .iter()
+            .flat_map(|(word, bits)| {
+                (0..256)
+                    .filter(|bit| bits.bit(*bit))
+                    .map(move |bit| ((*word as i32) * 256 + bit as i32) * self.tick_spacing)
+            })
+            .collect::<Vec<_>>();
+        ticks.sort_unstable();
+        ticks
+    }
+
+    /// Liquidity of an initialized tick, if its word is loaded.
+    pub fn tick_info(&self, tick: i32) -> Option<TickInfo> {
+        self.ticks.get(&tick).copied()
+    }
+
+    fn word_range(&self, words: i16) -> impl Iterator<Item = i16> + Clone {
+        let (current, _) = tick_bitmap::position(compress(self.tick, self.tick_spacing));
+        let (min, _) = tick_bitmap::position(compress(MIN_TICK, self.tick_spacing));
+        let (max, _) = tick_bitmap::position(compress(MAX_TICK, self.tick_spacing));
+
+        current.saturating_sub(words).max(min)..=current.saturating_add(words).min(max)
+    }
+
+    /// Mirrors `Tick.update`, flipping the tick in the bitmap once it gains or loses all liquidity.
+    fn update_tick(&mut self, tick: i32, liquidity_delta: i128, upper: bool) -> Result<()> {
+        let (word, _) = tick_bitmap::position(compress(tick, self.tick_spacing));
+        if !self.bitmap.contains_key(&word) {
+            return Err(eyre!("Tick bitmap word {} is not loaded", word));
+        }
+
+        let info = self.ticks.get(&tick).copied().unwrap_or_default();
+        let liquidity_gross = add_delta(info.liquidity_gross, liquidity_delta)?;
+        // Crossing the upper tick upwards removes the liquidity again
+        let liquidity_net = if upper {
+            info.liquidity_net.checked_sub(liquidity_delta)
+        } else {
+            info.liquidity_net.checked_add(liquidity_delta)
+        }
+        .ok_or_else(|| eyre!("Net liquidity overflow at tick {}", tick))?;
+
+        if (liquidity_gross == 0) != (info.liquidity_gross == 0) {
+            tick_bitmap::flip_tick(&mut self.bitmap, tick, self.tick_spacing)?;
+        }
+
+        if liquidity_gross == 0 {
+            self.ticks.remove(&tick);
+        } else {
+            self.ticks.insert(
+                tick,
+                TickInfo {
+                    liquidity_gross,
+                    liquidity_net,
+                },
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Add a signed delta to liquidity, mirroring `LiquidityMath.addDelta`.
+fn add_delta(liquidity: u128, delta: i128) -> Result<u128> {
+    liquidity
+        .checked_add_signed(delta)
+        .ok_or_else(|| eyre!("Liquidity {} cannot change by {}", liquidity, delta))
+}
+
+/// Storage slot of `mapping[key]` for a mapping with a signed integer key.
+fn mapping_slot(key: i32, slot: u64) -> U256 {
+    // Signed keys are sign extended to 32 bytes before hashing
+    let key = if key < 0 {
+        U256::from(key.unsigned_abs()).wrapping_neg()
+    } else {
+        U256::from(key)
+    };
+
+    let mut preimage = [0u8; 64];
+    preimage[..32].copy_from_slice(&key.to_be_bytes::<32>());
+    preimage[32..].copy_from_slice(&U256::from(slot).to_be_bytes::<32>());
+    U256::from_be_bytes(keccak256(preimage).0)
+}
+
+fn storage<DB>(cache_db: &mut DB, pool: Address, slot: U256) -> Result<U256>
+where
+    DB: Database,
+    DB::Error: Debug,
+{
+    cache_db
+        .storage(pool, slot)
+        .map_err(|e| eyre!("Could not fetch storage: {:?}", e))
+}
+
+/// Call a view function of `pool` on simulated state.
+pub(crate) fn view_call<DB, C>(cache_db: &mut DB, pool: Address, call: C) -> Result<C::Return>
+where
+    DB: Database + DatabaseCommit,
+    DB::Error: Debug,
+    C: SolCall,
+{
+    let tx = TransactionRequest::default()
+        .to(pool)
+        .input(TransactionInput::new(call.abi_encode().into()));
+
+    let result =
+        tracing::revm_call_read_only(cache_db, &CfgEnv::default(), &BlockEnv::default(), tx)?;
+    let output = result
+        .result
+        .output()
+        .ok_or_else(|| eyre!("No output from {}", C::SIGNATURE))?;
+
+    Ok(C::abi_decode_returns(output, true)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use alloy::{eips::BlockNumberOrTag, primitives::aliases::U24, sol};
+    use revm::db::CacheDB;
+
+    use crate::{
+        simulation::statecache::init_state_cache_db,
+        utils::{addresses, setup},
+    };
+
+    use super::*;
+
+    sol! {
+        #[sol(rpc)]
+        interface IQuoterV2 {
+            struct QuoteExactInputSingleParams {
+                address tokenIn;
+                address tokenOut;
+                uint256 amountIn;
+                uint24 fee;
+                uint160 sqrtPriceLimitX96;
+            }
+
+            function quoteExactInputSingle(QuoteExactInputSingleParams memory params)
+                external
+                returns (
+                    uint256 amountOut,
+                    uint160 sqrtPriceX96After,
+                    uint32 initializedTicksCrossed,
+                    uint256 gasEstimate
+                );
+        }
+    }
+
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    fn i256(value: i128) -> I256 {
+        I256::try_from(value).unwrap()
+    }
+
+    // A pool at price 1 with a single position around it
+    fn pool() -> UniswapV3PoolState {
+        let mut pool = UniswapV3PoolState {
+            address: Address::ZERO,
+            sqrt_price_x96: U256::from(1) << 96,
+            tick: 0,
+            liquidity: 0,
+            fee: 3000,
+            tick_spacing: 60,
+            fee_protocol: 0,
+            bitmap: HashMap::from([(-1, U256::ZERO), (0, U256::ZERO)]),
+            ticks: HashMap::new(),
+        };
+        pool.update_position(-600, 600, 2 * E18 as i128).unwrap();
+        pool
+    }
+
+    #[test]
+    fn test_update_position() {
+        let mut pool = pool();
+        assert_eq!(pool.liquidity, 2 * E18);
+        assert_eq!(pool.initialized_ticks(), vec![-600, 600]);
+        assert_eq!(
+            pool.tick_info(-600),
+            Some(TickInfo {
+                liquidity_gross: 2 * E18,
+                liquidity_net: 2 * E18 as i128,
+            })
+        );
+        assert_eq!(
+            pool.tick_info(600).unwrap().liquidity_net,
+            -(2 * E18 as i128)
+        );
+
+        // Out of range positions do not change the active liquidity
+        pool.update_position(60, 120, E18 as i128).unwrap();
+        assert_eq!(pool.liquidity, 2 * E18);
+
+        pool.update_position(60, 120, -(E18 as i128)).unwrap();
+        assert_eq!(pool.initialized_ticks(), vec![-600, 600]);
+
+        assert!(pool.update_position(600, -600, 1).is_err());
+        assert!(pool.update_position(-30, 600, 1).is_err());
+    }
+
+    #[test]
+    fn test_swap_within_range() {
+        let mut pool = pool();
+        let outcome = pool.swap(true, i256(E18 as i128 / 100), None).unwrap();
+
+        // Starting on an uninitialized tick boundary costs an empty step, like in the pool
+        assert_eq!(outcome.steps.len(), 2);
+        assert_eq!(outcome.steps[0].fee_amount, U256::ZERO);
+        assert_eq!(outcome.amount0, i256(E18 as i128 / 100));
+        assert!(outcome.amount1 < I256::ZERO);
+        assert!(outcome.fee_amount() >= U256::from(E18 / 100 * 3 / 1000));
+
+        assert!(pool.sqrt_price_x96 < U256::from(1) << 96);
+        assert_eq!(
+            pool.tick,
+            get_tick_at_sqrt_ratio(pool.sqrt_price_x96).unwrap()
+        );
+
+        // The only position earns every fee, minus rounding
+        let fees = outcome.fees_for_position(-600, 600, 2 * E18).unwrap();
+        assert!(fees <= outcome.fee_amount() && fees >= outcome.fee_amount() - U256::from(1));
+    }
+
+    #[test]
+    fn test_swap_crosses_ticks() {
+        let mut pool = pool();
+        pool.update_position(-120, -60, E18 as i128).unwrap();
+
+        // Exact output large enough to run through the extra position
+        let outcome = pool.swap(true, -i256(E18 as i128 / 25), None).unwrap();
+        assert_eq!(outcome.amount1, -i256(E18 as i128 / 25));
+        assert!(outcome.steps.len() > 2);
+        assert!(pool.tick < -120);
+
+        // The extra liquidity was active for part of the swap only
+        let inside = outcome.fees_for_position(-120, -60, E18).unwrap();
+        assert!(inside > U256::ZERO);
+        assert!(inside < outcome.fee_amount());
+
+        // Back below the position, only the wide one remains
+        assert_eq!(pool.liquidity, 2 * E18);
+    }
+
+    #[test]
+    fn test_swap_price_limit() {
+        let mut pool = pool();
+        let limit = get_sqrt_ratio_at_tick(-60).unwrap();
+
+        let outcome = pool.swap(true, i256(E18 as i128), Some(limit)).unwrap();
+        assert_eq!(pool.sqrt_price_x96, limit);
+        assert!(outcome.amount0 < i256(E18 as i128));
+
+        // The limit must be on the right side of the price
+        assert!(pool.swap(false, i256(1), Some(limit)).is_err());
+        assert!(pool.swap(true, i256(1), Some(U256::from(1) << 96)).is_err());
+    }
+
+    #[test]
+    fn test_swap_unloaded_word() {
+        let mut pool = pool();
+        let before = pool.clone();
+
+        // Runs out of liquidity at -600 and would continue into word -2
+        assert!(pool.swap(true, i256(E18 as i128 * 10), None).is_err());
+        assert_eq!(pool, before);
+    }
+
+    #[test]
+    fn test_unpack_slot0() {
+        // Tick -1 with the oracle and fee protocol fields set above it
+        let price = (U256::from(1) << 96) - U256::from(1);
+        let slot0 = (U256::from(0x44) << 232) | (U256::from(0xff_ffffu32) << 160) | price;
+
+        assert_eq!(unpack_slot0(slot0), (price, -1));
+        assert_eq!(unpack_slot0(U256::from(5) << 160), (U256::ZERO, 5));
+    }
+
+    #[test]
+    fn test_mapping_slot() {
+        // keccak256(abi.encode(int24(-1), uint256(5)))
+        let mut preimage = [0xffu8; 64];
+        preimage[32..].copy_from_slice(&U256::from(5).to_be_bytes::<32>());
+
+        assert_eq!(
+            mapping_slot(-1, TICKS_SLOT),
+            U256::from_be_bytes(keccak256(preimage).0)
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_load_matches_db() {
+        let (provider, _) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+        let pool = addresses::get_address("0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640").unwrap();
+
+        let block = provider.get_block_number().await.unwrap();
+        let state = UniswapV3PoolState::load(provider.as_ref(), pool, block.into(), 2)
+            .await
+            .unwrap();
+
+        assert_eq!(state.fee, 500);
+        assert_eq!(state.tick_spacing, 10);
+        assert!(state.liquidity > 0);
+        assert_eq!(state.bitmap.len(), 5);
+        assert!(!state.initialized_ticks().is_empty());
+
+        let mut cache_db = CacheDB::new(init_state_cache_db(
+            provider,
+            BlockNumberOrTag::Number(block).into(),
+            None,
+        ));
+        let (from_db, key) = tokio::task::spawn_blocking(move || {
+            (
+                UniswapV3PoolState::load_from_db(&mut cache_db, pool, 2).unwrap(),
+                PoolKey::load_from_db(&mut cache_db, pool).unwrap(),
+            )
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(from_db, state);
+        assert_eq!(key.token0, *addresses::USDC_ADDR);
+        assert_eq!(key.token1, *addresses::WETH);
+        assert_eq!(key.fee, 500);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    pub async fn test_swap_matches_quoter() {
+        let (provider, _) = setup::test_setup().await;
+        let provider = Arc::new(provider);
+        let pool = addresses::get_address("0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640").unwrap();
+        let quoter = addresses::get_address("0x61fFE014bA17989E743c5F6cB21bF9697530B21e").unwrap();
+
+        let block = provider.get_block_number().await.unwrap();
+        let mut state = UniswapV3PoolState::load(provider.as_ref(), pool, block.into(), 4)
+            .await
+            .unwrap();
+
+        // Sell 1000 WETH into the pool, enough to cross several ticks
+        let amount_in = U256::from(1000) * U256::from(E18);
+        let outcome = state
+            .swap(false, I256::try_from(amount_in).unwrap(), None)
+            .unwrap();
+
+        let quote = IQuoterV2::new(quoter, provider)
+            .quoteExactInputSingle(IQuoterV2::QuoteExactInputSingleParams {
+                tokenIn: *addresses::WETH,
+                tokenOut: *addresses::USDC_ADDR,
+                amountIn: amount_in,
+                fee: U24::from(state.fee),
+                sqrtPriceLimitX96: Default::default(),
+            })
+            .block(block.into())
+            .call()
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.amount1, I256::try_from(amount_in).unwrap());
+        assert_eq!(-outcome.amount0, I256::try_from(quote.amountOut).unwrap());
+        assert_eq!(state.sqrt_price_x96, U256::from(quote.sqrtPriceX96After));
+    }
+}
@@ -0,0 +1,382 @@
+use alloy::primitives::{I256, U256};
+use eyre::{eyre, Result};
+
+use super::full_math::{div_rounding_up, mul_div, mul_div_rounding_up};
+
+/// `2^96`, the scale of Q64.96 sqrt prices
+pub const Q96: U256 = U256::from_limbs([0, 1 << 32, 0, 0]);
+
+// Largest value a uint160 can hold
+const MAX_U160: U256 = U256::from_limbs([u64::MAX, u64::MAX, u32::MAX as u64, 0]);
+
+fn to_u160(value: U256) -> Result<U256> {
+    if value > MAX_U160 {
+        return Err(eyre!("Sqrt price {} overflows uint160", value));
+    }
+    Ok(value)
+}
+
+/// Calculate the next sqrt price after adding or removing `amount` of token0, mirroring
+/// `SqrtPriceMath.getNextSqrtPriceFromAmount0RoundingUp`.
+///
+/// # Arguments
+///
+/// * `sqrt_price_x96` - The starting sqrt price.
+/// * `liquidity` - The amount of usable liquidity.
+/// * `amount` - The amount of token0.
+/// * `add` - Whether the amount is added to or removed from the pool.
+///
+/// # Returns
+///
+/// * `Result<U256>` - The next sqrt price, rounded up.
+pub fn get_next_sqrt_price_from_amount0_rounding_up(
+    sqrt_price_x96: U256,
+    liquidity: u128,
+    amount: U256,
+    add: bool,
+) -> Result<U256> {
+    if amount.is_zero() {
+        return Ok(sqrt_price_x96);
+    }
+
+    let numerator1: U256 = U256::from(liquidity) << 96usize;
+    let product = amount.checked_mul(sqrt_price_x96);
+
+    if add {
+        // Use the more precise formula as long as nothing overflows
+        if let Some(denominator) = product.and_then(|product| numerator1.checked_add(product)) {
+            return mul_div_rounding_up(numerator1, sqrt_price_x96, denominator);
+        }
+
+        let denominator = (numerator1 / sqrt_price_x96)
+            .checked_add(amount)
+            .ok_or_else(|| eyre!("Sqrt price denominator overflow"))?;
+        div_rounding_up(numerator1, denominator)
+    } else {
+        let product = product
+            .filter(|product| numerator1 > *product)
+            .ok_or_else(|| eyre!("Not enough liquidity to remove {} of token0", amount))?;
+
+        to_u160(mul_div_rounding_up(
+            numerator1,
+            sqrt_price_x96,
+            numerator1 - product,
+        )?)
+    }
+}
+
+/// Calculate the next sqrt price after adding or removing `amount` of token1, mirroring
+/// `SqrtPriceMath.getNextSqrtPriceFromAmount1RoundingDown`.
+///
+/// # Arguments
+///
+/// * `sqrt_price_x96` - The starting sqrt price.
+/// * `liquidity` - The amount of usable liquidity.
+/// * `amount` - The amount of token1.
+/// * `add` - Whether the amount is added to or removed from the pool.
+///
+/// # Returns
+///
+/// * `Result<U256>` - The next sqrt price, rounded down.
+pub fn get_next_sqrt_price_from_amount1_rounding_down(
+    sqrt_price_x96: U256,
+    liquidity: u128,
+    amount: U256,
+    add: bool,
+) -> Result<U256> {
+    let liquidity = U256::from(liquidity);
+
+    if add {
+        let quotient = if amount <= MAX_U160 {
+            (amount << 96usize) / liquidity
+        } else {
+            mul_div(amount, Q96, liquidity)?
+        };
+
+        to_u160(
+            sqrt_price_x96
+                .checked_add(quotient)
+                .ok_or_else(|| eyre!("Sqrt price overflow"))?,
+        )
+    } else {
+        let quotient = if amount <= MAX_U160 {
+            div_rounding_up(amount << 96usize, liquidity)?
+        } else {
+            mul_div_rounding_up(amount, Q96, liquidity)?
+        };
+
+        if sqrt_price_x96 <= quotient {
+            return Err(eyre!("Not enough liquidity to remove {} of token1", amount));
+        }
+        Ok(sqrt_price_x96 - quotient)
+    }
+}
+
+/// Calculate the next sqrt price after swapping `amount_in` into the pool, mirroring
+/// `SqrtPriceMath.getNextSqrtPriceFromInput`.
+///
+/// # Arguments
+///
+/// * `sqrt_price_x96` - The starting sqrt price.
+/// * `liquidity` - The amount of usable liquidity.
+/// * `amount_in` - The amount of token0 or token1 swapped in.
+/// * `zero_for_one` - Whether token0 is swapped in.
+///
+/// # Returns
+///
+/// * `Result<U256>` - The next sqrt price, rounded so the price moves at least as far as it should.
+pub fn get_next_sqrt_price_from_input(
+    sqrt_price_x96: U256,
+    liquidity: u128,
+    amount_in: U256,
+    zero_for_one: bool,
+) -> Result<U256> {
+    if sqrt_price_x96.is_zero() || liquidity == 0 {
+        return Err(eyre!("Swap requires a price and liquidity"));
+    }
+
+    if zero_for_one {
+        get_next_sqrt_price_from_amount0_rounding_up(sqrt_price_x96, liquidity, amount_in, true)
+    } else {
+        get_next_sqrt_price_from_amount1_rounding_down(sqrt_price_x96, liquidity, amount_in, true)
+    }
+}
+
+/// Calculate the next sqrt price after swapping `amount_out` out of the pool, mirroring
+/// `SqrtPriceMath.getNextSqrtPriceFromOutput`.
+///
+/// # Arguments
+///
+/// * `sqrt_price_x96` - The starting sqrt price.
+/// * `liquidity` - The amount of usable liquidity.
+/// * `amount_out` - The amount of token0 or token1 swapped out.
+/// * `zero_for_one` - Whether token0 is swapped in, so token1 is swapped out.
+///
+/// # Returns
+///
+/// * `Result<U256>` - The next sqrt price, rounded so the price moves at least as far as it should.
+pub fn get_next_sqrt_price_from_output(
+    sqrt_price_x96: U256,
+    liquidity: u128,
+    amount_out: U256,
+    zero_for_one: bool,
+) -> Result<U256> {
+    if sqrt_price_x96.is_zero() || liquidity == 0 {
+        return Err(eyre!("Swap requires a price and liquidity"));
+    }
+
+    if zero_for_one {
+        get_next_sqrt_price_from_amount1_rounding_down(sqrt_price_x96, liquidity, amount_out, false)
+    } else {
+        get_next_sqrt_price_from_amount0_rounding_up(sqrt_price_x96, liquidity, amount_out, false)
+    }
+}
+
+/// Calculate the amount of token0 between two sqrt prices, mirroring `SqrtPriceMath.getAmount0Delta`.
+///
+/// # Arguments
+///
+/// * `sqrt_ratio_a_x96` - One of the sqrt prices, in any order.
+/// * `sqrt_ratio_b_x96` - The other sqrt price.
+/// * `liquidity` - The amount of usable liquidity.
+/// * `round_up` - Whether to round the amount up or down.
+///
+/// # Returns
+///
+/// * `Result<U256>` - The amount of token0.
+pub fn get_amount0_delta(
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<U256> {
+    let (lower, upper) = sorted(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+    if lower.is_zero() {
+        return Err(eyre!("Sqrt price must be positive"));
+    }
+
+    let numerator1: U256 = U256::from(liquidity) << 96usize;
+    let numerator2 = upper - lower;
+
+    if round_up {
+        div_rounding_up(mul_div_rounding_up(numerator1, numerator2, upper)?, lower)
+    } else {
+        Ok(mul_div(numerator1, numerator2, upper)? / lower)
+    }
+}
+
+/// Calculate the amount of token1 between two sqrt prices, mirroring `SqrtPriceMath.getAmount1Delta`.
+///
+/// # Arguments
+///
+/// * `sqrt_ratio_a_x96` - One of the sqrt prices, in any order.
+/// * `sqrt_ratio_b_x96` - The other sqrt price.
+/// * `liquidity` - The amount of usable liquidity.
+/// * `round_up` - Whether to round the amount up or down.
+///
+/// # Returns
+///
+/// * `Result<U256>` - The amount of token1.
+pub fn get_amount1_delta(
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<U256> {
+    let (lower, upper) = sorted(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+
+    if round_up {
+        mul_div_rounding_up(U256::from(liquidity), upper - lower, Q96)
+    } else {
+        mul_div(U256::from(liquidity), upper - lower, Q96)
+    }
+}
+
+/// Signed token0 delta for a liquidity change, rounded in the pool's favour.
+///
+/// Adding liquidity (`liquidity > 0`) returns the amount owed to the pool, removing it
+/// returns the negated amount owed to the position.
+pub fn get_amount0_delta_signed(
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    liquidity: i128,
+) -> Result<I256> {
+    let amount = get_amount0_delta(
+        sqrt_ratio_a_x96,
+        sqrt_ratio_b_x96,
+        liquidity.unsigned_abs(),
+        liquidity >= 0,
+    )?;
+    signed(amount, liquidity < 0)
+}
+
+/// Signed token1 delta for a liquidity change, see `get_amount0_delta_signed`.
+pub fn get_amount1_delta_signed(
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    liquidity: i128,
+) -> Result<I256> {
+    let amount = get_amount1_delta(
+        sqrt_ratio_a_x96,
+        sqrt_ratio_b_x96,
+        liquidity.unsigned_abs(),
+        liquidity >= 0,
+    )?;
+    signed(amount, liquidity < 0)
+}
+
+fn sorted(a: U256, b: U256) -> (U256, U256) {
+    if a > b {
+        (b, a)
+    } else {
+        (a, b)
+    }
+}
+
+fn signed(amount: U256, negative: bool) -> Result<I256> {
+    let amount = I256::try_from(amount)?;
+    Ok(if negative { -amount } else { amount })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 1e18
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    fn u256(value: &str) -> U256 {
+        U256::from_str_radix(value, 10).unwrap()
+    }
+
+    // `encodePriceSqrt(1, 1)` from the v3-core tests
+    fn price_one() -> U256 {
+        Q96
+    }
+
+    // `encodePriceSqrt(121, 100)`
+    fn price_121_100() -> U256 {
+        u256("87150978765690771352898345369")
+    }
+
+    #[test]
+    fn test_get_next_sqrt_price_from_input() {
+        assert_eq!(
+            get_next_sqrt_price_from_input(price_one(), E18, U256::from(E18 / 10), true).unwrap(),
+            u256("72025602285694852357767227579")
+        );
+        assert_eq!(
+            get_next_sqrt_price_from_input(price_one(), E18, U256::from(E18 / 10), false).unwrap(),
+            price_121_100()
+        );
+
+        // Zero input leaves the price untouched
+        assert_eq!(
+            get_next_sqrt_price_from_input(price_one(), E18, U256::ZERO, true).unwrap(),
+            price_one()
+        );
+
+        assert!(get_next_sqrt_price_from_input(U256::ZERO, E18, U256::from(1), true).is_err());
+        assert!(get_next_sqrt_price_from_input(price_one(), 0, U256::from(1), true).is_err());
+    }
+
+    #[test]
+    fn test_get_next_sqrt_price_from_output() {
+        assert_eq!(
+            get_next_sqrt_price_from_output(price_one(), E18, U256::from(E18 / 10), true).unwrap(),
+            u256("71305346262837903834189555302")
+        );
+        assert_eq!(
+            get_next_sqrt_price_from_output(price_one(), E18, U256::from(E18 / 10), false).unwrap(),
+            u256("88031291682515930659493278152")
+        );
+
+        // Cannot take out more than the virtual reserves
+        assert!(get_next_sqrt_price_from_output(price_one(), E18, U256::from(E18), false).is_err());
+        assert!(get_next_sqrt_price_from_output(price_one(), E18, U256::from(E18), true).is_err());
+    }
+
+    #[test]
+    fn test_get_amount_delta() {
+        assert_eq!(
+            get_amount0_delta(price_one(), price_121_100(), E18, true).unwrap(),
+            U256::from(90909090909090910u64)
+        );
+        assert_eq!(
+            get_amount0_delta(price_121_100(), price_one(), E18, false).unwrap(),
+            U256::from(90909090909090909u64)
+        );
+        assert_eq!(
+            get_amount1_delta(price_one(), price_121_100(), E18, true).unwrap(),
+            U256::from(100000000000000000u64)
+        );
+        assert_eq!(
+            get_amount1_delta(price_one(), price_121_100(), E18, false).unwrap(),
+            U256::from(99999999999999999u64)
+        );
+
+        assert_eq!(
+            get_amount0_delta(price_one(), price_121_100(), 0, true).unwrap(),
+            U256::ZERO
+        );
+        assert!(get_amount0_delta(U256::ZERO, price_one(), E18, true).is_err());
+    }
+
+    #[test]
+    fn test_get_amount_delta_signed() {
+        let liquidity = E18 as i128;
+
+        assert_eq!(
+            get_amount0_delta_signed(price_one(), price_121_100(), liquidity).unwrap(),
+            I256::try_from(90909090909090910i64).unwrap()
+        );
+        assert_eq!(
+            get_amount0_delta_signed(price_one(), price_121_100(), -liquidity).unwrap(),
+            I256::try_from(-90909090909090909i64).unwrap()
+        );
+        assert_eq!(
+            get_amount1_delta_signed(price_one(), price_121_100(), -liquidity).unwrap(),
+            I256::try_from(-99999999999999999i64).unwrap()
+        );
+    }
+}
@@ -0,0 +1,256 @@
+use alloy::primitives::{I256, U256};
+use eyre::Result;
+
+use super::{
+    full_math::{mul_div, mul_div_rounding_up},
+    sqrt_price_math::{
+        get_amount0_delta, get_amount1_delta, get_next_sqrt_price_from_input,
+        get_next_sqrt_price_from_output,
+    },
+};
+
+// Fees are expressed in hundredths of a bip
+const FEE_DENOMINATOR: u32 = 1_000_000;
+
+/// Result of swapping within a single tick range, see `compute_swap_step`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapStep {
+    /// Sqrt price after the step, never past the target price
+    pub sqrt_price_next_x96: U256,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    /// Fee paid on top of `amount_in`
+    pub fee_amount: U256,
+}
+
+/// Calculate the result of swapping some amount in or out within a single tick range,
+/// mirroring `SwapMath.computeSwapStep`.
+///
+/// # Arguments
+///
+/// * `sqrt_ratio_current_x96` - The current sqrt price of the pool.
+/// * `sqrt_ratio_target_x96` - The sqrt price the step cannot move past.
+/// * `liquidity` - The usable liquidity.
+/// * `amount_remaining` - The amount left to swap, positive for exact input and negative for exact output.
+/// * `fee_pips` - The pool fee in hundredths of a bip.
+///
+/// # Returns
+///
+/// * `Result<SwapStep>` - The next sqrt price with the amounts swapped and the fee paid.
+pub fn compute_swap_step(
+    sqrt_ratio_current_x96: U256,
+    sqrt_ratio_target_x96: U256,
+    liquidity: u128,
+    amount_remaining: I256,
+    fee_pips: u32,
+) -> Result<SwapStep> {
+    let zero_for_one = sqrt_ratio_current_x96 >= sqrt_ratio_target_x96;
+    let exact_in = amount_remaining >= I256::ZERO;
+    let amount_remaining_abs = amount_remaining.unsigned_abs();
+
+    let fee = U256::from(fee_pips);
+    let denominator = U256::from(FEE_DENOMINATOR);
+
+    let mut amount_in = U256::ZERO;
+    let mut amount_out = U256::ZERO;
+
+    let sqrt_price_next_x96 = if exact_in {
+        let amount_remaining_less_fee =
+            mul_div(amount_remaining_abs, denominator - fee, denominator)?;
+        amount_in = if zero_for_one {
+            get_amount0_delta(
+                sqrt_ratio_target_x96,
+                sqrt_ratio_current_x96,
+                liquidity,
+                true,
+            )?
+        } else {
+            get_amount1_delta(
+                sqrt_ratio_current_x96,
+                sqrt_ratio_target_x96,
+                liquidity,
+                true,
+            )?
+        };
+
+        if amount_remaining_less_fee >= amount_in {
+            sqrt_ratio_target_x96
+        } else {
+            get_next_sqrt_price_from_input(
+                sqrt_ratio_current_x96,
+                liquidity,
+                amount_remaining_less_fee,
+                zero_for_one,
+            )?
+        }
+    } else {
+        amount_out = if zero_for_one {
+            get_amount1_delta(
+                sqrt_ratio_target_x96,
+                sqrt_ratio_current_x96,
+                liquidity,
+                false,
+            )?
+        } else {
+            get_amount0_delta(
+                sqrt_ratio_current_x96,
+                sqrt_ratio_target_x96,
+                liquidity,
+                false,
+            )?
+        };
+
+        if amount_remaining_abs >= amount_out {
+            sqrt_ratio_target_x96
+        } else {
+            get_next_sqrt_price_from_output(
+                sqrt_ratio_current_x96,
+                liquidity,
+                amount_remaining_abs,
+                zero_for_one,
+            )?
+        }
+    };
+
+    // Amounts computed against the target can be reused if the step reached it
+    let max = sqrt_ratio_target_x96 == sqrt_price_next_x96;
+
+    if zero_for_one {
+        if !(max && exact_in) {
+            amount_in =
+                get_amount0_delta(sqrt_price_next_x96, sqrt_ratio_current_x96, liquidity, true)?;
+        }
+        if !(max && !exact_in) {
+            amount_out = get_amount1_delta(
+                sqrt_price_next_x96,
+                sqrt_ratio_current_x96,
+                liquidity,
+                false,
+            )?;
+        }
+    } else {
+        if !(max && exact_in) {
+            amount_in =
+                get_amount1_delta(sqrt_ratio_current_x96, sqrt_price_next_x96, liquidity, true)?;
+        }
+        if !(max && !exact_in) {
+            amount_out = get_amount0_delta(
+                sqrt_ratio_current_x96,
+                sqrt_price_next_x96,
+                liquidity,
+                false,
+            )?;
+        }
+    }
+
+    // Never pay out more than was asked for
+    if !exact_in && amount_out > amount_remaining_abs {
+        amount_out = amount_remaining_abs;
+    }
+
+    // If the target was not reached the whole remainder is taken, so it is all fee
+    let fee_amount = if exact_in && sqrt_price_next_x96 != sqrt_ratio_target_x96 {
+        amount_remaining_abs - amount_in
+    } else {
+        mul_div_rounding_up(amount_in, fee, denominator - fee)?
+    };
+
+    Ok(SwapStep {
+        sqrt_price_next_x96,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    fn u256(value: &str) -> U256 {
+        U256::from_str_radix(value, 10).unwrap()
+    }
+
+    fn i256(value: i128) -> I256 {
+        I256::try_from(value).unwrap()
+    }
+
+    // `encodePriceSqrt(1, 1)` from the v3-core tests
+    fn price_one() -> U256 {
+        U256::from(1) << 96
+    }
+
+    #[test]
+    fn test_exact_in_capped_at_target() {
+        // encodePriceSqrt(101, 100)
+        let target = u256("79623317895830914510639640423");
+        let step = compute_swap_step(price_one(), target, 2 * E18, i256(E18 as i128), 600).unwrap();
+
+        assert_eq!(step.sqrt_price_next_x96, target);
+        assert_eq!(step.amount_in, U256::from(9975124224178055u64));
+        assert_eq!(step.amount_out, U256::from(9925619580021728u64));
+        assert_eq!(step.fee_amount, U256::from(5988667735148u64));
+    }
+
+    #[test]
+    fn test_exact_out_capped_at_target() {
+        let target = u256("79623317895830914510639640423");
+        let step =
+            compute_swap_step(price_one(), target, 2 * E18, i256(-(E18 as i128)), 600).unwrap();
+
+        assert_eq!(step.sqrt_price_next_x96, target);
+        assert_eq!(step.amount_in, U256::from(9975124224178055u64));
+        assert_eq!(step.amount_out, U256::from(9925619580021728u64));
+        assert_eq!(step.fee_amount, U256::from(5988667735148u64));
+    }
+
+    #[test]
+    fn test_exact_in_fully_spent() {
+        // encodePriceSqrt(1000, 100)
+        let target = u256("250541448375047931186413801569");
+        let step = compute_swap_step(price_one(), target, 2 * E18, i256(E18 as i128), 600).unwrap();
+
+        assert_eq!(
+            step.sqrt_price_next_x96,
+            u256("118818475322642227089037862318")
+        );
+        assert_eq!(step.amount_in, U256::from(999400000000000000u64));
+        assert_eq!(step.amount_out, U256::from(666399946655997866u64));
+        assert_eq!(step.fee_amount, U256::from(600000000000000u64));
+        assert_eq!(step.amount_in + step.fee_amount, U256::from(E18));
+    }
+
+    #[test]
+    fn test_exact_out_fully_received() {
+        // encodePriceSqrt(10000, 100)
+        let target = u256("792281625142643375935439503360");
+        let step =
+            compute_swap_step(price_one(), target, 2 * E18, i256(-(E18 as i128)), 600).unwrap();
+
+        assert_eq!(
+            step.sqrt_price_next_x96,
+            u256("158456325028528675187087900672")
+        );
+        assert_eq!(step.amount_in, U256::from(2 * E18));
+        assert_eq!(step.amount_out, U256::from(E18));
+        assert_eq!(step.fee_amount, U256::from(1200720432259356u64));
+    }
+
+    #[test]
+    fn test_zero_for_one_exact_in() {
+        // encodePriceSqrt(1, 100)
+        let target = u256("7922816251426433759354395033");
+        let step =
+            compute_swap_step(price_one(), target, 2 * E18, i256(E18 as i128), 3000).unwrap();
+
+        assert_eq!(
+            step.sqrt_price_next_x96,
+            u256("52871646656165724119815782674")
+        );
+        assert_eq!(step.amount_in, U256::from(997000000000000000u64));
+        assert_eq!(step.amount_out, U256::from(665331998665331998u64));
+        assert_eq!(step.fee_amount, U256::from(3000000000000000u64));
+    }
+}
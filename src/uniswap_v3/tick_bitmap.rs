@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use alloy::primitives::U256;
+use eyre::{eyre, Result};
+
+/// Word and bit position of a compressed tick in the bitmap, mirroring `TickBitmap.position`.
+pub fn position(compressed: i32) -> (i16, u8) {
+    ((compressed >> 8) as i16, (compressed & 0xff) as u8)
+}
+
+/// Compress a tick by the tick spacing, rounding towards negative infinity.
+pub fn compress(tick: i32, tick_spacing: i32) -> i32 {
+    tick.div_euclid(tick_spacing)
+}
+
+/// Flip the initialized state of a tick, mirroring `TickBitmap.flipTick`.
+///
+/// # Arguments
+///
+/// * `bitmap` - The bitmap words by word position.
+/// * `tick` - The tick to flip, a multiple of the tick spacing.
+/// * `tick_spacing` - The tick spacing of the pool.
+///
+/// # Returns
+///
+/// * `Result<()>` - An error if the tick is not a multiple of the spacing.
+pub fn flip_tick(bitmap: &mut HashMap<i16, U256>, tick: i32, tick_spacing: i32) -> Result<()> {
+    if tick % tick_spacing != 0 {
+        return Err(eyre!(
+            "Tick {} is not a multiple of the spacing {}",
+            tick,
+            tick_spacing
+        ));
+    }
+
+    let (word, bit) = position(tick / tick_spacing);
+    *bitmap.entry(word).or_default() ^= U256::from(1) << bit as usize;
+    Ok(())
+}
+
+/// Find the next initialized tick within the same bitmap word, mirroring
+/// `TickBitmap.nextInitializedTickWithinOneWord`.
+///
+/// # Arguments
+///
+/// * `bitmap` - The bitmap words by word position.
+/// * `tick` - The starting tick.
+/// * `tick_spacing` - The tick spacing of the pool.
+/// * `lte` - Whether to search to the left, at or below the starting tick.
+///
+/// # Returns
+///
+/// * `Result<(i32, bool)>` - The next tick and whether it is initialized, or an error if the word is not loaded.
+pub fn next_initialized_tick_within_one_word(
+    bitmap: &HashMap<i16, U256>,
+    tick: i32,
+    tick_spacing: i32,
+    lte: bool,
+) -> Result<(i32, bool)> {
+    let compressed = compress(tick, tick_spacing);
+
+    // Only the bits at or below (left) or above (right) the current tick are of interest
+    let (bit, masked) = if lte {
+        let (word, bit) = position(compressed);
+        let mask =
+            (U256::from(1) << bit as usize) - U256::from(1) + (U256::from(1) << bit as usize);
+        (bit, load(bitmap, word)? & mask)
+    } else {
+        let (word, bit) = position(compressed + 1);
+        let mask = !((U256::from(1) << bit as usize) - U256::from(1));
+        (bit, load(bitmap, word)? & mask)
+    };
+
+    let initialized = !masked.is_zero();
+    let bit = bit as i32;
+
+    let next = match (lte, initialized) {
+        (true, true) => compressed - (bit - (masked.bit_len() as i32 - 1)),
+        (true, false) => compressed - bit,
+        (false, true) => compressed + 1 + (masked.trailing_zeros() as i32 - bit),
+        (false, false) => compressed + 1 + (255 - bit),
+    };
+
+    Ok((next * tick_spacing, initialized))
+}
+
+fn load(bitmap: &HashMap<i16, U256>, word: i16) -> Result<U256> {
+    bitmap
+        .get(&word)
+        .copied()
+        .ok_or_else(|| eyre!("Tick bitmap word {} is not loaded", word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The initialized ticks of the v3-core `TickBitmap` tests
+    fn bitmap() -> HashMap<i16, U256> {
+        let mut bitmap = HashMap::new();
+        for word in -2..=2 {
+            bitmap.insert(word, U256::ZERO);
+        }
+        for tick in [-200, -55, -4, 70, 78, 84, 139, 240, 535] {
+            flip_tick(&mut bitmap, tick, 1).unwrap();
+        }
+        bitmap
+    }
+
+    #[test]
+    fn test_flip_tick() {
+        let mut bitmap = HashMap::new();
+        flip_tick(&mut bitmap, -230, 10).unwrap();
+        assert_eq!(bitmap[&-1], U256::from(1) << 233);
+
+        flip_tick(&mut bitmap, -230, 10).unwrap();
+        assert_eq!(bitmap[&-1], U256::ZERO);
+
+        assert!(flip_tick(&mut bitmap, -235, 10).is_err());
+    }
+
+    #[test]
+    fn test_next_initialized_tick_to_the_right() {
+        let bitmap = bitmap();
+
+        for (tick, expected) in [
+            (78, (84, true)),
+            (-55, (-4, true)),
+            (77, (78, true)),
+            (-56, (-55, true)),
+            (255, (511, false)),
+            (-257, (-200, true)),
+            (328, (511, false)),
+        ] {
+            assert_eq!(
+                next_initialized_tick_within_one_word(&bitmap, tick, 1, false).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_next_initialized_tick_to_the_left() {
+        let bitmap = bitmap();
+
+        for (tick, expected) in [
+            (78, (78, true)),
+            (79, (78, true)),
+            (258, (256, false)),
+            (256, (256, false)),
+            (72, (70, true)),
+            (-257, (-512, false)),
+            (-55, (-55, true)),
+        ] {
+            assert_eq!(
+                next_initialized_tick_within_one_word(&bitmap, tick, 1, true).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_next_initialized_tick_with_spacing() {
+        let mut bitmap = HashMap::from([(-1, U256::ZERO), (0, U256::ZERO)]);
+        flip_tick(&mut bitmap, -20, 10).unwrap();
+
+        // Ticks between two spacings round down before searching
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, -15, 10, true).unwrap(),
+            (-20, true)
+        );
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, -25, 10, false).unwrap(),
+            (-20, true)
+        );
+    }
+
+    #[test]
+    fn test_missing_word() {
+        let bitmap = bitmap();
+        assert!(next_initialized_tick_within_one_word(&bitmap, 1023, 1, true).is_err());
+    }
+}
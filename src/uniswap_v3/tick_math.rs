@@ -0,0 +1,201 @@
+use alloy::primitives::{I256, U256};
+use eyre::{eyre, Result};
+
+/// Minimum tick which `get_sqrt_ratio_at_tick` accepts, `log_1.0001(2**-128)`
+pub const MIN_TICK: i32 = -887272;
+/// Maximum tick which `get_sqrt_ratio_at_tick` accepts, `log_1.0001(2**128)`
+pub const MAX_TICK: i32 = -MIN_TICK;
+
+/// Value of `get_sqrt_ratio_at_tick(MIN_TICK)`
+pub const MIN_SQRT_RATIO: U256 = U256::from_limbs([0x1000276a3, 0, 0, 0]);
+/// Value of `get_sqrt_ratio_at_tick(MAX_TICK)`
+pub const MAX_SQRT_RATIO: U256 =
+    U256::from_limbs([0x5d951d5263988d26, 0xefd1fc6a50648849, 0xfffd8963, 0]);
+
+// `1 / sqrt(1.0001)^(2^i)` as Q128.128 numbers for every bit of the absolute tick
+const RATIOS: [u128; 20] = [
+    0xfffcb933bd6fad37aa2d162d1a594001,
+    0xfff97272373d413259a46990580e213a,
+    0xfff2e50f5f656932ef12357cf3c7fdcc,
+    0xffe5caca7e10e4e61c3624eaa0941cd0,
+    0xffcb9843d60f6159c9db58835c926644,
+    0xff973b41fa98c081472e6896dfb254c0,
+    0xff2ea16466c96a3843ec78b326b52861,
+    0xfe5dee046a99a2a811c461f1969c3053,
+    0xfcbe86c7900a88aedcffc83b479aa3a4,
+    0xf987a7253ac413176f2b074cf7815e54,
+    0xf3392b0822b70005940c7a398e4b70f3,
+    0xe7159475a2c29b7443b29c7fa6e889d9,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e5,
+    0x70d869a156d2a1b890bb3df62baf32f7,
+    0x31be135f97d08fd981231505542fcfa6,
+    0x9aa508b5b7a84e1c677de54f3e99bc9,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe98,
+    0x48a170391f7dc42444e8fa2,
+];
+
+// `log_sqrt(1.0001)(2)` as a Q128.128 number
+const LOG_SQRT_10001: i128 = 255738958999603826347141;
+// Error bounds of the approximated logarithm, see `TickMath.getTickAtSqrtRatio`
+const TICK_LOW_ERROR: u128 = 3402992956809132418596140100660247210;
+const TICK_HIGH_ERROR: u128 = 291339464771989622907027621153398088495;
+
+/// Calculate `sqrt(1.0001^tick) * 2^96`, mirroring `TickMath.getSqrtRatioAtTick`.
+///
+/// # Arguments
+///
+/// * `tick` - The tick, between `MIN_TICK` and `MAX_TICK`.
+///
+/// # Returns
+///
+/// * `Result<U256>` - The sqrt price as a Q64.96 number.
+pub fn get_sqrt_ratio_at_tick(tick: i32) -> Result<U256> {
+    let abs_tick = tick.unsigned_abs();
+    if abs_tick > MAX_TICK.unsigned_abs() {
+        return Err(eyre!("Tick {} out of range", tick));
+    }
+
+    let mut ratio = if abs_tick & 1 != 0 {
+        U256::from(RATIOS[0])
+    } else {
+        U256::from(1) << 128
+    };
+    for (bit, factor) in RATIOS.iter().enumerate().skip(1) {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * U256::from(*factor)) >> 128;
+        }
+    }
+
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+
+    // Round up from Q128.128 to Q64.96, so `get_tick_at_sqrt_ratio` is consistent
+    let sqrt_price_x96 = ratio >> 32usize;
+    if (ratio % (U256::from(1) << 32usize)).is_zero() {
+        Ok(sqrt_price_x96)
+    } else {
+        Ok(sqrt_price_x96 + U256::from(1))
+    }
+}
+
+/// Calculate the greatest tick whose sqrt ratio is at most `sqrt_price_x96`, mirroring
+/// `TickMath.getTickAtSqrtRatio`.
+///
+/// # Arguments
+///
+/// * `sqrt_price_x96` - The sqrt price as a Q64.96 number.
+///
+/// # Returns
+///
+/// * `Result<i32>` - The tick, or an error if the price is outside `[MIN_SQRT_RATIO, MAX_SQRT_RATIO)`.
+pub fn get_tick_at_sqrt_ratio(sqrt_price_x96: U256) -> Result<i32> {
+    if sqrt_price_x96 < MIN_SQRT_RATIO || sqrt_price_x96 >= MAX_SQRT_RATIO {
+        return Err(eyre!("Sqrt price {} out of range", sqrt_price_x96));
+    }
+
+    let ratio: U256 = sqrt_price_x96 << 32usize;
+    let msb = ratio.bit_len() - 1;
+
+    // Normalise the ratio to 128 bits of precision
+    let mut r = if msb >= 128 {
+        ratio >> (msb - 127)
+    } else {
+        ratio << (127 - msb)
+    };
+
+    // Integer part of log_2, followed by 14 bits of the fraction
+    let mut log_2 = (msb as i128 - 128) << 64;
+    for bit in (50..64).rev() {
+        r = (r * r) >> 127;
+        // Squaring doubles the logarithm, an overflow past 2^128 sets the next bit
+        if r.bit(128) {
+            log_2 |= 1 << bit;
+            r >>= 1;
+        }
+    }
+
+    let log_sqrt10001 = I256::try_from(log_2)? * I256::try_from(LOG_SQRT_10001)?;
+
+    let tick_low = (log_sqrt10001 - I256::from_raw(U256::from(TICK_LOW_ERROR)))
+        .asr(128)
+        .as_i32();
+    let tick_high = (log_sqrt10001 + I256::from_raw(U256::from(TICK_HIGH_ERROR)))
+        .asr(128)
+        .as_i32();
+
+    if tick_low == tick_high || get_sqrt_ratio_at_tick(tick_high)? > sqrt_price_x96 {
+        Ok(tick_low)
+    } else {
+        Ok(tick_high)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u256(value: &str) -> U256 {
+        U256::from_str_radix(value, 10).unwrap()
+    }
+
+    #[test]
+    fn test_get_sqrt_ratio_at_tick() {
+        assert_eq!(get_sqrt_ratio_at_tick(MIN_TICK).unwrap(), MIN_SQRT_RATIO);
+        assert_eq!(get_sqrt_ratio_at_tick(MAX_TICK).unwrap(), MAX_SQRT_RATIO);
+        assert_eq!(
+            MAX_SQRT_RATIO,
+            u256("1461446703485210103287273052203988822378723970342")
+        );
+        assert_eq!(get_sqrt_ratio_at_tick(0).unwrap(), U256::from(1) << 96);
+
+        for (tick, expected) in [
+            (50, "79426470787362580746886972461"),
+            (100, "79625275426524748796330556128"),
+            (250, "80224679980005306637834519095"),
+            (500, "81233731461783161732293370115"),
+            (1000, "83290069058676223003182343270"),
+            (-50, "79030349367926598376800521322"),
+            (193677, "1271487029301751360839668024426277"),
+        ] {
+            assert_eq!(get_sqrt_ratio_at_tick(tick).unwrap(), u256(expected));
+        }
+
+        assert!(get_sqrt_ratio_at_tick(MIN_TICK - 1).is_err());
+        assert!(get_sqrt_ratio_at_tick(MAX_TICK + 1).is_err());
+    }
+
+    #[test]
+    fn test_get_tick_at_sqrt_ratio() {
+        assert_eq!(get_tick_at_sqrt_ratio(MIN_SQRT_RATIO).unwrap(), MIN_TICK);
+        assert_eq!(
+            get_tick_at_sqrt_ratio(MAX_SQRT_RATIO - U256::from(1)).unwrap(),
+            MAX_TICK - 1
+        );
+        assert_eq!(get_tick_at_sqrt_ratio(U256::from(1) << 96).unwrap(), 0);
+
+        let sqrt_price = u256("79426470787362580746886972461");
+        assert_eq!(get_tick_at_sqrt_ratio(sqrt_price).unwrap(), 50);
+        assert_eq!(
+            get_tick_at_sqrt_ratio(sqrt_price - U256::from(1)).unwrap(),
+            49
+        );
+
+        assert!(get_tick_at_sqrt_ratio(MIN_SQRT_RATIO - U256::from(1)).is_err());
+        assert!(get_tick_at_sqrt_ratio(MAX_SQRT_RATIO).is_err());
+    }
+
+    #[test]
+    fn test_tick_round_trip() {
+        for tick in (MIN_TICK..MAX_TICK).step_by(7919) {
+            let sqrt_price = get_sqrt_ratio_at_tick(tick).unwrap();
+            assert_eq!(get_tick_at_sqrt_ratio(sqrt_price).unwrap(), tick);
+
+            // Any price below the next tick still rounds down to this one
+            let next = get_sqrt_ratio_at_tick(tick + 1).unwrap();
+            assert_eq!(get_tick_at_sqrt_ratio(next - U256::from(1)).unwrap(), tick);
+        }
+    }
+}
@@ -14,6 +14,21 @@ pub static UNISWAP_V3_ROUTER: LazyLock<Address> = LazyLock::new(|| {
         .parse()
         .unwrap()
 });
+pub static SWAP_ROUTER_02: LazyLock<Address> = LazyLock::new(|| {
+    "0x68b3465833fb72A70ecDF485E0e4C7bD8665Fc45"
+        .parse()
+        .unwrap()
+});
+pub static UNIVERSAL_ROUTER: LazyLock<Address> = LazyLock::new(|| {
+    "0x3fC91A3afd70395Cd496C647d5a6CC9D4B2b7FAD"
+        .parse()
+        .unwrap()
+});
+pub static UNISWAP_V3_FACTORY: LazyLock<Address> = LazyLock::new(|| {
+    "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+        .parse()
+        .unwrap()
+});
 
 pub static WETH: LazyLock<Address> = LazyLock::new(|| {
     "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
@@ -7,6 +7,7 @@ use alloy::{
 };
 use eyre::{eyre, Result};
 use revm::primitives::FixedBytes;
+use std::time::Duration;
 
 #[macro_export]
 macro_rules! pow {
@@ -40,6 +41,34 @@ pub async fn get_block_timestamp_future(
         .map(|block| U256::from(block.header.timestamp + seconds))
 }
 
+/// Wait until the chain has reached a given block number.
+///
+/// # Arguments
+///
+/// * `provider` - A reference to the provider.
+/// * `block_number` - The block number to wait for.
+/// * `poll_interval` - How often to poll for a new block.
+///
+/// # Returns
+///
+/// * `Result<u64>` - The latest block number, at least `block_number`.
+pub async fn wait_for_block(
+    provider: &impl Provider<Http<reqwest::Client>>,
+    block_number: u64,
+    poll_interval: Duration,
+) -> Result<u64> {
+    loop {
+        let latest = provider
+            .get_block_number()
+            .await
+            .map_err(|e| eyre!("GET_BLOCK_NUMBER failed: {:?}", e))?;
+        if latest >= block_number {
+            return Ok(latest);
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
 pub fn calculate_slot_mapping(info: Vec<u8>, slot_value: u8) -> U256 {
     let mut storage_key = info.clone();
     let padded_slot = FixedBytes::<32>::left_padding_from(&[slot_value]).to_vec();
@@ -79,4 +108,17 @@ mod tests {
             timestamp + U256::from(DELAY)
         );
     }
+
+    #[tokio::test]
+    async fn check_wait_for_current_block() {
+        let (provider, _) = setup::test_setup().await;
+
+        let current = provider.get_block_number().await.unwrap();
+
+        let latest = wait_for_block(&provider, current, Duration::from_millis(100))
+            .await
+            .expect("WAIT_FOR_BLOCK failed");
+
+        assert!(latest >= current);
+    }
 }
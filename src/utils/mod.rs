@@ -1,3 +1,5 @@
 pub mod addresses;
 pub mod blockchain_utils;
 pub mod setup;
+#[cfg(test)]
+pub mod testtx;
@@ -0,0 +1,33 @@
+use alloy::{
+    network::{EthereumWallet, TransactionBuilder},
+    rpc::types::{Transaction, TransactionRequest},
+    signers::local::PrivateKeySigner,
+};
+
+/// Sign a request and wrap it like a pending transaction received from the mempool.
+///
+/// # Arguments
+///
+/// * `signer` - The sender, which also signs the transaction.
+/// * `request` - The transaction to sign, with every field its type requires besides the sender.
+///
+/// # Returns
+///
+/// * `Transaction` - The signed transaction, not yet mined.
+pub async fn sign_pending(signer: &PrivateKeySigner, request: TransactionRequest) -> Transaction {
+    let wallet = EthereumWallet::from(signer.clone());
+    let envelope = request
+        .with_from(signer.address())
+        .build(&wallet)
+        .await
+        .unwrap();
+
+    Transaction {
+        inner: envelope,
+        block_hash: None,
+        block_number: None,
+        transaction_index: None,
+        effective_gas_price: None,
+        from: signer.address(),
+    }
+}
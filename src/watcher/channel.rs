@@ -0,0 +1,268 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+use tokio::sync::Notify;
+
+/// Number of transactions a subscription queues unless configured otherwise.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// What a full channel does with a new value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DropPolicy {
+    /// Evict the oldest queued value, stale candidates are the least likely to land
+    #[default]
+    DropOldest,
+    /// Discard the new value
+    DropNewest,
+    /// Wait for the receiver to make room, back-pressuring the subscription
+    Block,
+}
+
+/// Outcome of a send while the receiver is alive.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SendOutcome<T> {
+    Queued,
+    /// The value was queued in place of the oldest one, which is returned
+    Evicted(T),
+    /// The channel was full and the value was discarded
+    Rejected(T),
+}
+
+/// The receiver was dropped, the value sent is handed back.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ReceiverClosed<T>(pub T);
+
+struct Shared<T> {
+    queue: Mutex<VecDeque<T>>,
+    capacity: usize,
+    policy: DropPolicy,
+    sender_closed: AtomicBool,
+    receiver_closed: AtomicBool,
+    // Wakes the receiver when a value is queued or the sender is dropped
+    value_sent: Notify,
+    // Wakes a blocked sender when a value is taken or the receiver is dropped
+    value_taken: Notify,
+}
+
+/// Sending half of a bounded channel, see `channel`.
+pub struct PendingSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// Receiving half of a bounded channel, see `channel`.
+pub struct PendingReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// Create a bounded single producer, single consumer channel.
+///
+/// Unlike `tokio::sync::mpsc`, a full channel can make room by evicting its oldest
+/// value, so a slow consumer always sees the freshest transactions.
+///
+/// # Arguments
+///
+/// * `capacity` - The most values queued at once, at least 1.
+/// * `policy` - What to do with a value sent while the channel is full.
+///
+/// # Returns
+///
+/// * `(PendingSender<T>, PendingReceiver<T>)` - The two halves of the channel.
+pub fn channel<T>(capacity: usize, policy: DropPolicy) -> (PendingSender<T>, PendingReceiver<T>) {
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(VecDeque::new()),
+        capacity: capacity.max(1),
+        policy,
+        sender_closed: AtomicBool::new(false),
+        receiver_closed: AtomicBool::new(false),
+        value_sent: Notify::new(),
+        value_taken: Notify::new(),
+    });
+
+    (
+        PendingSender {
+            shared: shared.clone(),
+        },
+        PendingReceiver { shared },
+    )
+}
+
+impl<T> PendingSender<T> {
+    /// Queue a value, applying the drop policy if the channel is full.
+    ///
+    /// Only waits with `DropPolicy::Block`, until the receiver takes a value or is dropped.
+    pub async fn send(&self, value: T) -> Result<SendOutcome<T>, ReceiverClosed<T>> {
+        loop {
+            {
+                let mut queue = self
+                    .shared
+                    .queue
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                if self.shared.receiver_closed.load(Ordering::SeqCst) {
+                    return Err(ReceiverClosed(value));
+                }
+
+                if queue.len() < self.shared.capacity {
+                    queue.push_back(value);
+                    self.shared.value_sent.notify_one();
+                    return Ok(SendOutcome::Queued);
+                }
+                match self.shared.policy {
+                    DropPolicy::DropOldest => {
+                        let oldest = queue.pop_front();
+                        queue.push_back(value);
+                        self.shared.value_sent.notify_one();
+                        return Ok(oldest.map_or(SendOutcome::Queued, SendOutcome::Evicted));
+                    }
+                    DropPolicy::DropNewest => return Ok(SendOutcome::Rejected(value)),
+                    DropPolicy::Block => {}
+                }
+            }
+
+            self.shared.value_taken.notified().await;
+        }
+    }
+
+    /// Whether the receiver was dropped.
+    pub fn is_closed(&self) -> bool {
+        self.shared.receiver_closed.load(Ordering::SeqCst)
+    }
+}
+
+impl<T> Drop for PendingSender<T> {
+    fn drop(&mut self) {
+        self.shared.sender_closed.store(true, Ordering::SeqCst);
+        self.shared.value_sent.notify_one();
+    }
+}
+
+impl<T> PendingReceiver<T> {
+    /// Wait for the next value, `None` once the sender is dropped and the queue drained.
+    ///
+    /// Cancel safe, so it can be raced in `tokio::select!` without losing values.
+    pub async fn recv(&mut self) -> Option<T> {
+        loop {
+            if let Some(value) = self.try_recv() {
+                return Some(value);
+            }
+            if self.shared.sender_closed.load(Ordering::SeqCst) {
+                // A value may have been queued right before the sender was dropped
+                return self.try_recv();
+            }
+
+            self.shared.value_sent.notified().await;
+        }
+    }
+
+    /// Take the next value if one is queued.
+    pub fn try_recv(&mut self) -> Option<T> {
+        let value = self
+            .shared
+            .queue
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop_front();
+        if value.is_some() {
+            self.shared.value_taken.notify_one();
+        }
+        value
+    }
+
+    /// Number of values queued.
+    pub fn len(&self) -> usize {
+        self.shared
+            .queue
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Drop for PendingReceiver<T> {
+    fn drop(&mut self) {
+        self.shared.receiver_closed.store(true, Ordering::SeqCst);
+        self.shared.value_taken.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drop_oldest() {
+        let (sender, mut recv) = channel(2, DropPolicy::DropOldest);
+
+        assert_eq!(sender.send(1).await, Ok(SendOutcome::Queued));
+        assert_eq!(sender.send(2).await, Ok(SendOutcome::Queued));
+        assert_eq!(sender.send(3).await, Ok(SendOutcome::Evicted(1)));
+        assert_eq!(recv.len(), 2);
+
+        assert_eq!(recv.recv().await, Some(2));
+        assert_eq!(recv.recv().await, Some(3));
+        assert!(recv.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_drop_newest() {
+        let (sender, mut recv) = channel(2, DropPolicy::DropNewest);
+
+        assert_eq!(sender.send(1).await, Ok(SendOutcome::Queued));
+        assert_eq!(sender.send(2).await, Ok(SendOutcome::Queued));
+        assert_eq!(sender.send(3).await, Ok(SendOutcome::Rejected(3)));
+
+        assert_eq!(recv.recv().await, Some(1));
+        assert_eq!(recv.recv().await, Some(2));
+        assert_eq!(recv.try_recv(), None);
+    }
+
+    #[tokio::test]
+    async fn test_block() {
+        let (sender, mut recv) = channel(1, DropPolicy::Block);
+        sender.send(1).await.unwrap();
+
+        // The second send waits until the first value is taken
+        let handle = tokio::spawn(async move { sender.send(2).await });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!handle.is_finished(), "Send should block while full");
+
+        assert_eq!(recv.recv().await, Some(1));
+        assert_eq!(handle.await.unwrap(), Ok(SendOutcome::Queued));
+        assert_eq!(recv.recv().await, Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_receiver_dropped() {
+        let (sender, recv) = channel(1, DropPolicy::Block);
+        sender.send(1).await.unwrap();
+
+        // A blocked send is released when the receiver goes away
+        let handle = tokio::spawn(async move { sender.send(2).await });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        drop(recv);
+
+        assert_eq!(handle.await.unwrap(), Err(ReceiverClosed(2)));
+    }
+
+    #[tokio::test]
+    async fn test_sender_dropped() {
+        let (sender, mut recv) = channel(4, DropPolicy::default());
+        sender.send(1).await.unwrap();
+        drop(sender);
+
+        // Queued values are still delivered before the channel reports closing
+        assert_eq!(recv.recv().await, Some(1));
+        assert_eq!(recv.recv().await, None);
+    }
+}
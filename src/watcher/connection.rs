@@ -0,0 +1,201 @@
+use std::{sync::Arc, time::Duration};
+
+use alloy::{
+    providers::Provider,
+    pubsub::{PubSubFrontend, SubscriptionStream},
+    rpc::types::Transaction,
+};
+use eyre::Result;
+use futures_util::future::BoxFuture;
+use tokio::sync::watch;
+
+use super::{membuilder::create_ws_provider, shutdownconfig::ShutdownConfig};
+
+/// Opens a fresh connection to the node, called again every time the connection drops.
+pub type Connector =
+    Arc<dyn Fn() -> BoxFuture<'static, Result<Arc<dyn Provider<PubSubFrontend>>>> + Send + Sync>;
+
+/// Connect to a WebSocket endpoint.
+pub fn ws_connector(url: impl Into<String>) -> Connector {
+    let url = url.into();
+    Arc::new(move || {
+        let url = url.clone();
+        Box::pin(async move { create_ws_provider(&url).await })
+    })
+}
+
+/// State of the connection a mempool subscription receives transactions over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connected,
+    /// The connection dropped, this is the attempt at reconnecting under way
+    Reconnecting {
+        attempt: u32,
+    },
+    /// Not subscribed, either not yet or no longer
+    Disconnected,
+}
+
+impl ConnectionState {
+    pub fn is_connected(&self) -> bool {
+        matches!(self, ConnectionState::Connected)
+    }
+}
+
+/// Exponential backoff between reconnect attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+    pub multiplier: u32,
+    /// Attempts before giving up, retries forever if not set
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(500),
+            max: Duration::from_secs(30),
+            multiplier: 2,
+            max_attempts: None,
+        }
+    }
+}
+
+impl Backoff {
+    /// Delay before the reconnect attempt `attempt`, counting from 0, or `None` once
+    /// the attempts are exhausted.
+    pub fn delay(&self, attempt: u32) -> Option<Duration> {
+        if self.max_attempts.is_some_and(|max| attempt >= max) {
+            return None;
+        }
+
+        let factor = self.multiplier.saturating_pow(attempt);
+        Some(self.initial.saturating_mul(factor).min(self.max))
+    }
+}
+
+/// Read side of the connection state, for the bot to check the feed is live.
+#[derive(Debug, Clone)]
+pub struct ConnectionHealth {
+    recv: watch::Receiver<ConnectionState>,
+}
+
+impl ConnectionHealth {
+    pub fn new(recv: watch::Receiver<ConnectionState>) -> Self {
+        Self { recv }
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        *self.recv.borrow()
+    }
+
+    /// Whether pending transactions are being received.
+    pub fn is_healthy(&self) -> bool {
+        self.state().is_connected()
+    }
+
+    /// Wait for the next state change, `None` once the mempool is dropped.
+    pub async fn changed(&mut self) -> Option<ConnectionState> {
+        self.recv.changed().await.ok()?;
+        Some(*self.recv.borrow_and_update())
+    }
+}
+
+/// Reconnect and resubscribe to full pending transactions until it succeeds.
+///
+/// Without a connector, the subscription is renewed over `provider`, whose transport
+/// may have reconnected on its own.
+///
+/// # Arguments
+///
+/// * `provider` - The provider of the dropped subscription, replaced on reconnecting.
+/// * `connector` - Opens a new connection.
+/// * `backoff` - The delays between attempts.
+/// * `state` - Updated with every attempt.
+/// * `shutdown_config` - Stops reconnecting once shutdown is signalled.
+///
+/// # Returns
+///
+/// * `Option<SubscriptionStream<Transaction>>` - The new subscription, `None` if the
+///   attempts ran out or shutdown was signalled.
+pub(crate) async fn resubscribe(
+    provider: &mut Arc<dyn Provider<PubSubFrontend>>,
+    connector: Option<&Connector>,
+    backoff: &Backoff,
+    state: &watch::Sender<ConnectionState>,
+    shutdown_config: &ShutdownConfig,
+) -> Option<SubscriptionStream<Transaction>> {
+    let mut attempt = 0;
+    while let Some(delay) = backoff.delay(attempt) {
+        attempt += 1;
+        state.send_replace(ConnectionState::Reconnecting { attempt });
+
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = shutdown_config.wait_for_shutdown() => return None,
+        }
+
+        if let Some(connector) = connector {
+            match connector().await {
+                Ok(new_provider) => *provider = new_provider,
+                Err(e) => {
+                    println!("Reconnect attempt {} failed: {:?}", attempt, e);
+                    continue;
+                }
+            }
+        }
+
+        match provider.subscribe_full_pending_transactions().await {
+            Ok(sub) => {
+                println!("Resubscribed to pending transactions");
+                state.send_replace(ConnectionState::Connected);
+                return Some(sub.into_stream());
+            }
+            Err(e) => println!("Resubscribe attempt {} failed: {:?}", attempt, e),
+        }
+    }
+
+    println!("Giving up reconnecting after {} attempts", attempt);
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let backoff = Backoff {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(1),
+            multiplier: 2,
+            max_attempts: Some(6),
+        };
+
+        assert_eq!(backoff.delay(0), Some(Duration::from_millis(100)));
+        assert_eq!(backoff.delay(1), Some(Duration::from_millis(200)));
+        assert_eq!(backoff.delay(3), Some(Duration::from_millis(800)));
+        assert_eq!(backoff.delay(4), Some(Duration::from_secs(1)));
+        assert_eq!(backoff.delay(6), None);
+
+        // Large attempts saturate at the maximum instead of overflowing
+        let forever = Backoff::default();
+        assert_eq!(forever.delay(1000), Some(forever.max));
+    }
+
+    #[tokio::test]
+    async fn test_connection_health() {
+        let (sender, recv) = watch::channel(ConnectionState::Disconnected);
+        let mut health = ConnectionHealth::new(recv);
+        assert!(!health.is_healthy());
+
+        sender.send_replace(ConnectionState::Connected);
+        assert_eq!(health.changed().await, Some(ConnectionState::Connected));
+        assert!(health.is_healthy());
+
+        drop(sender);
+        assert_eq!(health.changed().await, None);
+    }
+}
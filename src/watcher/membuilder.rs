@@ -6,12 +6,39 @@ use alloy::{
 };
 use eyre::Result;
 
-use super::{mempool::MemPool, shutdownconfig::ShutdownConfig};
+use super::{
+    channel::{DropPolicy, DEFAULT_CAPACITY},
+    connection::{self, Backoff, Connector},
+    mempool::MemPool,
+    metrics::MemPoolMetrics,
+    pendingpool::PendingPool,
+    shutdownconfig::ShutdownConfig,
+};
 
-#[derive(Default)]
 pub struct MemPoolBuilder {
     provider: Option<Arc<dyn Provider<PubSubFrontend>>>,
     shutdown_config: ShutdownConfig,
+    channel_capacity: usize,
+    drop_policy: DropPolicy,
+    metrics: Arc<MemPoolMetrics>,
+    connector: Option<Connector>,
+    backoff: Backoff,
+    pending_pool: Option<Arc<PendingPool>>,
+}
+
+impl Default for MemPoolBuilder {
+    fn default() -> Self {
+        Self {
+            provider: None,
+            shutdown_config: ShutdownConfig::default(),
+            channel_capacity: DEFAULT_CAPACITY,
+            drop_policy: DropPolicy::default(),
+            metrics: Arc::default(),
+            connector: None,
+            backoff: Backoff::default(),
+            pending_pool: None,
+        }
+    }
 }
 
 impl MemPoolBuilder {
@@ -27,13 +54,64 @@ impl MemPoolBuilder {
         self
     }
 
-    /// Build the mempool instance.
+    /// Set how many filtered transactions a subscription queues for its receiver.
+    pub fn with_channel_capacity(mut self, channel_capacity: usize) -> Self {
+        self.channel_capacity = channel_capacity;
+        self
+    }
+
+    /// Set what a subscription does with transactions once its channel is full.
+    pub fn with_drop_policy(mut self, drop_policy: DropPolicy) -> Self {
+        self.drop_policy = drop_policy;
+        self
+    }
+
+    /// Share the counters the subscriptions update, to read them elsewhere.
+    pub fn with_metrics(mut self, metrics: Arc<MemPoolMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Connect over WebSocket to `url`, and again whenever the connection drops.
+    pub fn with_ws_url(self, url: impl Into<String>) -> Self {
+        self.with_connector(connection::ws_connector(url))
+    }
+
+    /// Set how to open a connection, and to reopen it whenever it drops.
+    pub fn with_connector(mut self, connector: Connector) -> Self {
+        self.connector = Some(connector);
+        self
+    }
+
+    /// Set the delays between reconnect attempts.
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Deduplicate transactions through a pool shared with other feeds, see `PendingPool`.
+    pub fn with_pending_pool(mut self, pending_pool: Arc<PendingPool>) -> Self {
+        self.pending_pool = Some(pending_pool);
+        self
+    }
+
+    /// Build the mempool instance, connecting with the connector if no provider is set.
     pub async fn build(self) -> Result<MemPool> {
-        Ok(MemPool::new(
-            self.provider
-                .ok_or_else(|| eyre::eyre!("Provider not set"))?,
-            self.shutdown_config,
-        ))
+        let provider = match (self.provider, &self.connector) {
+            (Some(provider), _) => provider,
+            (None, Some(connector)) => connector().await?,
+            (None, None) => return Err(eyre::eyre!("Provider not set")),
+        };
+
+        let mempool = MemPool::new(provider, self.shutdown_config)
+            .with_channel(self.channel_capacity, self.drop_policy)
+            .with_metrics(self.metrics)
+            .with_reconnect(self.connector, self.backoff);
+
+        Ok(match self.pending_pool {
+            Some(pending_pool) => mempool.with_pending_pool(pending_pool),
+            None => mempool,
+        })
     }
 }
 
@@ -4,16 +4,27 @@ use eyre::Result;
 
 use alloy::{providers::Provider, pubsub::PubSubFrontend, rpc::types::Transaction};
 use futures_util::StreamExt;
-use tokio::{
-    sync::mpsc::{self, UnboundedReceiver},
-    task::JoinHandle,
+use tokio::{sync::watch, task::JoinHandle};
+
+use super::{
+    channel::{self, DropPolicy, PendingReceiver, ReceiverClosed, SendOutcome, DEFAULT_CAPACITY},
+    connection::{self, Backoff, ConnectionHealth, ConnectionState, Connector},
+    metrics::MemPoolMetrics,
+    pendingpool::PendingPool,
+    shutdownconfig::ShutdownConfig,
+    subscribefilter::ShallowFilter,
 };
 
-use super::{shutdownconfig::ShutdownConfig, subscribefilter::ShallowFilter};
-
 pub struct MemPool {
     provider: Arc<dyn Provider<PubSubFrontend>>,
     shutdown_config: ShutdownConfig,
+    channel_capacity: usize,
+    drop_policy: DropPolicy,
+    metrics: Arc<MemPoolMetrics>,
+    connector: Option<Connector>,
+    backoff: Backoff,
+    state: Arc<watch::Sender<ConnectionState>>,
+    pending_pool: Option<Arc<PendingPool>>,
 }
 
 impl MemPool {
@@ -24,60 +35,134 @@ impl MemPool {
         Self {
             provider,
             shutdown_config,
+            channel_capacity: DEFAULT_CAPACITY,
+            drop_policy: DropPolicy::default(),
+            metrics: Arc::default(),
+            connector: None,
+            backoff: Backoff::default(),
+            state: Arc::new(watch::Sender::new(ConnectionState::Disconnected)),
+            pending_pool: None,
         }
     }
 
+    /// Bound the channel of every subscription, handling overflow with `drop_policy`.
+    pub fn with_channel(mut self, capacity: usize, drop_policy: DropPolicy) -> Self {
+        self.channel_capacity = capacity;
+        self.drop_policy = drop_policy;
+        self
+    }
+
+    pub fn with_metrics(mut self, metrics: Arc<MemPoolMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Reconnect with `connector` when a subscription's connection drops, waiting
+    /// according to `backoff` between attempts.
+    pub fn with_reconnect(mut self, connector: Option<Connector>, backoff: Backoff) -> Self {
+        self.connector = connector;
+        self.backoff = backoff;
+        self
+    }
+
+    /// Track forwarded transactions in `pending_pool`, skipping those it already holds,
+    /// so that several feeds sharing the pool deliver each transaction once.
+    pub fn with_pending_pool(mut self, pending_pool: Arc<PendingPool>) -> Self {
+        self.pending_pool = Some(pending_pool);
+        self
+    }
+
+    /// The state of the subscriptions' connection, which every subscription updates.
+    pub fn health(&self) -> ConnectionHealth {
+        ConnectionHealth::new(self.state.subscribe())
+    }
+
+    /// Counters of the transactions the subscriptions handled.
+    pub fn metrics(&self) -> Arc<MemPoolMetrics> {
+        self.metrics.clone()
+    }
+
     // Subscribe to mempool and send transactions to buffer
     pub async fn subscribe<F>(
         &self,
         filter_type: F,
-    ) -> Result<(
-        JoinHandle<()>,
-        UnboundedReceiver<Transaction>,
-        ShutdownConfig,
-    )>
+    ) -> Result<(JoinHandle<()>, PendingReceiver<Transaction>, ShutdownConfig)>
     where
         F: ShallowFilter + Clone + Send + Sync + 'static,
     {
-        let (sender, recv) = mpsc::unbounded_channel::<Transaction>();
+        let (sender, recv) = channel::channel(self.channel_capacity, self.drop_policy);
 
         let sub = self.provider.subscribe_full_pending_transactions().await?;
-
-        // Filter stream based on filter type
-        let stream = sub.into_stream().filter_map(move |tx| {
-            let filter_type = filter_type.clone();
-            async move {
-                if filter_type.filter(&tx) {
-                    Some(tx)
-                } else {
-                    None
-                }
-            }
-        });
+        self.state.send_replace(ConnectionState::Connected);
         println!("Awaiting pending transactions...");
 
         // Pin the stream for use in the async block
-        let pinned_stream = Box::pin(stream);
+        let pinned_stream = Box::pin(sub.into_stream());
 
-        // Take the stream and print the pending transaction.
         // Clone items
         let shutdown_config = self.shutdown_config.clone();
+        let metrics = self.metrics.clone();
+        let mut provider = self.provider.clone();
+        let connector = self.connector.clone();
+        let backoff = self.backoff;
+        let state = self.state.clone();
+        let pending_pool = self.pending_pool.clone();
 
         let handle = tokio::spawn(async move {
-            let shutdown_config = shutdown_config;
-            // Take ownership of tx
-            let sender = sender;
-            let mut stream = pinned_stream; // Store the stream in a pinned variable
+            let mut stream = pinned_stream;
             loop {
                 tokio::select! {
                     tx = stream.next() => {
                         if shutdown_config.is_shutdown() {
                             break;
                         }
-                        if let Some(tx) = tx {
-                            // Send transaction to channel
-                            // TODO: Better error handling
-                            sender.send(tx).unwrap();
+                        // The subscription ends when its connection drops
+                        let Some(tx) = tx else {
+                            state.send_replace(ConnectionState::Disconnected);
+                            println!("Mempool subscription ended, reconnecting...");
+                            match connection::resubscribe(
+                                &mut provider,
+                                connector.as_ref(),
+                                &backoff,
+                                &state,
+                                &shutdown_config,
+                            )
+                            .await
+                            {
+                                Some(new_stream) => {
+                                    stream = Box::pin(new_stream);
+                                    continue;
+                                }
+                                None => break,
+                            }
+                        };
+
+                        metrics.record_received();
+                        if !filter_type.filter(&tx) {
+                            metrics.record_filtered();
+                            continue;
+                        }
+                        if pending_pool.as_ref().is_some_and(|pool| !pool.insert(tx.clone())) {
+                            metrics.record_duplicate();
+                            continue;
+                        }
+
+                        // A blocked send must not hold up shutdown
+                        let outcome = tokio::select! {
+                            outcome = sender.send(tx) => outcome,
+                            _ = shutdown_config.wait_for_shutdown() => break,
+                        };
+                        match outcome {
+                            Ok(SendOutcome::Queued) => metrics.record_forwarded(),
+                            Ok(SendOutcome::Evicted(_)) => {
+                                metrics.record_forwarded();
+                                metrics.record_dropped();
+                            }
+                            Ok(SendOutcome::Rejected(_)) => metrics.record_dropped(),
+                            Err(ReceiverClosed(_)) => {
+                                println!("Receiver dropped, closing the mempool subscription");
+                                break;
+                            }
                         }
                     }
                     _ = tokio::time::sleep(std::time::Duration::from_millis(100)) => {
@@ -89,6 +174,7 @@ impl MemPool {
             }
             // Gracefully shutdown channel
             drop(sender);
+            state.send_replace(ConnectionState::Disconnected);
 
             shutdown_config.finish();
         });
@@ -99,6 +185,16 @@ impl MemPool {
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
+    use alloy::{
+        network::{EthereumWallet, TransactionBuilder},
+        node_bindings::Anvil,
+        primitives::{Address, U256},
+        providers::ProviderBuilder,
+        rpc::types::TransactionRequest,
+        signers::local::PrivateKeySigner,
+    };
 
     use crate::{
         config::testconfig,
@@ -181,4 +277,72 @@ mod tests {
         // Verify that the shutdown result has been set
         assert!(shutdown.is_finished(), "Stream should have finished");
     }
+
+    /// Wait until the connection reaches a state matching `done`.
+    async fn wait_for_state(health: &mut ConnectionHealth, done: fn(&ConnectionState) -> bool) {
+        tokio::time::timeout(Duration::from_secs(30), async {
+            while !done(&health.state()) {
+                health.changed().await.expect("Mempool dropped");
+            }
+        })
+        .await
+        .expect("Timed out waiting for the connection state");
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_after_node_restart() {
+        let anvil = Anvil::new().spawn();
+        let port = anvil.port();
+        let key = anvil.keys()[0].clone();
+
+        let mempool = MemPoolBuilder::default()
+            .with_ws_url(anvil.ws_endpoint())
+            .with_backoff(Backoff {
+                initial: Duration::from_millis(100),
+                max: Duration::from_millis(500),
+                multiplier: 2,
+                max_attempts: None,
+            })
+            .build()
+            .await
+            .unwrap();
+        let mut health = mempool.health();
+
+        let (handle, mut recv, shutdown) =
+            mempool.subscribe(ShallowFilterType::None).await.unwrap();
+        assert!(health.is_healthy());
+
+        // Killing the node ends the subscription
+        drop(anvil);
+        wait_for_state(&mut health, |state| !state.is_connected()).await;
+        assert!(!health.is_healthy());
+
+        // The watcher resubscribes once the node is back on the same port
+        let anvil = Anvil::new().port(port).spawn();
+        wait_for_state(&mut health, ConnectionState::is_connected).await;
+
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(EthereumWallet::from(PrivateKeySigner::from(key)))
+            .on_http(anvil.endpoint_url());
+        let tx_hash = *provider
+            .send_transaction(
+                TransactionRequest::default()
+                    .with_to(Address::repeat_byte(0x01))
+                    .with_value(U256::from(1)),
+            )
+            .await
+            .unwrap()
+            .tx_hash();
+
+        let tx = tokio::time::timeout(Duration::from_secs(10), recv.recv())
+            .await
+            .expect("Timed out waiting for transaction")
+            .expect("Channel closed");
+        assert_eq!(*tx.inner.tx_hash(), tx_hash);
+
+        shutdown.shutdown();
+        handle.await.unwrap();
+        assert_eq!(health.state(), ConnectionState::Disconnected);
+    }
 }
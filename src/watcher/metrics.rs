@@ -0,0 +1,72 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Counters of the transactions a mempool subscription handled.
+#[derive(Debug, Default)]
+pub struct MemPoolMetrics {
+    received: AtomicU64,
+    filtered: AtomicU64,
+    duplicates: AtomicU64,
+    forwarded: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl MemPoolMetrics {
+    /// Pending transactions received from the node.
+    pub fn received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    /// Transactions the filter rejected.
+    pub fn filtered(&self) -> u64 {
+        self.filtered.load(Ordering::Relaxed)
+    }
+
+    /// Transactions already received on another feed.
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates.load(Ordering::Relaxed)
+    }
+
+    /// Transactions queued for the receiver.
+    pub fn forwarded(&self) -> u64 {
+        self.forwarded.load(Ordering::Relaxed)
+    }
+
+    /// Transactions discarded because the channel was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_received(&self) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_filtered(&self) {
+        self.filtered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_duplicate(&self) {
+        self.duplicates.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_forwarded(&self) {
+        self.forwarded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl std::fmt::Display for MemPoolMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "received {}, filtered {}, duplicates {}, forwarded {}, dropped {}",
+            self.received(),
+            self.filtered(),
+            self.duplicates(),
+            self.forwarded(),
+            self.dropped()
+        )
+    }
+}
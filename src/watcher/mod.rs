@@ -1,4 +1,9 @@
+pub mod channel;
+pub mod connection;
 pub mod membuilder;
-mod mempool;
-mod shutdownconfig;
+pub mod mempool;
+pub mod metrics;
+pub mod pendingpool;
+pub mod shutdownconfig;
 pub mod subscribefilter;
+pub mod swapfilter;
@@ -0,0 +1,390 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use alloy::{
+    consensus::Transaction as _,
+    eips::BlockNumberOrTag,
+    primitives::{Address, B256},
+    providers::Provider,
+    rpc::types::{BlockTransactionsKind, Transaction},
+    transports::Transport,
+};
+use eyre::{eyre, Result};
+use tokio::sync::broadcast;
+
+use super::shutdownconfig::ShutdownConfig;
+
+/// Blocks a transaction may stay pending before it is considered dropped.
+pub const DEFAULT_MAX_AGE: u64 = 25;
+// Events buffered for each subscriber before the slowest starts missing them
+const EVENT_CAPACITY: usize = 1024;
+
+/// A change in the status of a tracked transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PendingEvent {
+    /// First sighting of the transaction, on any feed
+    Pending(B256),
+    Included {
+        hash: B256,
+        block: u64,
+    },
+    /// A transaction with the same sender and nonce took its place
+    Replaced {
+        hash: B256,
+        by: B256,
+    },
+    /// Not mined within the maximum age
+    Dropped(B256),
+}
+
+impl PendingEvent {
+    /// The transaction the event is about.
+    pub fn hash(&self) -> B256 {
+        match self {
+            PendingEvent::Pending(hash)
+            | PendingEvent::Included { hash, .. }
+            | PendingEvent::Replaced { hash, .. }
+            | PendingEvent::Dropped(hash) => *hash,
+        }
+    }
+
+    /// Whether the transaction has left the mempool, so no bundle can still include it.
+    pub fn is_final(&self) -> bool {
+        !matches!(self, PendingEvent::Pending(_))
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    tx: Transaction,
+    // Block the transaction was first seen at, set by the next block if none was seen yet
+    seen_block: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct Pool {
+    by_hash: HashMap<B256, Entry>,
+    by_nonce: HashMap<(Address, u64), B256>,
+    block: Option<u64>,
+}
+
+impl Pool {
+    fn remove(&mut self, hash: &B256) -> Option<Entry> {
+        let entry = self.by_hash.remove(hash)?;
+        let key = (entry.tx.from, entry.tx.nonce());
+        if self.by_nonce.get(&key) == Some(hash) {
+            self.by_nonce.remove(&key);
+        }
+        Some(entry)
+    }
+}
+
+/// Pending transactions seen on any feed, keyed by hash and by sender and nonce.
+///
+/// Every feed inserts what it receives, so a transaction is only handled once. Call
+/// `on_block` with every new block, or run `track_blocks`, to learn which transactions
+/// were mined, replaced or dropped. Subscribers receive a `PendingEvent` for each.
+#[derive(Debug)]
+pub struct PendingPool {
+    pool: Mutex<Pool>,
+    max_age: u64,
+    events: broadcast::Sender<PendingEvent>,
+}
+
+impl Default for PendingPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_AGE)
+    }
+}
+
+impl PendingPool {
+    /// Create a pool dropping transactions still pending `max_age` blocks after they were seen.
+    pub fn new(max_age: u64) -> Self {
+        Self {
+            pool: Mutex::default(),
+            max_age,
+            events: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+
+    fn pool(&self) -> MutexGuard<'_, Pool> {
+        self.pool.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn emit(&self, events: &[PendingEvent]) {
+        for event in events {
+            // Fails only without subscribers
+            let _ = self.events.send(event.clone());
+        }
+    }
+
+    /// Receive the events of every transaction from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<PendingEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn contains(&self, hash: &B256) -> bool {
+        self.pool().by_hash.contains_key(hash)
+    }
+
+    pub fn get(&self, hash: &B256) -> Option<Transaction> {
+        self.pool().by_hash.get(hash).map(|entry| entry.tx.clone())
+    }
+
+    pub fn len(&self) -> usize {
+        self.pool().by_hash.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Track a pending transaction, replacing any tracked one with the same sender and nonce.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `false` if the transaction is already tracked, or pays less than the
+    ///   tracked transaction it would replace, which nodes would reject.
+    pub fn insert(&self, tx: Transaction) -> bool {
+        let hash = *tx.inner.tx_hash();
+        let key = (tx.from, tx.nonce());
+        let mut events = Vec::new();
+
+        {
+            let mut pool = self.pool();
+            if pool.by_hash.contains_key(&hash) {
+                return false;
+            }
+
+            if let Some(previous) = pool.by_nonce.get(&key).copied() {
+                let previous_fee = pool.by_hash[&previous].tx.max_fee_per_gas();
+                if tx.max_fee_per_gas() < previous_fee {
+                    return false;
+                }
+                pool.remove(&previous);
+                events.push(PendingEvent::Replaced {
+                    hash: previous,
+                    by: hash,
+                });
+            }
+
+            let seen_block = pool.block;
+            pool.by_nonce.insert(key, hash);
+            pool.by_hash.insert(hash, Entry { tx, seen_block });
+            events.push(PendingEvent::Pending(hash));
+        }
+
+        self.emit(&events);
+        true
+    }
+
+    /// Settle the transactions mined in a block and drop those pending for too long.
+    ///
+    /// # Arguments
+    ///
+    /// * `number` - The block number.
+    /// * `mined` - The transactions of the block.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<PendingEvent>` - The events emitted, in order.
+    pub fn on_block<'a>(
+        &self,
+        number: u64,
+        mined: impl IntoIterator<Item = &'a Transaction>,
+    ) -> Vec<PendingEvent> {
+        let mut events = Vec::new();
+
+        {
+            let mut pool = self.pool();
+            pool.block = Some(pool.block.map_or(number, |block| block.max(number)));
+
+            for tx in mined {
+                let hash = *tx.inner.tx_hash();
+                let Some(pending) = pool.by_nonce.get(&(tx.from, tx.nonce())).copied() else {
+                    continue;
+                };
+
+                pool.remove(&pending);
+                events.push(if pending == hash {
+                    PendingEvent::Included {
+                        hash,
+                        block: number,
+                    }
+                } else {
+                    PendingEvent::Replaced {
+                        hash: pending,
+                        by: hash,
+                    }
+                });
+            }
+
+            let mut expired = Vec::new();
+            for (hash, entry) in pool.by_hash.iter_mut() {
+                let seen_block = *entry.seen_block.get_or_insert(number);
+                if number.saturating_sub(seen_block) >= self.max_age {
+                    expired.push(*hash);
+                }
+            }
+            for hash in expired {
+                pool.remove(&hash);
+                events.push(PendingEvent::Dropped(hash));
+            }
+        }
+
+        self.emit(&events);
+        events
+    }
+
+    /// Feed every new block to `on_block` until shutdown is signalled.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - A reference to the provider.
+    /// * `poll_interval` - How often to poll for a new block.
+    /// * `shutdown_config` - Stops tracking once shutdown is signalled.
+    pub async fn track_blocks<T, P>(
+        &self,
+        provider: &P,
+        poll_interval: Duration,
+        shutdown_config: ShutdownConfig,
+    ) -> Result<()>
+    where
+        T: Transport + Clone,
+        P: Provider<T>,
+    {
+        let mut last = None;
+
+        while !shutdown_config.is_shutdown() {
+            let latest = provider
+                .get_block_number()
+                .await
+                .map_err(|e| eyre!("GET_BLOCK_NUMBER failed: {:?}", e))?;
+
+            for number in last.map_or(latest, |last: u64| last + 1)..=latest {
+                let block = provider
+                    .get_block_by_number(
+                        BlockNumberOrTag::Number(number),
+                        BlockTransactionsKind::Full,
+                    )
+                    .await
+                    .map_err(|e| eyre!("GET_BLOCK_BY_NUMBER failed: {:?}", e))?
+                    .ok_or_else(|| eyre!("Block not found"))?;
+                self.on_block(number, block.transactions.txns());
+                last = Some(number);
+            }
+
+            tokio::time::sleep(poll_interval).await;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        network::TransactionBuilder, primitives::U256, rpc::types::TransactionRequest,
+        signers::local::PrivateKeySigner,
+    };
+
+    use crate::utils::testtx;
+
+    use super::*;
+
+    /// A transfer of `signer` with the given nonce and max fee.
+    async fn signed(signer: &PrivateKeySigner, nonce: u64, max_fee: u128) -> Transaction {
+        let request = TransactionRequest::default()
+            .with_to(Address::repeat_byte(0x01))
+            .with_value(U256::from(max_fee))
+            .with_nonce(nonce)
+            .with_chain_id(1)
+            .with_gas_limit(21_000)
+            .with_max_fee_per_gas(max_fee)
+            .with_max_priority_fee_per_gas(1);
+        testtx::sign_pending(signer, request).await
+    }
+
+    fn hash(tx: &Transaction) -> B256 {
+        *tx.inner.tx_hash()
+    }
+
+    #[tokio::test]
+    async fn test_dedup_and_replace() {
+        let signer = PrivateKeySigner::random();
+        let pool = PendingPool::default();
+        let mut events = pool.subscribe();
+
+        let tx = signed(&signer, 0, 50).await;
+        assert!(pool.insert(tx.clone()));
+        // The same transaction from a second feed
+        assert!(!pool.insert(tx.clone()));
+        assert_eq!(
+            events.recv().await.unwrap(),
+            PendingEvent::Pending(hash(&tx))
+        );
+
+        // A cheaper transaction with the same nonce cannot replace it
+        assert!(!pool.insert(signed(&signer, 0, 40).await));
+
+        let replacement = signed(&signer, 0, 60).await;
+        assert!(pool.insert(replacement.clone()));
+        assert_eq!(
+            events.recv().await.unwrap(),
+            PendingEvent::Replaced {
+                hash: hash(&tx),
+                by: hash(&replacement),
+            }
+        );
+        assert_eq!(
+            events.recv().await.unwrap(),
+            PendingEvent::Pending(hash(&replacement))
+        );
+
+        assert!(!pool.contains(&hash(&tx)));
+        assert_eq!(pool.get(&hash(&replacement)), Some(replacement));
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_on_block() {
+        let signer = PrivateKeySigner::random();
+        let pool = PendingPool::new(2);
+
+        let included = signed(&signer, 0, 50).await;
+        let replaced = signed(&signer, 1, 50).await;
+        let dropped = signed(&signer, 2, 50).await;
+        for tx in [&included, &replaced, &dropped] {
+            pool.insert(tx.clone());
+        }
+
+        // The second nonce was mined with a transaction the feeds never saw
+        let unseen = signed(&signer, 1, 70).await;
+        let other = signed(&PrivateKeySigner::random(), 0, 50).await;
+        assert_eq!(
+            pool.on_block(100, [&included, &unseen, &other]),
+            vec![
+                PendingEvent::Included {
+                    hash: hash(&included),
+                    block: 100,
+                },
+                PendingEvent::Replaced {
+                    hash: hash(&replaced),
+                    by: hash(&unseen),
+                },
+            ]
+        );
+        assert_eq!(pool.len(), 1);
+
+        // Transactions seen before the first block count their age from it
+        assert!(pool.on_block(101, []).is_empty());
+        assert_eq!(
+            pool.on_block(102, []),
+            vec![PendingEvent::Dropped(hash(&dropped))]
+        );
+        assert!(pool.is_empty());
+    }
+}
@@ -1,4 +1,9 @@
-use std::sync::{atomic, Arc};
+use std::{
+    sync::{atomic, Arc},
+    time::Duration,
+};
+
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 #[derive(Clone)]
 pub struct ShutdownConfig {
@@ -19,6 +24,13 @@ impl ShutdownConfig {
         self.flag.load(atomic::Ordering::SeqCst)
     }
 
+    /// Wait until shutdown is signalled, polling the flag like the watcher loop does.
+    pub async fn wait_for_shutdown(&self) {
+        while !self.is_shutdown() {
+            tokio::time::sleep(SHUTDOWN_POLL_INTERVAL).await;
+        }
+    }
+
     pub fn is_finished(&self) -> bool {
         self.result.load(atomic::Ordering::SeqCst)
     }
@@ -1,49 +1,142 @@
-use alloy::consensus::TxType;
-use alloy::primitives::{Address, Bytes, TxKind};
+use alloy::consensus::Transaction as _;
+use alloy::primitives::{Address, Bytes, FixedBytes, U256};
 use alloy::rpc::types::Transaction;
 
+use super::swapfilter::SwapFilter;
+
 pub trait ShallowFilter {
     fn filter(&self, tx: &Transaction) -> bool;
 }
 
+/// Filters on the fields every transaction type shares, so legacy, EIP-2930,
+/// EIP-1559, EIP-4844 and EIP-7702 transactions are matched alike.
 #[derive(Clone, Debug)]
 pub enum ShallowFilterType {
     Recipient(Address),
+    Sender(Address),
+    /// Exact match of the full calldata
     CallData(Bytes),
+    /// Match of the 4 byte function selector the calldata starts with
+    Selector(FixedBytes<4>),
+    /// Transactions sending at least this much ETH
+    MinValue(U256),
+    /// Transactions willing to pay at least this gas price, the max fee for EIP-1559 ones
+    MinGasPrice(u128),
+    ContractCreation,
+    /// Calls to known routers making a swap the filter accepts
+    Swap(SwapFilter),
+    /// Matches if any of the filters match
+    Any(Vec<ShallowFilterType>),
+    /// Matches if all of the filters match
+    All(Vec<ShallowFilterType>),
+    Not(Box<ShallowFilterType>),
     None,
 }
 
 impl ShallowFilter for ShallowFilterType {
     fn filter(&self, tx: &Transaction) -> bool {
         match self {
-            ShallowFilterType::Recipient(addr) => filter_by_addr(tx, addr),
+            ShallowFilterType::Recipient(addr) => tx.to() == Some(*addr),
+            ShallowFilterType::Sender(addr) => tx.from == *addr,
+            ShallowFilterType::CallData(data) => tx.input() == data,
+            ShallowFilterType::Selector(selector) => tx.input().starts_with(selector.as_slice()),
+            ShallowFilterType::MinValue(value) => tx.value() >= *value,
+            ShallowFilterType::MinGasPrice(price) => tx.max_fee_per_gas() >= *price,
+            ShallowFilterType::ContractCreation => tx.kind().is_create(),
+            ShallowFilterType::Swap(filter) => filter.filter(tx),
+            ShallowFilterType::Any(filters) => filters.iter().any(|filter| filter.filter(tx)),
+            ShallowFilterType::All(filters) => filters.iter().all(|filter| filter.filter(tx)),
+            ShallowFilterType::Not(filter) => !filter.filter(tx),
             ShallowFilterType::None => true,
-            ShallowFilterType::CallData(data) => filter_by_data(tx, data),
         }
     }
 }
 
-fn filter_by_addr(tx: &Transaction, expected: &Address) -> bool {
-    match tx.inner.tx_type() {
-        TxType::Eip1559 => {
-            let tx = tx.inner.as_eip1559().unwrap();
-            let to = tx.clone().strip_signature().to;
-            match to {
-                TxKind::Call(addr) => addr == *expected,
-                TxKind::Create => false,
-            }
-        }
-        _ => false,
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        consensus::TxType, eips::eip2930::AccessList, network::TransactionBuilder,
+        primitives::TxKind, rpc::types::TransactionRequest, signers::local::PrivateKeySigner,
+    };
+
+    use crate::utils::testtx;
+
+    use super::*;
+
+    const SELECTOR: [u8; 4] = [0x41, 0x4b, 0xf3, 0x89];
+
+    /// A transaction of `tx_type` calling `to` with `SELECTOR` and one argument.
+    async fn signed(signer: &PrivateKeySigner, tx_type: TxType, to: TxKind) -> Transaction {
+        let request = TransactionRequest::default()
+            .with_kind(to)
+            .with_input([SELECTOR.as_slice(), &[0x01; 32]].concat())
+            .with_value(U256::from(1000))
+            .with_nonce(0)
+            .with_chain_id(1)
+            .with_gas_limit(100_000);
+
+        let request = match tx_type {
+            TxType::Legacy => request.with_gas_price(50),
+            TxType::Eip2930 => request
+                .with_gas_price(50)
+                .with_access_list(AccessList::default()),
+            _ => request
+                .with_max_fee_per_gas(50)
+                .with_max_priority_fee_per_gas(1),
+        };
+        let tx = testtx::sign_pending(signer, request).await;
+        assert_eq!(tx.inner.tx_type(), tx_type);
+        tx
     }
-}
 
-fn filter_by_data(tx: &Transaction, expected: &Bytes) -> bool {
-    match tx.inner.tx_type() {
-        TxType::Eip1559 => {
-            let tx = tx.inner.as_eip1559().unwrap();
-            let data = tx.clone().strip_signature().input;
-            data == *expected
+    #[tokio::test]
+    async fn test_filters_match_every_tx_type() {
+        let signer = PrivateKeySigner::random();
+        let router = Address::repeat_byte(0x01);
+
+        for tx_type in [TxType::Legacy, TxType::Eip2930, TxType::Eip1559] {
+            let tx = signed(&signer, tx_type, TxKind::Call(router)).await;
+
+            assert!(ShallowFilterType::Recipient(router).filter(&tx));
+            assert!(!ShallowFilterType::Recipient(Address::ZERO).filter(&tx));
+            assert!(ShallowFilterType::Sender(signer.address()).filter(&tx));
+            assert!(ShallowFilterType::CallData(tx.input().clone()).filter(&tx));
+            assert!(!ShallowFilterType::CallData(Bytes::from(SELECTOR)).filter(&tx));
+            assert!(ShallowFilterType::Selector(FixedBytes(SELECTOR)).filter(&tx));
+            assert!(!ShallowFilterType::Selector(FixedBytes([0; 4])).filter(&tx));
+            assert!(ShallowFilterType::MinValue(U256::from(1000)).filter(&tx));
+            assert!(!ShallowFilterType::MinValue(U256::from(1001)).filter(&tx));
+            assert!(ShallowFilterType::MinGasPrice(50).filter(&tx));
+            assert!(!ShallowFilterType::MinGasPrice(51).filter(&tx));
+            assert!(!ShallowFilterType::ContractCreation.filter(&tx));
         }
-        _ => false,
+
+        let create = signed(&signer, TxType::Legacy, TxKind::Create).await;
+        assert!(ShallowFilterType::ContractCreation.filter(&create));
+        assert!(!ShallowFilterType::Recipient(router).filter(&create));
+    }
+
+    #[tokio::test]
+    async fn test_composite_filters() {
+        let signer = PrivateKeySigner::random();
+        let router = Address::repeat_byte(0x01);
+        let tx = signed(&signer, TxType::Eip1559, TxKind::Call(router)).await;
+
+        let to_router = ShallowFilterType::Recipient(router);
+        let to_other = ShallowFilterType::Recipient(Address::ZERO);
+
+        assert!(ShallowFilterType::Any(vec![to_other.clone(), to_router.clone()]).filter(&tx));
+        assert!(!ShallowFilterType::All(vec![to_other.clone(), to_router.clone()]).filter(&tx));
+        assert!(ShallowFilterType::All(vec![
+            to_router.clone(),
+            ShallowFilterType::Selector(FixedBytes(SELECTOR)),
+        ])
+        .filter(&tx));
+        assert!(ShallowFilterType::Not(Box::new(to_other)).filter(&tx));
+
+        // Empty composites follow `Iterator::any` and `Iterator::all`
+        assert!(!ShallowFilterType::Any(Vec::new()).filter(&tx));
+        assert!(ShallowFilterType::All(Vec::new()).filter(&tx));
+        assert!(ShallowFilterType::None.filter(&tx));
     }
 }
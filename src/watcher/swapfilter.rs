@@ -0,0 +1,754 @@
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use alloy::{
+    consensus::Transaction as _,
+    primitives::{Address, Bytes, U256},
+    rpc::types::Transaction,
+    sol_types::{SolInterface, SolValue},
+};
+
+use crate::{
+    dex::protocol::V2_FEE,
+    interfaces::{
+        router02::IUniswapV2Router::IUniswapV2RouterCalls,
+        swaprouter::{
+            ISwapRouter::ISwapRouterCalls, ISwapRouter02::ISwapRouter02Calls,
+            IUniversalRouter::IUniversalRouterCalls,
+        },
+    },
+    utils::addresses,
+};
+
+use super::subscribefilter::ShallowFilter;
+
+// Universal Router commands, the upper two bits of a command are flags
+const COMMAND_TYPE_MASK: u8 = 0x3f;
+const V3_SWAP_EXACT_IN: u8 = 0x00;
+const V3_SWAP_EXACT_OUT: u8 = 0x01;
+const V2_SWAP_EXACT_IN: u8 = 0x08;
+const V2_SWAP_EXACT_OUT: u8 = 0x09;
+
+/// Universal Router amount standing for its whole balance, i.e. the ETH sent along once wrapped
+const CONTRACT_BALANCE: U256 = U256::from_limbs([0, 0, 0, 1 << 63]);
+
+const ADDRESS_SIZE: usize = 20;
+const FEE_SIZE: usize = 3;
+
+/// The calldata layouts of the routers swaps are decoded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouterKind {
+    UniswapV2,
+    SwapRouter,
+    SwapRouter02,
+    UniversalRouter,
+}
+
+/// A swap decoded from a router call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouterSwap {
+    /// Tokens from the one sold to the one bought
+    pub path: Vec<Address>,
+    /// Fee tier of every hop, V2 hops charge `V2_FEE`
+    pub fees: Vec<u32>,
+    /// Amount sold, the most the sender is willing to sell for exact output swaps
+    pub amount_in: U256,
+    pub exact_input: bool,
+    /// Unix time after which the swap reverts, if the call has one
+    pub deadline: Option<U256>,
+}
+
+impl RouterSwap {
+    pub fn token_in(&self) -> Option<Address> {
+        self.path.first().copied()
+    }
+
+    pub fn token_out(&self) -> Option<Address> {
+        self.path.last().copied()
+    }
+}
+
+/// Decode the swaps a router call makes.
+///
+/// # Arguments
+///
+/// * `router` - The layout of the router's calldata.
+/// * `input` - The calldata.
+/// * `value` - The ETH sent along, which is the amount sold when swapping from ETH.
+///
+/// # Returns
+///
+/// * `Vec<RouterSwap>` - The swaps in execution order, empty if the call swaps nothing.
+pub fn decode_swaps(router: RouterKind, input: &[u8], value: U256) -> Vec<RouterSwap> {
+    match router {
+        RouterKind::UniswapV2 => decode_v2_router(input, value),
+        RouterKind::SwapRouter => decode_swap_router(input),
+        RouterKind::SwapRouter02 => decode_swap_router02(input, None),
+        RouterKind::UniversalRouter => decode_universal_router(input, value),
+    }
+}
+
+fn decode_v2_router(input: &[u8], value: U256) -> Vec<RouterSwap> {
+    use IUniswapV2RouterCalls as Call;
+
+    let Ok(call) = Call::abi_decode(input, true) else {
+        return Vec::new();
+    };
+    let (path, amount_in, exact_input, deadline) = match call {
+        Call::swapExactTokensForTokens(c) => (c.path, c.amountIn, true, c.deadline),
+        Call::swapTokensForExactTokens(c) => (c.path, c.amountInMax, false, c.deadline),
+        Call::swapExactTokensForETH(c) => (c.path, c.amountIn, true, c.deadline),
+        Call::swapTokensForExactETH(c) => (c.path, c.amountInMax, false, c.deadline),
+        Call::swapExactTokensForTokensSupportingFeeOnTransferTokens(c) => {
+            (c.path, c.amountIn, true, c.deadline)
+        }
+        Call::swapExactTokensForETHSupportingFeeOnTransferTokens(c) => {
+            (c.path, c.amountIn, true, c.deadline)
+        }
+        Call::swapExactETHForTokens(c) => (c.path, value, true, c.deadline),
+        Call::swapExactETHForTokensSupportingFeeOnTransferTokens(c) => {
+            (c.path, value, true, c.deadline)
+        }
+        // The ETH not needed for the exact output is refunded
+        Call::swapETHForExactTokens(c) => (c.path, value, false, c.deadline),
+        _ => return Vec::new(),
+    };
+
+    vec![v2_swap(path, amount_in, exact_input, Some(deadline))]
+}
+
+fn decode_swap_router(input: &[u8]) -> Vec<RouterSwap> {
+    use ISwapRouterCalls as Call;
+
+    let Ok(call) = Call::abi_decode(input, true) else {
+        return Vec::new();
+    };
+    match call {
+        Call::exactInputSingle(c) => vec![RouterSwap {
+            path: vec![c.params.tokenIn, c.params.tokenOut],
+            fees: vec![c.params.fee.to()],
+            amount_in: c.params.amountIn,
+            exact_input: true,
+            deadline: Some(c.params.deadline),
+        }],
+        Call::exactOutputSingle(c) => vec![RouterSwap {
+            path: vec![c.params.tokenIn, c.params.tokenOut],
+            fees: vec![c.params.fee.to()],
+            amount_in: c.params.amountInMaximum,
+            exact_input: false,
+            deadline: Some(c.params.deadline),
+        }],
+        Call::exactInput(c) => v3_swap(
+            &c.params.path,
+            c.params.amountIn,
+            true,
+            Some(c.params.deadline),
+        )
+        .into_iter()
+        .collect(),
+        Call::exactOutput(c) => v3_swap(
+            &c.params.path,
+            c.params.amountInMaximum,
+            false,
+            Some(c.params.deadline),
+        )
+        .into_iter()
+        .collect(),
+        Call::multicall(c) => c
+            .data
+            .iter()
+            .flat_map(|data| decode_swap_router(data))
+            .collect(),
+    }
+}
+
+/// SwapRouter02 calls carry no deadline of their own, they inherit the one of the
+/// `multicall` wrapping them.
+fn decode_swap_router02(input: &[u8], deadline: Option<U256>) -> Vec<RouterSwap> {
+    use ISwapRouter02Calls as Call;
+
+    let Ok(call) = Call::abi_decode(input, true) else {
+        return Vec::new();
+    };
+    let multicall = |data: &[Bytes], deadline: Option<U256>| -> Vec<RouterSwap> {
+        data.iter()
+            .flat_map(|data| decode_swap_router02(data, deadline))
+            .collect()
+    };
+    match call {
+        Call::exactInputSingle(c) => vec![RouterSwap {
+            path: vec![c.params.tokenIn, c.params.tokenOut],
+            fees: vec![c.params.fee.to()],
+            amount_in: c.params.amountIn,
+            exact_input: true,
+            deadline,
+        }],
+        Call::exactOutputSingle(c) => vec![RouterSwap {
+            path: vec![c.params.tokenIn, c.params.tokenOut],
+            fees: vec![c.params.fee.to()],
+            amount_in: c.params.amountInMaximum,
+            exact_input: false,
+            deadline,
+        }],
+        Call::exactInput(c) => v3_swap(&c.params.path, c.params.amountIn, true, deadline)
+            .into_iter()
+            .collect(),
+        Call::exactOutput(c) => v3_swap(&c.params.path, c.params.amountInMaximum, false, deadline)
+            .into_iter()
+            .collect(),
+        Call::swapExactTokensForTokens(c) => vec![v2_swap(c.path, c.amountIn, true, deadline)],
+        Call::swapTokensForExactTokens(c) => {
+            vec![v2_swap(c.path, c.amountInMax, false, deadline)]
+        }
+        Call::multicall_0(c) => multicall(&c.data, Some(c.deadline)),
+        Call::multicall_1(c) => multicall(&c.data, deadline),
+        Call::multicall_2(c) => multicall(&c.data, deadline),
+    }
+}
+
+fn decode_universal_router(input: &[u8], value: U256) -> Vec<RouterSwap> {
+    let (commands, inputs, deadline) = match IUniversalRouterCalls::abi_decode(input, true) {
+        Ok(IUniversalRouterCalls::execute_0(c)) => (c.commands, c.inputs, Some(c.deadline)),
+        Ok(IUniversalRouterCalls::execute_1(c)) => (c.commands, c.inputs, None),
+        Err(_) => return Vec::new(),
+    };
+
+    commands
+        .iter()
+        .zip(inputs.iter())
+        .filter_map(|(command, input)| {
+            decode_command(command & COMMAND_TYPE_MASK, input, value, deadline)
+        })
+        .collect()
+}
+
+/// Decode the swap of a Universal Router command, `None` for the commands which do not swap.
+fn decode_command(
+    command: u8,
+    input: &[u8],
+    value: U256,
+    deadline: Option<U256>,
+) -> Option<RouterSwap> {
+    let amount = |amount: U256| {
+        if amount == CONTRACT_BALANCE {
+            value
+        } else {
+            amount
+        }
+    };
+
+    match command {
+        V3_SWAP_EXACT_IN | V3_SWAP_EXACT_OUT => {
+            // (recipient, amountIn or amountOut, amountOutMin or amountInMax, path, payerIsUser)
+            let (_, amount_a, amount_b, path, _) =
+                <(Address, U256, U256, Bytes, bool)>::abi_decode_params(input, true).ok()?;
+            if command == V3_SWAP_EXACT_IN {
+                v3_swap(&path, amount(amount_a), true, deadline)
+            } else {
+                v3_swap(&path, amount_b, false, deadline)
+            }
+        }
+        V2_SWAP_EXACT_IN | V2_SWAP_EXACT_OUT => {
+            let (_, amount_a, amount_b, path, _) =
+                <(Address, U256, U256, Vec<Address>, bool)>::abi_decode_params(input, true).ok()?;
+            if command == V2_SWAP_EXACT_IN {
+                Some(v2_swap(path, amount(amount_a), true, deadline))
+            } else {
+                Some(v2_swap(path, amount_b, false, deadline))
+            }
+        }
+        _ => None,
+    }
+}
+
+fn v2_swap(
+    path: Vec<Address>,
+    amount_in: U256,
+    exact_input: bool,
+    deadline: Option<U256>,
+) -> RouterSwap {
+    RouterSwap {
+        fees: vec![V2_FEE; path.len().saturating_sub(1)],
+        path,
+        amount_in,
+        exact_input,
+        deadline,
+    }
+}
+
+/// A swap along an encoded V3 path, which exact output swaps encode from the token bought.
+fn v3_swap(
+    path: &[u8],
+    amount_in: U256,
+    exact_input: bool,
+    deadline: Option<U256>,
+) -> Option<RouterSwap> {
+    let (mut path, mut fees) = decode_path(path)?;
+    if !exact_input {
+        path.reverse();
+        fees.reverse();
+    }
+
+    Some(RouterSwap {
+        path,
+        fees,
+        amount_in,
+        exact_input,
+        deadline,
+    })
+}
+
+/// Split a V3 path, a token followed by a 3 byte fee and the next token for every hop.
+pub fn decode_path(path: &[u8]) -> Option<(Vec<Address>, Vec<u32>)> {
+    let hop_size = FEE_SIZE + ADDRESS_SIZE;
+    if path.len() < ADDRESS_SIZE + hop_size || (path.len() - ADDRESS_SIZE) % hop_size != 0 {
+        return None;
+    }
+
+    let mut tokens = vec![Address::from_slice(&path[..ADDRESS_SIZE])];
+    let mut fees = Vec::new();
+    for hop in path[ADDRESS_SIZE..].chunks_exact(hop_size) {
+        fees.push(u32::from_be_bytes([0, hop[0], hop[1], hop[2]]));
+        tokens.push(Address::from_slice(&hop[FEE_SIZE..]));
+    }
+
+    Some((tokens, fees))
+}
+
+/// USD price of a token, to value the amount a swap sells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenPrice {
+    /// Price of a whole token in USD with 8 decimals, like the oracle reports it
+    pub usd: U256,
+    pub decimals: u8,
+}
+
+impl TokenPrice {
+    /// USD value of `amount`, with 8 decimals.
+    pub fn value(&self, amount: U256) -> U256 {
+        amount.saturating_mul(self.usd) / U256::from(10).pow(U256::from(self.decimals))
+    }
+}
+
+/// Matches transactions to known routers by the swaps their calldata decodes to, so
+/// that candidates are screened before any simulation is spent on them.
+///
+/// A transaction matches if any of its swaps meets every criterion set.
+#[derive(Debug, Clone)]
+pub struct SwapFilter {
+    routers: HashMap<Address, RouterKind>,
+    token: Option<Address>,
+    fee: Option<u32>,
+    min_amount_in_usd: Option<(U256, HashMap<Address, TokenPrice>)>,
+    future_deadline: bool,
+}
+
+impl Default for SwapFilter {
+    /// The Uniswap routers deployed on mainnet.
+    fn default() -> Self {
+        Self::new(HashMap::from([
+            (*addresses::UNISWAP_V2_ROUTER, RouterKind::UniswapV2),
+            (*addresses::UNISWAP_V3_ROUTER, RouterKind::SwapRouter),
+            (*addresses::SWAP_ROUTER_02, RouterKind::SwapRouter02),
+            (*addresses::UNIVERSAL_ROUTER, RouterKind::UniversalRouter),
+        ]))
+    }
+}
+
+impl SwapFilter {
+    pub fn new(routers: HashMap<Address, RouterKind>) -> Self {
+        Self {
+            routers,
+            token: None,
+            fee: None,
+            min_amount_in_usd: None,
+            future_deadline: false,
+        }
+    }
+
+    pub fn with_router(mut self, router: Address, kind: RouterKind) -> Self {
+        self.routers.insert(router, kind);
+        self
+    }
+
+    /// Only match swaps whose path contains `token`.
+    pub fn with_token(mut self, token: Address) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Only match swaps through a pool of the fee tier, in hundredths of a bip.
+    pub fn with_fee(mut self, fee: u32) -> Self {
+        self.fee = Some(fee);
+        self
+    }
+
+    /// Only match swaps selling at least `usd`, with 8 decimals. Swaps selling a token
+    /// missing from `prices` never match.
+    pub fn with_min_amount_in_usd(
+        mut self,
+        usd: U256,
+        prices: HashMap<Address, TokenPrice>,
+    ) -> Self {
+        self.min_amount_in_usd = Some((usd, prices));
+        self
+    }
+
+    /// Only match swaps which have not expired, swaps without a deadline always do.
+    pub fn with_future_deadline(mut self) -> Self {
+        self.future_deadline = true;
+        self
+    }
+
+    /// Decode the swaps of a transaction, empty if it is not sent to a known router.
+    pub fn decode(&self, tx: &Transaction) -> Vec<RouterSwap> {
+        match tx.to().and_then(|to| self.routers.get(&to)) {
+            Some(kind) => decode_swaps(*kind, tx.input(), tx.value()),
+            None => Vec::new(),
+        }
+    }
+
+    /// Whether a swap meets every criterion at unix time `now`.
+    pub fn matches(&self, swap: &RouterSwap, now: u64) -> bool {
+        if self.token.is_some_and(|token| !swap.path.contains(&token)) {
+            return false;
+        }
+        if self.fee.is_some_and(|fee| !swap.fees.contains(&fee)) {
+            return false;
+        }
+        if let Some((min, prices)) = &self.min_amount_in_usd {
+            let value = swap
+                .token_in()
+                .and_then(|token| prices.get(&token))
+                .map(|price| price.value(swap.amount_in));
+            if !value.is_some_and(|value| value >= *min) {
+                return false;
+            }
+        }
+        if self.future_deadline
+            && swap
+                .deadline
+                .is_some_and(|deadline| deadline < U256::from(now))
+        {
+            return false;
+        }
+
+        true
+    }
+}
+
+impl ShallowFilter for SwapFilter {
+    fn filter(&self, tx: &Transaction) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+
+        self.decode(tx).iter().any(|swap| self.matches(swap, now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        network::TransactionBuilder,
+        primitives::aliases::{U160, U24},
+        rpc::types::TransactionRequest,
+        signers::local::PrivateKeySigner,
+        sol_types::SolCall,
+    };
+
+    use crate::{
+        interfaces::{
+            router02::IUniswapV2Router,
+            swaprouter::{ISwapRouter, ISwapRouter02, IUniversalRouter},
+        },
+        utils::testtx,
+    };
+
+    use super::*;
+
+    const DEADLINE: u64 = 1_700_000_000;
+
+    fn token(byte: u8) -> Address {
+        Address::repeat_byte(byte)
+    }
+
+    fn encode_path(tokens: &[Address], fees: &[u32]) -> Bytes {
+        let mut path = tokens[0].to_vec();
+        for (fee, token) in fees.iter().zip(&tokens[1..]) {
+            path.extend_from_slice(&fee.to_be_bytes()[1..]);
+            path.extend_from_slice(token.as_slice());
+        }
+        path.into()
+    }
+
+    #[test]
+    fn test_decode_path() {
+        let path = encode_path(&[token(1), token(2), token(3)], &[500, 3000]);
+
+        assert_eq!(
+            decode_path(&path),
+            Some((vec![token(1), token(2), token(3)], vec![500, 3000]))
+        );
+        assert_eq!(decode_path(&path[..ADDRESS_SIZE]), None);
+        assert_eq!(decode_path(&path[..path.len() - 1]), None);
+    }
+
+    #[test]
+    fn test_decode_v2_router() {
+        let input = IUniswapV2Router::swapExactTokensForTokensCall {
+            amountIn: U256::from(100),
+            amountOutMin: U256::ZERO,
+            path: vec![token(1), token(2)],
+            to: token(9),
+            deadline: U256::from(DEADLINE),
+        }
+        .abi_encode();
+        assert_eq!(
+            decode_swaps(RouterKind::UniswapV2, &input, U256::ZERO),
+            vec![RouterSwap {
+                path: vec![token(1), token(2)],
+                fees: vec![V2_FEE],
+                amount_in: U256::from(100),
+                exact_input: true,
+                deadline: Some(U256::from(DEADLINE)),
+            }]
+        );
+
+        // Swaps from ETH sell the value sent
+        let input = IUniswapV2Router::swapETHForExactTokensCall {
+            amountOut: U256::from(5),
+            path: vec![token(1), token(2), token(3)],
+            to: token(9),
+            deadline: U256::from(DEADLINE),
+        }
+        .abi_encode();
+        let swaps = decode_swaps(RouterKind::UniswapV2, &input, U256::from(42));
+        assert_eq!(swaps[0].amount_in, U256::from(42));
+        assert_eq!(swaps[0].fees, vec![V2_FEE, V2_FEE]);
+        assert!(!swaps[0].exact_input);
+
+        let input = IUniswapV2Router::WETHCall {}.abi_encode();
+        assert!(decode_swaps(RouterKind::UniswapV2, &input, U256::ZERO).is_empty());
+        assert!(decode_swaps(RouterKind::UniswapV2, &[0xde, 0xad], U256::ZERO).is_empty());
+    }
+
+    #[test]
+    fn test_decode_swap_router_multicall() {
+        let single = ISwapRouter::exactInputSingleCall {
+            params: ISwapRouter::ExactInputSingleParams {
+                tokenIn: token(1),
+                tokenOut: token(2),
+                fee: U24::from(500),
+                recipient: token(9),
+                deadline: U256::from(DEADLINE),
+                amountIn: U256::from(100),
+                amountOutMinimum: U256::ZERO,
+                sqrtPriceLimitX96: U160::ZERO,
+            },
+        }
+        .abi_encode();
+        // Exact output paths start from the token bought
+        let output = ISwapRouter::exactOutputCall {
+            params: ISwapRouter::ExactOutputParams {
+                path: encode_path(&[token(3), token(2)], &[10000]),
+                recipient: token(9),
+                deadline: U256::from(DEADLINE),
+                amountOut: U256::from(7),
+                amountInMaximum: U256::from(200),
+            },
+        }
+        .abi_encode();
+        let input = ISwapRouter::multicallCall {
+            data: vec![single.into(), output.into()],
+        }
+        .abi_encode();
+
+        let swaps = decode_swaps(RouterKind::SwapRouter, &input, U256::ZERO);
+        assert_eq!(swaps.len(), 2);
+        assert_eq!(swaps[0].path, vec![token(1), token(2)]);
+        assert_eq!(swaps[0].fees, vec![500]);
+        assert_eq!(
+            swaps[1],
+            RouterSwap {
+                path: vec![token(2), token(3)],
+                fees: vec![10000],
+                amount_in: U256::from(200),
+                exact_input: false,
+                deadline: Some(U256::from(DEADLINE)),
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_swap_router02_deadline() {
+        let v2 = ISwapRouter02::swapExactTokensForTokensCall {
+            amountIn: U256::from(100),
+            amountOutMin: U256::ZERO,
+            path: vec![token(1), token(2)],
+            to: token(9),
+        }
+        .abi_encode();
+        let v3 = ISwapRouter02::exactInputCall {
+            params: ISwapRouter02::ExactInputParams {
+                path: encode_path(&[token(2), token(3)], &[3000]),
+                recipient: token(9),
+                amountIn: U256::from(50),
+                amountOutMinimum: U256::ZERO,
+            },
+        }
+        .abi_encode();
+
+        assert_eq!(
+            decode_swaps(RouterKind::SwapRouter02, &v2, U256::ZERO)[0].deadline,
+            None
+        );
+
+        let input = ISwapRouter02::multicall_0Call {
+            deadline: U256::from(DEADLINE),
+            data: vec![v2.into(), v3.into()],
+        }
+        .abi_encode();
+        let swaps = decode_swaps(RouterKind::SwapRouter02, &input, U256::ZERO);
+        assert_eq!(swaps.len(), 2);
+        assert!(swaps
+            .iter()
+            .all(|swap| swap.deadline == Some(U256::from(DEADLINE))));
+        assert_eq!(swaps[1].path, vec![token(2), token(3)]);
+    }
+
+    #[test]
+    fn test_decode_universal_router() {
+        let v3 = (
+            token(9),
+            CONTRACT_BALANCE,
+            U256::ZERO,
+            encode_path(&[token(1), token(2)], &[500]),
+            false,
+        )
+            .abi_encode_params();
+        let v2 = (
+            token(9),
+            U256::from(10),
+            U256::from(300),
+            vec![token(2), token(3)],
+            true,
+        )
+            .abi_encode_params();
+        // WRAP_ETH, then both swaps with the allow revert flag on the second
+        let input = IUniversalRouter::execute_0Call {
+            commands: vec![0x0b, V3_SWAP_EXACT_IN, 0x80 | V2_SWAP_EXACT_OUT].into(),
+            inputs: vec![
+                (token(9), CONTRACT_BALANCE).abi_encode_params().into(),
+                v3.into(),
+                v2.into(),
+            ],
+            deadline: U256::from(DEADLINE),
+        }
+        .abi_encode();
+
+        let swaps = decode_swaps(RouterKind::UniversalRouter, &input, U256::from(1000));
+        assert_eq!(
+            swaps,
+            vec![
+                RouterSwap {
+                    path: vec![token(1), token(2)],
+                    fees: vec![500],
+                    amount_in: U256::from(1000),
+                    exact_input: true,
+                    deadline: Some(U256::from(DEADLINE)),
+                },
+                RouterSwap {
+                    path: vec![token(2), token(3)],
+                    fees: vec![V2_FEE],
+                    amount_in: U256::from(300),
+                    exact_input: false,
+                    deadline: Some(U256::from(DEADLINE)),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_matches() {
+        let swap = RouterSwap {
+            path: vec![token(1), token(2)],
+            fees: vec![500],
+            // 2 tokens with 6 decimals
+            amount_in: U256::from(2_000_000),
+            exact_input: true,
+            deadline: Some(U256::from(DEADLINE)),
+        };
+        // 3 USD per token
+        let prices = HashMap::from([(
+            token(1),
+            TokenPrice {
+                usd: U256::from(300_000_000),
+                decimals: 6,
+            },
+        )]);
+        let filter = SwapFilter::default();
+
+        assert!(filter.matches(&swap, 0));
+        assert!(filter.clone().with_token(token(2)).matches(&swap, 0));
+        assert!(!filter.clone().with_token(token(3)).matches(&swap, 0));
+        assert!(filter.clone().with_fee(500).matches(&swap, 0));
+        assert!(!filter.clone().with_fee(3000).matches(&swap, 0));
+        assert!(filter
+            .clone()
+            .with_min_amount_in_usd(U256::from(600_000_000), prices.clone())
+            .matches(&swap, 0));
+        assert!(!filter
+            .clone()
+            .with_min_amount_in_usd(U256::from(600_000_001), prices)
+            .matches(&swap, 0));
+        assert!(!filter
+            .clone()
+            .with_min_amount_in_usd(U256::ZERO, HashMap::new())
+            .matches(&swap, 0));
+        assert!(filter
+            .clone()
+            .with_future_deadline()
+            .matches(&swap, DEADLINE));
+        assert!(!filter
+            .clone()
+            .with_future_deadline()
+            .matches(&swap, DEADLINE + 1));
+    }
+
+    #[tokio::test]
+    async fn test_filter_transaction() {
+        let signer = PrivateKeySigner::random();
+        let router = token(0xaa);
+        let input = IUniswapV2Router::swapExactETHForTokensCall {
+            amountOutMin: U256::ZERO,
+            path: vec![token(1), token(2)],
+            to: signer.address(),
+            deadline: U256::MAX,
+        }
+        .abi_encode();
+
+        let request = TransactionRequest::default()
+            .with_to(router)
+            .with_input(input)
+            .with_value(U256::from(1000))
+            .with_nonce(0)
+            .with_chain_id(1)
+            .with_gas_limit(200_000)
+            .with_max_fee_per_gas(50)
+            .with_max_priority_fee_per_gas(1);
+        let tx = testtx::sign_pending(&signer, request).await;
+
+        let filter = SwapFilter::default().with_token(token(2));
+        assert!(!filter.filter(&tx));
+
+        let filter = filter
+            .with_router(router, RouterKind::UniswapV2)
+            .with_future_deadline();
+        assert!(filter.filter(&tx));
+        assert_eq!(filter.decode(&tx)[0].amount_in, U256::from(1000));
+        assert!(!filter.with_token(token(3)).filter(&tx));
+    }
+}